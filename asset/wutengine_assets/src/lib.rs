@@ -36,6 +36,15 @@ pub struct AssetRef<T> {
 }
 
 impl<T> AssetRef<T> {
+    /// Creates a new reference to the asset with the given ID, or an empty reference
+    #[inline]
+    pub const fn new(asset_id: Option<uuid::NonNilUuid>) -> Self {
+        Self {
+            asset_id,
+            _ph: PhantomData,
+        }
+    }
+
     /// Returns the referenced ID
     #[inline]
     pub fn get_id(&self) -> Option<uuid::NonNilUuid> {
@@ -72,6 +72,7 @@ impl<T: EditorWindow> Component for EditorWindowContainer<T> {
         let run_sys_config = SystemConfig {
             dependencies: &[],
             parallel_batch_size: Some(NonZeroU32::new(1).unwrap()),
+            ..Default::default()
         };
 
         manifest.add_system_with_config::<(&mut Self, &EguiWindowContainer)>(
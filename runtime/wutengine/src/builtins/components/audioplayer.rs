@@ -77,6 +77,16 @@ impl AudioPlayer {
         self.player_init = true;
     }
 
+    /// Stops the playback and releases the player
+    fn release(&mut self) {
+        if let Some(player) = self.player.take() {
+            player.stop();
+        }
+
+        self.player_init = false;
+        self.clip_init = false;
+    }
+
     fn add_clip(&mut self) {
         assert!(self.player_init, "Player must be initialized");
 
@@ -111,4 +121,12 @@ impl Component for AudioPlayer {
         Self: Sized,
     {
     }
+
+    fn on_remove(&mut self, _entity: crate::entity::Entity) {
+        self.release();
+    }
+
+    fn on_destroy(&mut self, _entity: crate::entity::Entity) {
+        self.release();
+    }
 }
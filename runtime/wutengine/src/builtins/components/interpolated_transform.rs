@@ -0,0 +1,139 @@
+//! Opt-in interpolation of fixed-step transforms for rendering
+
+use wutengine_math::Mat4;
+
+use crate::builtins::components::Transform;
+use crate::component::Component;
+use crate::time;
+use crate::world::World;
+
+/// Makes draw-submitting components render the [`Transform`] of the entity interpolated
+/// between its two latest fixed-step poses, instead of the last written pose.
+///
+/// Add to entities that are moved during fixed updates, such as physics-driven entities,
+/// to prevent stutter when the fixed rate and the frame rate differ. Rendering lags behind
+/// the simulation by at most one fixed step
+#[derive(Debug, Clone, Copy, Default)]
+pub struct InterpolatedTransform {
+    /// The previous and current fixed-step local-to-world matrices, or [`None`] if
+    /// no pose has been captured since creation or the last reset
+    poses: Option<(Mat4, Mat4)>,
+}
+
+impl InterpolatedTransform {
+    /// Returns a new [`InterpolatedTransform`]. Interpolation starts after the next fixed step
+    #[inline]
+    pub const fn new() -> Self {
+        Self { poses: None }
+    }
+
+    /// Discards the captured poses, so the next fixed-step pose is rendered as-is instead of
+    /// being interpolated from the previous one. Call after teleporting an entity
+    #[inline]
+    pub fn reset(&mut self) {
+        self.poses = None;
+    }
+
+    /// Returns the local-to-world matrix at `alpha` between the previous and current fixed-step
+    /// poses, or [`None`] if no pose has been captured yet
+    pub fn interpolated_local_to_world(&self, alpha: f32) -> Option<Mat4> {
+        let (previous, current) = self.poses?;
+
+        let (prev_scale, prev_rotation, prev_translation) =
+            previous.to_scale_rotation_translation();
+        let (cur_scale, cur_rotation, cur_translation) = current.to_scale_rotation_translation();
+
+        Some(Mat4::from_scale_rotation_translation(
+            prev_scale.lerp(cur_scale, alpha),
+            prev_rotation.slerp(cur_rotation, alpha),
+            prev_translation.lerp(cur_translation, alpha),
+        ))
+    }
+
+    /// Stores `local_to_world` as the current fixed-step pose, making the old current pose the previous one
+    fn capture(&mut self, local_to_world: Mat4) {
+        let previous = self.poses.map_or(local_to_world, |(_, current)| current);
+
+        self.poses = Some((previous, local_to_world));
+    }
+}
+
+impl Component for InterpolatedTransform {
+    const ID: uuid::NonNilUuid =
+        uuid::NonNilUuid::new(uuid::uuid!("0f6d3c52-8a41-4e2b-b7d9-5c1e93a4f806")).unwrap();
+}
+
+/// Captures the current pose of every entity with an [`InterpolatedTransform`]. Called by the
+/// runtime after each fixed step, once the transform hierarchy has been propagated
+pub(crate) fn capture_fixed_poses(world: &mut World) {
+    profiling::function_scope!();
+
+    for (interpolated, transform) in world
+        .ecs
+        .query_mut::<(&mut InterpolatedTransform, &Transform)>()
+    {
+        interpolated.capture(transform.local_to_world());
+    }
+}
+
+/// Returns the local-to-world matrix draw-submitting components should render with.
+/// Interpolated by the current fixed alpha if the entity has an [`InterpolatedTransform`]
+pub(crate) fn render_local_to_world(
+    transform: Option<&Transform>,
+    interpolated: Option<&InterpolatedTransform>,
+) -> Mat4 {
+    interpolated
+        .and_then(|interpolated| interpolated.interpolated_local_to_world(time::fixed_alpha()))
+        .or_else(|| transform.map(Transform::local_to_world))
+        .unwrap_or(Mat4::IDENTITY)
+}
+
+#[cfg(test)]
+mod test {
+    use wutengine_math::Quat;
+    use wutengine_math::Vec3;
+
+    use super::InterpolatedTransform;
+    use crate::builtins::components::Transform;
+    use crate::world::World;
+
+    #[test]
+    fn test_interpolate_between_fixed_poses() {
+        let mut world = World::new();
+
+        let entity = world
+            .ecs
+            .spawn((Transform::new(), InterpolatedTransform::new()));
+
+        assert!(
+            world
+                .ecs
+                .get::<&InterpolatedTransform>(entity)
+                .unwrap()
+                .interpolated_local_to_world(0.5)
+                .is_none()
+        );
+
+        super::capture_fixed_poses(&mut world);
+
+        {
+            let mut transform = world.ecs.get::<&mut Transform>(entity).unwrap();
+            transform.set_local_position(Vec3::new(2.0, 0.0, 0.0));
+            transform.set_local_rotation(Quat::from_rotation_y(1.0));
+        }
+
+        super::capture_fixed_poses(&mut world);
+
+        let halfway = world
+            .ecs
+            .get::<&InterpolatedTransform>(entity)
+            .unwrap()
+            .interpolated_local_to_world(0.5)
+            .unwrap();
+
+        let (_, rotation, translation) = halfway.to_scale_rotation_translation();
+
+        assert!(translation.abs_diff_eq(Vec3::X, 1e-5));
+        assert!(rotation.abs_diff_eq(Quat::from_rotation_y(0.5), 1e-5));
+    }
+}
@@ -1,11 +1,15 @@
 //! Builtin components
 
 mod audioplayer;
+pub(crate) mod interpolated_transform;
 mod name;
 pub mod physics;
 pub mod rendering;
-mod transform;
+mod timer;
+pub(crate) mod transform;
 
 pub use audioplayer::*;
+pub use interpolated_transform::InterpolatedTransform;
 pub use name::*;
+pub use timer::*;
 pub use transform::*;
@@ -2,6 +2,7 @@ use core::fmt::Display;
 use core::ops::Deref;
 
 use crate::component::Component;
+use crate::component::SerializableComponent;
 
 /// Simple component describing the user-assigned name for an entity
 #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
@@ -21,6 +22,18 @@ impl Component for Name {
         uuid::NonNilUuid::new(uuid::uuid!("aeed35e7-4dbb-4ec4-9d19-5478fe9ca4e2")).unwrap();
 }
 
+impl SerializableComponent for Name {
+    type Serialized = String;
+
+    fn to_serialized(&self) -> Self::Serialized {
+        self.0.clone()
+    }
+
+    fn from_serialized(serialized: Self::Serialized) -> Self {
+        Self(serialized)
+    }
+}
+
 impl Deref for Name {
     type Target = str;
 
@@ -1,109 +1,335 @@
 use crate::builtins::components::Transform;
 use crate::component::Component;
 
-use crate::math::*;
+use crate::math::Quat;
+use crate::math::Vec2;
+use crate::math::Vec3;
+use crate::system::Mut;
+use wutengine_physics::phys2d::PhysicsWorldReader;
 use wutengine_physics::phys2d::PhysicsWorldUpdater;
+use wutengine_physics::phys2d::collider::Collider;
 use wutengine_physics::phys2d::collider::ColliderData2D;
+use wutengine_physics::phys2d::rigidbody::Rigidbody;
+use wutengine_physics::phys2d::rigidbody::RigidbodyType2D;
 
-/// A set of colliders
+/// The maximum difference between the simulated and the synced pose of a rigidbody, for which the
+/// rigidbody is considered not to have moved
+const POSE_EPSILON: f32 = 1e-4;
+
+/// A set of 2D colliders, attached to a rigidbody that follows the entity's [`Transform`]. Only the XY
+/// position and the rotation around the Z axis of the transform are used.
+///
+/// The rigidbody is [`RigidbodyType2D::Fixed`] by default. If it is [`RigidbodyType2D::Dynamic`], the
+/// simulated pose is written back to the transform after each physics step
 #[derive(Debug, Default)]
 pub struct ColliderSet2D {
     colliders: Vec<Collider2D>,
+
+    /// The type of the rigidbody
+    body_type: RigidbodyType2D,
+
+    /// The rigidbody the colliders are attached to, once created
+    body: Option<Rigidbody>,
+
+    /// The entity pose the rigidbody was last synced to
+    last_pos_rot: (Vec2, f32),
+
+    /// Handles of removed colliders, deleted from the physics world during the next sync
+    removed: Vec<Collider>,
+
+    /// Handle of a replaced rigidbody, deleted from the physics world during the next sync
+    removed_body: Option<Rigidbody>,
 }
 
 impl ColliderSet2D {
+    /// Returns an empty set, with a rigidbody of the given type
+    pub fn with_body_type(body_type: RigidbodyType2D) -> Self {
+        Self {
+            body_type,
+            ..Default::default()
+        }
+    }
+
+    /// Returns the type of the rigidbody of this set
+    pub fn body_type(&self) -> RigidbodyType2D {
+        self.body_type
+    }
+
+    /// Changes the type of the rigidbody of this set. The rigidbody and its colliders are recreated
+    /// during the next sync
+    pub fn set_body_type(&mut self, body_type: RigidbodyType2D) {
+        if self.body_type == body_type {
+            return;
+        }
+
+        self.body_type = body_type;
+
+        let handles = self
+            .colliders
+            .iter_mut()
+            .filter_map(|collider| collider.handle.take());
+
+        self.removed.extend(handles);
+
+        if let Some(body) = self.body.take() {
+            self.removed_body = Some(body);
+        }
+    }
+
     /// Adds a new collider to this set
     pub fn add_collider(&mut self, collider: ColliderData2D) {
         self.colliders.push(Collider2D::new(collider));
     }
+
+    /// Removes the collider at `index` from this set, returning its data. Returns [`None`] if there
+    /// is no collider at `index`
+    pub fn remove_collider(&mut self, index: usize) -> Option<ColliderData2D> {
+        if index >= self.colliders.len() {
+            return None;
+        }
+
+        let collider = self.colliders.remove(index);
+
+        self.removed.extend(collider.handle);
+
+        Some(collider.data)
+    }
+
+    /// Returns the data of all colliders in this set
+    pub fn colliders(&self) -> impl Iterator<Item = &ColliderData2D> {
+        self.colliders.iter().map(|collider| &collider.data)
+    }
+
+    /// Deletes the rigidbody and all colliders of this set from the physics world, the next time it is updated
+    fn release(&mut self) {
+        let handles = self
+            .colliders
+            .iter_mut()
+            .filter_map(|collider| collider.handle.take())
+            .chain(self.removed.drain(..));
+
+        for handle in handles {
+            wutengine_physics::phys2d::delete_collider_deferred(handle);
+        }
+
+        for body in self.body.take().into_iter().chain(self.removed_body.take()) {
+            wutengine_physics::phys2d::delete_rigidbody_deferred(body);
+        }
+    }
 }
 
 #[derive(Debug, Default)]
 struct Collider2D {
-    handle: Option<crate::physics::phys2d::collider::Collider>,
-    last_pos_rot: (Vec2, f32),
+    handle: Option<Collider>,
     data: ColliderData2D,
+
+    /// Whether the shape is invalid, in which case no collider is created for it
+    invalid_shape: bool,
 }
 
 impl Collider2D {
     fn new(data: ColliderData2D) -> Self {
         Self {
             handle: None,
-            last_pos_rot: (Vec2::ZERO, 0.0),
             data,
+            invalid_shape: false,
         }
     }
-    fn recreate_collider(
+
+    fn create_collider(&mut self, body: &Rigidbody, physics_updater: &mut PhysicsWorldUpdater) {
+        let Some(builder) = self.data.create() else {
+            log::error!(
+                "Cannot create 2D collider with invalid shape {:?}",
+                self.data.type_data
+            );
+            self.invalid_shape = true;
+            return;
+        };
+
+        self.handle = Some(physics_updater.add_collider(builder, Some(body)));
+    }
+}
+
+impl ColliderSet2D {
+    /// Syncs the rigidbody and all colliders in this set to the physics world using the given [`PhysicsWorldUpdater`]
+    pub(crate) fn sync_to_physics_world(
         &mut self,
         transform: Option<&Transform>,
         physics_updater: &mut PhysicsWorldUpdater,
     ) {
-        self.handle = None;
+        for removed in self.removed.drain(..) {
+            physics_updater.delete_collider(removed);
+        }
 
-        let (pos, rot) = Self::calc_pos_rot(transform);
+        if let Some(removed) = self.removed_body.take() {
+            physics_updater.delete_rigidbody(removed);
+        }
 
-        self.handle = Some(physics_updater.add_collider(self.data.create(pos, rot)));
+        let (pos, rot) = calc_pos_rot(transform);
 
-        self.last_pos_rot = (pos, rot);
-    }
+        let body = match &self.body {
+            Some(body) => {
+                if self.last_pos_rot != (pos, rot) {
+                    physics_updater.move_rigidbody(body, (pos, rot));
+                }
 
-    fn calc_pos_rot(transform: Option<&Transform>) -> (Vec2, f32) {
-        let Some(transform) = transform else {
-            return (Vec2::ZERO, 0.0);
+                body
+            }
+            None => self
+                .body
+                .insert(physics_updater.add_rigidbody(self.body_type, (pos, rot))),
         };
 
-        let up = Vec3::Y;
-        let after_rot = transform.world_rotation() * up;
-        let without_z = after_rot.with_z(0.0).normalize();
-        let angle_pos = after_rot.angle_between(without_z);
-        let angle = if without_z.x > 0.0 {
-            angle_pos
-        } else {
-            -angle_pos
-        };
+        self.last_pos_rot = (pos, rot);
 
-        (transform.world_position().xy(), angle.to_degrees())
+        for collider in &mut self.colliders {
+            if collider.handle.is_none() && !collider.invalid_shape {
+                collider.create_collider(body, physics_updater);
+            }
+        }
     }
 
-    fn update_pos_rot(
+    /// Writes the simulated pose of the rigidbody of this set back to the given [`Transform`], if it is
+    /// [`RigidbodyType2D::Dynamic`]. The Z position and the rotation around the other axes are kept.
+    /// The transform is only written to, and so marked as changed, if the rigidbody was moved
+    pub(crate) fn sync_from_physics_world(
         &mut self,
-        transform: Option<&Transform>,
-        physics_updater: &mut PhysicsWorldUpdater,
+        transform: &mut Mut<'_, Transform>,
+        physics_reader: &PhysicsWorldReader,
     ) {
-        let Some(handle) = self.handle.as_ref() else {
+        if self.body_type != RigidbodyType2D::Dynamic {
+            return;
+        }
+
+        let Some((pos, rot)) = self
+            .body
+            .as_ref()
+            .and_then(|body| physics_reader.rigidbody_pose(body))
+        else {
             return;
         };
 
-        let (pos, rot) = Self::calc_pos_rot(transform);
+        let moved = !pos.abs_diff_eq(self.last_pos_rot.0, POSE_EPSILON)
+            || angle_difference(rot, self.last_pos_rot.1).abs() > POSE_EPSILON;
 
-        if self.last_pos_rot == (pos, rot) {
+        if !moved {
             return;
         }
 
-        physics_updater.move_collider(handle, (pos + self.data.offset, rot + self.data.rotation));
+        let (_, current_rot) = calc_pos_rot(Some(transform));
+        let transform: &mut Transform = transform;
+        let z_delta = Quat::from_rotation_z(angle_difference(rot, current_rot).to_radians());
 
-        self.last_pos_rot = (pos, rot);
+        transform.set_world_position(pos.extend(transform.world_position().z));
+        transform.set_world_rotation(z_delta * transform.world_rotation());
+
+        // The rigidbody was moved by the simulation, so it need not be moved back on the next sync
+        self.last_pos_rot = calc_pos_rot(Some(transform));
     }
 }
 
-impl ColliderSet2D {
-    /// Syncs all colliders in this set to the physics world using the given [`PhysicsWorldUpdater`]
-    pub(crate) fn sync_to_physics_world(
-        &mut self,
-        transform: Option<&Transform>,
-        physics_updater: &mut PhysicsWorldUpdater,
-    ) {
-        for collider in self.colliders.iter_mut() {
-            if collider.handle.is_none() {
-                collider.recreate_collider(transform, physics_updater);
-            }
+/// Returns the XY world position and the world rotation around the Z axis (in degrees) of the transform
+fn calc_pos_rot(transform: Option<&Transform>) -> (Vec2, f32) {
+    let Some(transform) = transform else {
+        return (Vec2::ZERO, 0.0);
+    };
 
-            collider.update_pos_rot(transform, physics_updater);
-        }
-    }
+    let right = transform.world_rotation() * Vec3::X;
+    let angle = right.y.atan2(right.x);
+
+    (transform.world_position().truncate(), angle.to_degrees())
+}
+
+/// Returns the difference `a - b` of two angles in degrees, wrapped to [-180, 180)
+fn angle_difference(a: f32, b: f32) -> f32 {
+    (a - b + 180.0).rem_euclid(360.0) - 180.0
 }
 
 impl Component for ColliderSet2D {
     const ID: uuid::NonNilUuid =
         uuid::NonNilUuid::new(uuid::uuid!("f716d2f8-024e-43df-bcc7-a06e0f984f14")).unwrap();
+
+    fn on_remove(&mut self, _entity: crate::entity::Entity) {
+        self.release();
+    }
+
+    fn on_destroy(&mut self, _entity: crate::entity::Entity) {
+        self.release();
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::ColliderSet2D;
+    use super::calc_pos_rot;
+    use crate::builtins::components::Transform;
+    use crate::math::Quat;
+    use crate::math::Vec2;
+    use crate::math::Vec3;
+    use crate::system::Mut;
+    use wutengine_physics::phys2d::collider::ColliderData2D;
+    use wutengine_physics::phys2d::rigidbody::RigidbodyType2D;
+
+    #[test]
+    fn test_pose_from_transform() {
+        let transform = Transform::new_at_local(
+            Vec3::new(1.0, 2.0, 5.0),
+            Quat::from_rotation_z(90_f32.to_radians()),
+            Vec3::ONE,
+        );
+
+        let (pos, rot) = calc_pos_rot(Some(&transform));
+        assert!(pos.abs_diff_eq(Vec2::new(1.0, 2.0), 1e-5));
+        assert!((rot - 90.0).abs() < 1e-4);
+    }
+
+    #[test]
+    fn test_dynamic_body_moves_transform() {
+        crate::test_util::init_globals();
+
+        let mut world = hecs::World::new();
+
+        // Far enough apart for the colliders not to touch
+        let spawn = |world: &mut hecs::World, body_type, x| {
+            let mut set = ColliderSet2D::with_body_type(body_type);
+            set.add_collider(ColliderData2D::default());
+
+            let transform =
+                Transform::new_at_local(Vec3::new(x, 10.0, 3.0), Quat::IDENTITY, Vec3::ONE);
+
+            world.spawn((set, transform))
+        };
+
+        let dynamic = spawn(&mut world, RigidbodyType2D::Dynamic, 0.0);
+        let fixed = spawn(&mut world, RigidbodyType2D::Fixed, 5.0);
+
+        wutengine_physics::update_physics_world(
+            |updater_2d| {
+                for (set, transform) in world.query_mut::<(&mut ColliderSet2D, &Transform)>() {
+                    set.sync_to_physics_world(Some(transform), updater_2d);
+                }
+            },
+            #[cfg(feature = "phys3d")]
+            |_| {},
+        );
+
+        for _ in 0..10 {
+            wutengine_physics::step(1.0 / 60.0);
+        }
+
+        wutengine_physics::phys2d::read_physics_world(|reader_2d| {
+            for (set, mut transform) in world.query_mut::<(&mut ColliderSet2D, Mut<Transform>)>() {
+                set.sync_from_physics_world(&mut transform, reader_2d);
+            }
+        });
+
+        // The dynamic body fell, keeping its Z position, while the fixed one stayed in place
+        let fallen = world.get::<&Transform>(dynamic).unwrap().world_position();
+        assert!(fallen.y < 10.0);
+        assert!(fallen.x.abs() < 1e-4);
+        assert!((fallen.z - 3.0).abs() < 1e-4);
+
+        let fixed = world.get::<&Transform>(fixed).unwrap().world_position();
+        assert!(fixed.abs_diff_eq(Vec3::new(5.0, 10.0, 3.0), 1e-5));
+    }
 }
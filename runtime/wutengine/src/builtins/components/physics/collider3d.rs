@@ -1,12 +1,385 @@
 use crate::builtins::components::Transform;
+use crate::component::Component;
 
-pub enum ColliderSet3D {}
+use crate::math::Quat;
+use crate::math::Vec3;
+use crate::system::Mut;
+use wutengine_physics::phys3d::PhysicsWorldReader;
+use wutengine_physics::phys3d::PhysicsWorldUpdater;
+use wutengine_physics::phys3d::collider::Collider;
+use wutengine_physics::phys3d::collider::ColliderData3D;
+use wutengine_physics::phys3d::rigidbody::Rigidbody;
+use wutengine_physics::phys3d::rigidbody::RigidbodyType3D;
+
+/// The maximum difference between the simulated and the synced pose of a rigidbody, for which the
+/// rigidbody is considered not to have moved
+const POSE_EPSILON: f32 = 1e-4;
+
+/// A set of 3D colliders, attached to a rigidbody that follows the entity's [`Transform`]. Scale is not applied.
+///
+/// The rigidbody is [`RigidbodyType3D::Fixed`] by default. If it is [`RigidbodyType3D::Dynamic`], the
+/// simulated pose is written back to the transform after each physics step
+#[derive(Debug, Default)]
+pub struct ColliderSet3D {
+    colliders: Vec<Collider3D>,
+
+    /// The type of the rigidbody
+    body_type: RigidbodyType3D,
+
+    /// The rigidbody the colliders are attached to, once created
+    body: Option<Rigidbody>,
+
+    /// The entity pose the rigidbody was last synced to
+    last_pos_rot: (Vec3, Quat),
+
+    /// Handles of removed colliders, deleted from the physics world during the next sync
+    removed: Vec<Collider>,
+
+    /// Handle of a replaced rigidbody, deleted from the physics world during the next sync
+    removed_body: Option<Rigidbody>,
+}
+
+impl ColliderSet3D {
+    /// Returns an empty set, with a rigidbody of the given type
+    pub fn with_body_type(body_type: RigidbodyType3D) -> Self {
+        Self {
+            body_type,
+            ..Default::default()
+        }
+    }
+
+    /// Returns the type of the rigidbody of this set
+    pub fn body_type(&self) -> RigidbodyType3D {
+        self.body_type
+    }
+
+    /// Changes the type of the rigidbody of this set. The rigidbody and its colliders are recreated
+    /// during the next sync
+    pub fn set_body_type(&mut self, body_type: RigidbodyType3D) {
+        if self.body_type == body_type {
+            return;
+        }
+
+        self.body_type = body_type;
+
+        let handles = self
+            .colliders
+            .iter_mut()
+            .filter_map(|collider| collider.handle.take());
+
+        self.removed.extend(handles);
+
+        if let Some(body) = self.body.take() {
+            self.removed_body = Some(body);
+        }
+    }
+
+    /// Adds a new collider to this set
+    pub fn add_collider(&mut self, collider: ColliderData3D) {
+        self.colliders.push(Collider3D::new(collider));
+    }
+
+    /// Removes the collider at `index` from this set, returning its data. Returns [`None`] if there
+    /// is no collider at `index`
+    pub fn remove_collider(&mut self, index: usize) -> Option<ColliderData3D> {
+        if index >= self.colliders.len() {
+            return None;
+        }
+
+        let collider = self.colliders.remove(index);
+
+        self.removed.extend(collider.handle);
+
+        Some(collider.data)
+    }
+
+    /// Returns the data of all colliders in this set
+    pub fn colliders(&self) -> impl Iterator<Item = &ColliderData3D> {
+        self.colliders.iter().map(|collider| &collider.data)
+    }
+
+    /// Deletes the rigidbody and all colliders of this set from the physics world, the next time it is updated
+    fn release(&mut self) {
+        let handles = self
+            .colliders
+            .iter_mut()
+            .filter_map(|collider| collider.handle.take())
+            .chain(self.removed.drain(..));
+
+        for handle in handles {
+            wutengine_physics::phys3d::delete_collider_deferred(handle);
+        }
+
+        for body in self.body.take().into_iter().chain(self.removed_body.take()) {
+            wutengine_physics::phys3d::delete_rigidbody_deferred(body);
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct Collider3D {
+    handle: Option<Collider>,
+    data: ColliderData3D,
+
+    /// Whether the shape is invalid, in which case no collider is created for it
+    invalid_shape: bool,
+}
+
+impl Collider3D {
+    fn new(data: ColliderData3D) -> Self {
+        Self {
+            handle: None,
+            data,
+            invalid_shape: false,
+        }
+    }
+
+    fn create_collider(&mut self, body: &Rigidbody, physics_updater: &mut PhysicsWorldUpdater) {
+        let Some(builder) = self.data.create() else {
+            log::error!(
+                "Cannot create 3D collider with invalid shape {:?}",
+                self.data.type_data
+            );
+            self.invalid_shape = true;
+            return;
+        };
+
+        self.handle = Some(physics_updater.add_collider(builder, Some(body)));
+    }
+}
 
 impl ColliderSet3D {
+    /// Syncs the rigidbody and all colliders in this set to the physics world using the given [`PhysicsWorldUpdater`]
     pub(crate) fn sync_to_physics_world(
         &mut self,
         transform: Option<&Transform>,
-        physics_updater: &mut crate::physics::phys3d::PhysicsWorldUpdater,
+        physics_updater: &mut PhysicsWorldUpdater,
+    ) {
+        for removed in self.removed.drain(..) {
+            physics_updater.delete_collider(removed);
+        }
+
+        if let Some(removed) = self.removed_body.take() {
+            physics_updater.delete_rigidbody(removed);
+        }
+
+        let (pos, rot) = calc_pos_rot(transform);
+
+        let body = match &self.body {
+            Some(body) => {
+                if self.last_pos_rot != (pos, rot) {
+                    physics_updater.move_rigidbody(body, (pos, rot));
+                }
+
+                body
+            }
+            None => self
+                .body
+                .insert(physics_updater.add_rigidbody(self.body_type, (pos, rot))),
+        };
+
+        self.last_pos_rot = (pos, rot);
+
+        for collider in &mut self.colliders {
+            if collider.handle.is_none() && !collider.invalid_shape {
+                collider.create_collider(body, physics_updater);
+            }
+        }
+    }
+
+    /// Writes the simulated pose of the rigidbody of this set back to the given [`Transform`], if it is
+    /// [`RigidbodyType3D::Dynamic`]. The transform is only written to, and so marked as changed, if the
+    /// rigidbody was moved
+    pub(crate) fn sync_from_physics_world(
+        &mut self,
+        transform: &mut Mut<'_, Transform>,
+        physics_reader: &PhysicsWorldReader,
     ) {
+        if self.body_type != RigidbodyType3D::Dynamic {
+            return;
+        }
+
+        let Some((pos, rot)) = self
+            .body
+            .as_ref()
+            .and_then(|body| physics_reader.rigidbody_pose(body))
+        else {
+            return;
+        };
+
+        let moved = !pos.abs_diff_eq(self.last_pos_rot.0, POSE_EPSILON)
+            || !rot.abs_diff_eq(self.last_pos_rot.1, POSE_EPSILON);
+
+        if !moved {
+            return;
+        }
+
+        let transform: &mut Transform = transform;
+
+        transform.set_world_position(pos);
+        transform.set_world_rotation(rot);
+
+        // The rigidbody was moved by the simulation, so it need not be moved back on the next sync
+        self.last_pos_rot = calc_pos_rot(Some(transform));
+    }
+}
+
+/// Returns the world position and rotation of the transform
+fn calc_pos_rot(transform: Option<&Transform>) -> (Vec3, Quat) {
+    let Some(transform) = transform else {
+        return (Vec3::ZERO, Quat::IDENTITY);
+    };
+
+    (transform.world_position(), transform.world_rotation())
+}
+
+impl Component for ColliderSet3D {
+    const ID: uuid::NonNilUuid =
+        uuid::NonNilUuid::new(uuid::uuid!("3b0f6a52-8d4e-4c1e-9a57-2f6c9e8d1b43")).unwrap();
+
+    fn on_remove(&mut self, _entity: crate::entity::Entity) {
+        self.release();
+    }
+
+    fn on_destroy(&mut self, _entity: crate::entity::Entity) {
+        self.release();
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::ColliderSet3D;
+    use crate::builtins::components::Transform;
+    use crate::math::Quat;
+    use crate::math::Vec3;
+    use crate::system::Mut;
+    use wutengine_physics::phys3d::collider::ColliderData3D;
+    use wutengine_physics::phys3d::collider::ColliderType3D;
+    use wutengine_physics::phys3d::rigidbody::RigidbodyType3D;
+
+    /// Syncs all sets in `world` to the physics world
+    fn sync_to_physics_world(world: &mut hecs::World) {
+        wutengine_physics::update_physics_world(
+            #[cfg(feature = "phys2d")]
+            |_| {},
+            |updater_3d| {
+                for (set, transform) in world.query_mut::<(&mut ColliderSet3D, &Transform)>() {
+                    set.sync_to_physics_world(Some(transform), updater_3d);
+                }
+            },
+        );
+    }
+
+    /// Returns the number of colliders attached to the rigidbody of `set`
+    fn num_attached_colliders(set: &ColliderSet3D) -> usize {
+        let mut num = 0;
+
+        wutengine_physics::phys3d::read_physics_world(|reader_3d| {
+            num = reader_3d
+                .num_attached_colliders(set.body.as_ref().unwrap())
+                .unwrap();
+        });
+
+        num
+    }
+
+    #[test]
+    fn test_remove_collider() {
+        crate::test_util::init_globals();
+
+        let mut world = hecs::World::new();
+
+        let mut set = ColliderSet3D::default();
+        set.add_collider(ColliderData3D::default());
+        set.add_collider(ColliderData3D::default());
+
+        let entity = world.spawn((set, Transform::new()));
+
+        sync_to_physics_world(&mut world);
+        assert_eq!(
+            2,
+            num_attached_colliders(&world.get::<&ColliderSet3D>(entity).unwrap())
+        );
+
+        assert!(
+            world
+                .get::<&mut ColliderSet3D>(entity)
+                .unwrap()
+                .remove_collider(0)
+                .is_some()
+        );
+
+        sync_to_physics_world(&mut world);
+
+        let set = world.get::<&ColliderSet3D>(entity).unwrap();
+        assert!(set.removed.is_empty());
+        assert_eq!(1, num_attached_colliders(&set));
+    }
+
+    #[test]
+    fn test_invalid_convex_hull() {
+        crate::test_util::init_globals();
+
+        let mut world = hecs::World::new();
+
+        // Too few points for a convex hull
+        let mut set = ColliderSet3D::default();
+        set.add_collider(ColliderData3D {
+            type_data: ColliderType3D::Convex {
+                points: vec![Vec3::ZERO, Vec3::X],
+            },
+            ..Default::default()
+        });
+
+        let entity = world.spawn((set, Transform::new()));
+
+        sync_to_physics_world(&mut world);
+
+        let set = world.get::<&ColliderSet3D>(entity).unwrap();
+        assert!(set.colliders[0].handle.is_none());
+        assert!(set.colliders[0].invalid_shape);
+        assert_eq!(0, num_attached_colliders(&set));
+    }
+
+    #[test]
+    fn test_dynamic_body_moves_transform() {
+        crate::test_util::init_globals();
+
+        let mut world = hecs::World::new();
+
+        // Far enough apart for the colliders not to touch
+        let spawn = |world: &mut hecs::World, body_type, x| {
+            let mut set = ColliderSet3D::with_body_type(body_type);
+            set.add_collider(ColliderData3D::default());
+
+            let transform =
+                Transform::new_at_local(Vec3::new(x, 10.0, 3.0), Quat::IDENTITY, Vec3::ONE);
+
+            world.spawn((set, transform))
+        };
+
+        let dynamic = spawn(&mut world, RigidbodyType3D::Dynamic, 0.0);
+        let fixed = spawn(&mut world, RigidbodyType3D::Fixed, 5.0);
+
+        sync_to_physics_world(&mut world);
+
+        for _ in 0..10 {
+            wutengine_physics::step(1.0 / 60.0);
+        }
+
+        wutengine_physics::phys3d::read_physics_world(|reader_3d| {
+            for (set, mut transform) in world.query_mut::<(&mut ColliderSet3D, Mut<Transform>)>() {
+                set.sync_from_physics_world(&mut transform, reader_3d);
+            }
+        });
+
+        // The dynamic body fell, while the fixed one stayed in place
+        let fallen = world.get::<&Transform>(dynamic).unwrap().world_position();
+        assert!(fallen.y < 10.0);
+        assert!(fallen.x.abs() < 1e-4);
+        assert!((fallen.z - 3.0).abs() < 1e-4);
+
+        let fixed = world.get::<&Transform>(fixed).unwrap().world_position();
+        assert!(fixed.abs_diff_eq(Vec3::new(5.0, 10.0, 3.0), 1e-5));
     }
 }
@@ -14,8 +14,11 @@ use wutengine_shadercompiler::CAMERA_PARAMS_BIND_GROUP_INDEX;
 use wutengine_shadercompiler::MATERIAL_PARAMS_BIND_GROUP_INDEX;
 use wutengine_util_macro::unique_id_type32;
 
+use crate::builtins::components::InterpolatedTransform;
 use crate::builtins::components::Transform;
+use crate::builtins::components::interpolated_transform;
 use crate::component::Component;
+use crate::component::SerializableComponent;
 use crate::graphics::DrawCommand;
 use crate::graphics::material::{Material, MaterialParameter};
 use crate::graphics::renderpass::RenderPass;
@@ -456,20 +459,66 @@ impl Component for Camera {
     where
         Self: Sized,
     {
-        manifest.add_system::<(&mut Camera, Option<&Transform>)>(
+        manifest.add_system::<(
+            &mut Camera,
+            Option<&Transform>,
+            Option<&InterpolatedTransform>,
+        )>(
             Phase::PreRender,
             "Camera pre-render preparation",
-            |_, (camera, transform)| {
+            |_, (camera, transform, interpolated)| {
                 profiling::scope!("Camera pre-render preparation");
 
                 camera.update_render_target();
 
-                camera.update_view_projection(
-                    transform.map_or(Mat4::IDENTITY, Transform::local_to_world),
-                );
+                camera.update_view_projection(interpolated_transform::render_local_to_world(
+                    transform,
+                    interpolated,
+                ));
 
                 camera.update_cam_bind_group();
             },
         );
     }
 }
+
+/// The serialized form of a [`Camera`]. The render target is a runtime-only
+/// value, and is not serialized
+#[derive(Debug, Clone, Copy, serde::Serialize, serde::Deserialize)]
+pub struct SerializedCamera {
+    /// The projection the camera uses
+    pub projection: CameraProjection,
+
+    /// The background of the camera's viewport
+    pub background: CameraBackground,
+
+    /// The viewport dimensions
+    pub viewport: CameraViewport,
+
+    /// The near/far clipping planes
+    pub clipping_planes: (f32, f32),
+}
+
+impl SerializableComponent for Camera {
+    type Serialized = SerializedCamera;
+
+    fn to_serialized(&self) -> Self::Serialized {
+        SerializedCamera {
+            projection: self.projection,
+            background: self.background,
+            viewport: self.viewport,
+            clipping_planes: self.clipping_planes,
+        }
+    }
+
+    fn from_serialized(serialized: Self::Serialized) -> Self {
+        let mut camera = Self::new();
+
+        camera.projection = serialized.projection;
+        camera.background = serialized.background;
+        camera.viewport = serialized.viewport;
+        camera.clipping_planes = serialized.clipping_planes;
+
+        camera
+    }
+}
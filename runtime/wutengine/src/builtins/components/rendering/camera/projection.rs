@@ -1,7 +1,7 @@
 use crate::math::Mat4;
 
 /// The different types of possible [`super::Camera`] projections.
-#[derive(Debug, Clone, Copy, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq, serde::Serialize, serde::Deserialize)]
 pub enum CameraProjection {
     /// Perspective-projecting camera.
     Perspective(FieldOfView),
@@ -40,7 +40,7 @@ impl CameraProjection {
 }
 
 /// Field-of-view definition for a [`CameraProjection`]
-#[derive(Debug, Clone, Copy, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq, serde::Serialize, serde::Deserialize)]
 pub enum FieldOfView {
     /// Vertical degrees
     Vertical(f32),
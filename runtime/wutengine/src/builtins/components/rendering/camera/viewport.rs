@@ -1,7 +1,7 @@
 use core::fmt::Display;
 
 /// The configuration for the viewport of a [`Camera`]
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, serde::Serialize, serde::Deserialize)]
 pub struct CameraViewport {
     /// Location of the left side of the viewport, as expressed as a fraction of the window. From 0.0-1.0
     pub x: f32,
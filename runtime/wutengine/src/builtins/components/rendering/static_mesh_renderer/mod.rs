@@ -1,8 +1,14 @@
 use wutengine_asset_server::AutoLoad;
+use wutengine_assets::AssetRef;
+use wutengine_assets::assets::material::SerializedMaterial;
+use wutengine_assets::assets::mesh::SerializedMesh;
 use wutengine_math::Mat4;
 
+use crate::builtins::components::InterpolatedTransform;
 use crate::builtins::components::Transform;
+use crate::builtins::components::interpolated_transform;
 use crate::component::Component;
+use crate::component::SerializableComponent;
 use crate::graphics;
 use crate::graphics::material::Material;
 use crate::graphics::mesh::Mesh;
@@ -41,16 +47,48 @@ impl Component for StaticMeshRenderer {
     where
         Self: Sized,
     {
-        manifest.add_system::<(&Self, Option<&Transform>)>(
+        manifest.add_system::<(&Self, Option<&Transform>, Option<&InterpolatedTransform>)>(
             Phase::PreRender,
             "StaticMeshRenderer submit draw call",
-            |_, (this, transform)| {
-                this.submit_draw_call(transform.map_or(Mat4::IDENTITY, Transform::local_to_world));
+            |_, (this, transform, interpolated)| {
+                this.submit_draw_call(interpolated_transform::render_local_to_world(
+                    transform,
+                    interpolated,
+                ));
             },
         );
     }
 }
 
+/// The serialized form of a [`StaticMeshRenderer`]. Only meshes and materials
+/// loaded from assets can be serialized
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct SerializedStaticMeshRenderer {
+    /// The mesh to render
+    pub mesh: AssetRef<SerializedMesh>,
+
+    /// The material to render the mesh with
+    pub material: AssetRef<SerializedMaterial>,
+}
+
+impl SerializableComponent for StaticMeshRenderer {
+    type Serialized = SerializedStaticMeshRenderer;
+
+    fn to_serialized(&self) -> Self::Serialized {
+        SerializedStaticMeshRenderer {
+            mesh: AssetRef::new(self.mesh.asset_id()),
+            material: AssetRef::new(self.material.asset_id()),
+        }
+    }
+
+    fn from_serialized(serialized: Self::Serialized) -> Self {
+        Self {
+            mesh: AutoLoad::new_from_ref(serialized.mesh),
+            material: AutoLoad::new_from_ref(serialized.material),
+        }
+    }
+}
+
 /// System implementations
 impl StaticMeshRenderer {
     fn submit_draw_call(&self, transform: Mat4) {
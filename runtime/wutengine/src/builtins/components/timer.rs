@@ -0,0 +1,237 @@
+//! Timers stored on entities, ticked by game time
+
+use core::time::Duration;
+
+use crate::component::Component;
+use crate::runtime::SystemConfig;
+use crate::system::Phase;
+use crate::system::SystemLabel;
+use crate::time;
+
+/// Whether a [`Timer`] stops or restarts when it finishes
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum TimerMode {
+    /// The timer finishes once, and stays finished until reset
+    #[default]
+    Once,
+
+    /// The timer restarts every time it finishes
+    Repeating,
+}
+
+/// A timer component, ticked by game time during [`Phase::Update`]. Systems that check the timer
+/// in the same phase should run after [`Timer::TICK_LABEL`].
+///
+/// To cancel a timer, remove the component from its entity
+#[derive(Debug, Clone, Default)]
+pub struct Timer {
+    duration: Duration,
+    elapsed: Duration,
+    mode: TimerMode,
+    paused: bool,
+    finished: bool,
+    times_finished_this_tick: u32,
+}
+
+/// Public API
+impl Timer {
+    /// The label of the system that ticks all timers
+    pub const TICK_LABEL: SystemLabel = SystemLabel::new("Timer tick");
+
+    /// Returns a new timer of the given `duration` and `mode`
+    #[inline]
+    pub const fn new(duration: Duration, mode: TimerMode) -> Self {
+        Self {
+            duration,
+            elapsed: Duration::ZERO,
+            mode,
+            paused: false,
+            finished: false,
+            times_finished_this_tick: 0,
+        }
+    }
+
+    /// Returns a new one-shot timer of the given `duration`
+    #[inline]
+    pub const fn once(duration: Duration) -> Self {
+        Self::new(duration, TimerMode::Once)
+    }
+
+    /// Returns a new repeating timer with the given `interval`
+    #[inline]
+    pub const fn repeating(interval: Duration) -> Self {
+        Self::new(interval, TimerMode::Repeating)
+    }
+
+    /// Advances the timer by `delta`, unless it is paused. Called automatically for timers on entities
+    pub fn tick(&mut self, delta: Duration) {
+        self.times_finished_this_tick = 0;
+
+        if self.paused || (self.finished && self.mode == TimerMode::Once) {
+            return;
+        }
+
+        self.elapsed += delta;
+
+        if self.elapsed < self.duration {
+            return;
+        }
+
+        self.finished = true;
+
+        match self.mode {
+            TimerMode::Once => {
+                self.elapsed = self.duration;
+                self.times_finished_this_tick = 1;
+            }
+            TimerMode::Repeating if self.duration.is_zero() => {
+                self.elapsed = Duration::ZERO;
+                self.times_finished_this_tick = 1;
+            }
+            TimerMode::Repeating => {
+                let duration = self.duration.as_nanos();
+                let elapsed = self.elapsed.as_nanos();
+
+                self.times_finished_this_tick =
+                    u32::try_from(elapsed / duration).unwrap_or(u32::MAX);
+                self.elapsed = Duration::from_nanos(
+                    u64::try_from(elapsed % duration).expect("Smaller than the duration"),
+                );
+            }
+        }
+    }
+
+    /// Returns `true` if the timer finished during its last tick
+    #[inline]
+    pub const fn just_finished(&self) -> bool {
+        self.times_finished_this_tick > 0
+    }
+
+    /// Returns how many times the timer finished during its last tick. Can be more than one for
+    /// repeating timers with intervals shorter than a frame
+    #[inline]
+    pub const fn times_finished_this_tick(&self) -> u32 {
+        self.times_finished_this_tick
+    }
+
+    /// Returns `true` if the timer has finished at least once since it was created or reset
+    #[inline]
+    pub const fn is_finished(&self) -> bool {
+        self.finished
+    }
+
+    /// Pauses the timer. A paused timer does not advance until resumed
+    #[inline]
+    pub fn pause(&mut self) {
+        self.paused = true;
+    }
+
+    /// Resumes the timer if it was paused
+    #[inline]
+    pub fn resume(&mut self) {
+        self.paused = false;
+    }
+
+    /// Returns `true` if the timer is paused
+    #[inline]
+    pub const fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    /// Restarts the timer from zero, and clears its finished state
+    #[inline]
+    pub fn reset(&mut self) {
+        self.elapsed = Duration::ZERO;
+        self.finished = false;
+        self.times_finished_this_tick = 0;
+    }
+
+    /// Returns the game time that passed since the timer (re)started
+    #[inline]
+    pub const fn elapsed(&self) -> Duration {
+        self.elapsed
+    }
+
+    /// Returns the game time left until the timer next finishes. Zero for finished one-shot timers
+    #[inline]
+    pub fn remaining(&self) -> Duration {
+        self.duration.saturating_sub(self.elapsed)
+    }
+
+    /// Returns the duration, or interval, of the timer
+    #[inline]
+    pub const fn duration(&self) -> Duration {
+        self.duration
+    }
+
+    /// Sets the duration, or interval, of the timer. Does not reset the elapsed time
+    #[inline]
+    pub fn set_duration(&mut self, duration: Duration) {
+        self.duration = duration;
+    }
+
+    /// Returns the mode of the timer
+    #[inline]
+    pub const fn mode(&self) -> TimerMode {
+        self.mode
+    }
+}
+
+impl Component for Timer {
+    const ID: uuid::NonNilUuid =
+        uuid::NonNilUuid::new(uuid::uuid!("8e2c71d4-5b0a-4f39-a6e1-3d94c7b2f510")).unwrap();
+
+    fn insert_default_component_systems(manifest: &mut crate::runtime::SystemManifest)
+    where
+        Self: Sized,
+    {
+        manifest.add_system_with_config::<&mut Self>(
+            Phase::Update,
+            "Timer tick",
+            &SystemConfig {
+                labels: &[Self::TICK_LABEL],
+                ..Default::default()
+            },
+            |_, timer| {
+                timer.tick(Duration::from_nanos(time::delta_nanos()));
+            },
+        );
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use core::time::Duration;
+
+    use super::Timer;
+
+    #[test]
+    fn test_timer_tick() {
+        let mut once = Timer::once(Duration::from_secs(2));
+        let mut repeating = Timer::repeating(Duration::from_secs(1));
+
+        once.tick(Duration::from_millis(1500));
+        repeating.tick(Duration::from_millis(2500));
+
+        assert!(!once.just_finished());
+        assert_eq!(Duration::from_millis(500), once.remaining());
+        assert_eq!(2, repeating.times_finished_this_tick());
+        assert_eq!(Duration::from_millis(500), repeating.remaining());
+
+        once.pause();
+        once.tick(Duration::from_secs(1));
+
+        assert!(!once.is_finished());
+
+        once.resume();
+        once.tick(Duration::from_secs(1));
+
+        assert!(once.just_finished());
+        assert_eq!(Duration::ZERO, once.remaining());
+
+        once.tick(Duration::from_secs(1));
+
+        assert!(once.is_finished());
+        assert!(!once.just_finished());
+    }
+}
@@ -1,8 +1,14 @@
+//! The transform component and the transform hierarchy
+
 use wutengine_math::Mat4;
 use wutengine_math::Quat;
 use wutengine_math::Vec3;
 
 use crate::component::Component;
+use crate::component::SerializableComponent;
+use crate::entity::Entity;
+use crate::system::Mut;
+use crate::world::World;
 
 /// A 3D transform component, containing both the local transform and the transform hierarchy
 #[derive(Debug)]
@@ -11,6 +17,20 @@ pub struct Transform {
     rotation: Quat,
     scale: Vec3,
     local_to_world: Mat4,
+
+    /// The local-to-world matrix of the parent, as of the last hierarchy propagation.
+    /// Identity if this transform has no parent
+    parent_to_world: Mat4,
+
+    /// The rotations of all ancestors composed, as of the last hierarchy propagation.
+    /// Identity if this transform has no parent
+    parent_rotation: Quat,
+
+    /// The parent entity, if any
+    pub(crate) parent: Option<Entity>,
+
+    /// The child entities
+    pub(crate) children: Vec<Entity>,
 }
 
 /// Public API
@@ -28,6 +48,10 @@ impl Transform {
             rotation,
             scale,
             local_to_world: Mat4::NAN,
+            parent_to_world: Mat4::IDENTITY,
+            parent_rotation: Quat::IDENTITY,
+            parent: None,
+            children: Vec::new(),
         };
 
         new.recalculate_local_to_world();
@@ -41,6 +65,20 @@ impl Transform {
         self.local_to_world
     }
 
+    /// Returns the parent of this transform, if any.
+    ///
+    /// To change the parent, see [`Entity::set_parent`]
+    #[inline]
+    pub const fn parent(&self) -> Option<Entity> {
+        self.parent
+    }
+
+    /// Returns the children of this transform
+    #[inline]
+    pub fn children(&self) -> &[Entity] {
+        &self.children
+    }
+
     /// Returns the current local position
     #[inline]
     pub const fn local_position(&self) -> Vec3 {
@@ -61,14 +99,16 @@ impl Transform {
 
     /// Returns the current world position
     #[inline]
-    pub const fn world_position(&self) -> Vec3 {
-        self.translation
+    pub fn world_position(&self) -> Vec3 {
+        self.local_to_world.w_axis.truncate()
     }
 
-    /// Returns the current world rotation
+    /// Returns the current world rotation. This is the composition of the local rotations of this
+    /// transform and all of its ancestors, so it is well-defined even if an ancestor has a
+    /// non-uniform scale
     #[inline]
-    pub const fn world_rotation(&self) -> Quat {
-        self.rotation
+    pub fn world_rotation(&self) -> Quat {
+        self.parent_rotation * self.rotation
     }
 
     /// Set the local position
@@ -98,15 +138,18 @@ impl Transform {
     /// Set the world position
     #[inline]
     pub fn set_world_position(&mut self, world_position: Vec3) {
-        self.translation = world_position;
+        self.translation = self
+            .parent_to_world
+            .inverse()
+            .transform_point3(world_position);
 
         self.recalculate_local_to_world();
     }
 
     /// Set the world rotation
     #[inline]
-    pub fn set_world_rotation(&mut self, world_position: Quat) {
-        self.rotation = world_position;
+    pub fn set_world_rotation(&mut self, world_rotation: Quat) {
+        self.rotation = self.parent_rotation.inverse() * world_rotation;
 
         self.recalculate_local_to_world();
     }
@@ -115,11 +158,156 @@ impl Transform {
 /// Private API
 impl Transform {
     fn recalculate_local_to_world(&mut self) {
-        self.local_to_world =
-            Mat4::from_scale_rotation_translation(self.scale, self.rotation, self.translation);
+        self.local_to_world = self.parent_to_world
+            * Mat4::from_scale_rotation_translation(self.scale, self.rotation, self.translation);
+    }
+
+    /// Sets the cached local-to-world matrix and world rotation of the parent, and updates our own
+    /// local-to-world matrix accordingly
+    fn set_parent_to_world(&mut self, parent_to_world: Mat4, parent_rotation: Quat) {
+        self.parent_to_world = parent_to_world;
+        self.parent_rotation = parent_rotation;
+
+        self.recalculate_local_to_world();
+    }
+}
+
+/// Recalculates the local-to-world matrices of all transforms that are part of a hierarchy,
+/// starting at the root transforms and working down to their children
+pub(crate) fn propagate_transforms(world: &mut World) {
+    profiling::function_scope!();
+
+    let mut to_update = Vec::new();
+
+    for transform in world.ecs.query_mut::<&Transform>() {
+        if transform.parent.is_some() {
+            continue;
+        }
+
+        for child in &transform.children {
+            to_update.push((*child, transform.local_to_world, transform.world_rotation()));
+        }
+    }
+
+    while let Some((entity, parent_to_world, parent_rotation)) = to_update.pop() {
+        let Ok(mut transform) = world.ecs.query_one_mut::<Mut<Transform>>(entity.0) else {
+            log::error!("Child entity {entity} has no transform. Invalid hierarchy");
+            continue;
+        };
+
+        // Only transforms that actually moved along with their parent are marked as changed
+        if transform.parent_to_world != parent_to_world
+            || transform.parent_rotation != parent_rotation
+        {
+            transform.set_parent_to_world(parent_to_world, parent_rotation);
+        }
+
+        for child in &transform.children {
+            to_update.push((*child, transform.local_to_world, transform.world_rotation()));
+        }
     }
 }
 
+/// Makes `parent` the parent of `child`, removing `child` from its previous parent if needed.
+/// If `parent` is [`None`], `child` becomes a root transform.
+///
+/// The local transform of `child` is kept as-is, so its world-space transform changes along with its parent
+pub(crate) fn set_parent(world: &mut World, child: Entity, parent: Option<Entity>) {
+    if let Some(parent) = parent
+        && is_ancestor_or_self(world, child, parent)
+    {
+        log::error!(
+            "Cannot make entity {parent} the parent of entity {child} because it would create a cycle"
+        );
+        return;
+    }
+
+    let Ok(child_transform) = world.ecs.query_one_mut::<&mut Transform>(child.0) else {
+        log::error!("Cannot set the parent of entity {child} because it has no transform");
+        return;
+    };
+
+    let old_parent = child_transform.parent.take();
+
+    if let Some(old_parent) = old_parent
+        && let Ok(old_parent_transform) = world.ecs.query_one_mut::<&mut Transform>(old_parent.0)
+    {
+        old_parent_transform.children.retain(|c| *c != child);
+    }
+
+    let mut parent_to_world = None;
+
+    if let Some(parent) = parent {
+        if let Ok(parent_transform) = world.ecs.query_one_mut::<&mut Transform>(parent.0) {
+            parent_transform.children.push(child);
+            parent_to_world = Some((
+                parent_transform.local_to_world,
+                parent_transform.world_rotation(),
+            ));
+        } else {
+            log::error!(
+                "Cannot make entity {parent} the parent of entity {child} because it has no transform"
+            );
+        }
+    }
+
+    let child_transform = world
+        .ecs
+        .query_one_mut::<&mut Transform>(child.0)
+        .expect("Transform checked above");
+
+    child_transform.parent = parent_to_world.and(parent);
+    let (parent_to_world, parent_rotation) =
+        parent_to_world.unwrap_or((Mat4::IDENTITY, Quat::IDENTITY));
+
+    child_transform.set_parent_to_world(parent_to_world, parent_rotation);
+}
+
+/// Moves the place of `replaced` in the transform hierarchy to `replacement`, which replaces it
+/// on the same entity. The local transform of the replacement is kept
+pub(crate) fn keep_hierarchy(replaced: &mut Transform, replacement: &mut Transform) {
+    replacement.parent = replaced.parent.take();
+    replacement.children = core::mem::take(&mut replaced.children);
+    replacement.set_parent_to_world(replaced.parent_to_world, replaced.parent_rotation);
+}
+
+/// Detaches `entity` from its parent and children, making all of them root transforms
+pub(crate) fn detach(world: &mut World, entity: Entity) {
+    let Ok(transform) = world.ecs.query_one_mut::<&Transform>(entity.0) else {
+        return;
+    };
+
+    let has_parent = transform.parent.is_some();
+    let children = transform.children.clone();
+
+    if has_parent {
+        set_parent(world, entity, None);
+    }
+
+    for child in children {
+        set_parent(world, child, None);
+    }
+}
+
+/// Returns `true` if `entity` is `other`, or is anywhere in the parent chain of `other`
+fn is_ancestor_or_self(world: &mut World, entity: Entity, other: Entity) -> bool {
+    let mut cur = Some(other);
+
+    while let Some(cur_entity) = cur {
+        if cur_entity == entity {
+            return true;
+        }
+
+        cur = world
+            .ecs
+            .query_one_mut::<&Transform>(cur_entity.0)
+            .ok()
+            .and_then(|transform| transform.parent);
+    }
+
+    false
+}
+
 impl Default for Transform {
     fn default() -> Self {
         Self::new()
@@ -130,3 +318,271 @@ impl Component for Transform {
     const ID: uuid::NonNilUuid =
         uuid::NonNilUuid::new(uuid::uuid!("5462eaa9-fed4-4603-84f4-1edf0bcdaeee")).unwrap();
 }
+
+/// The serialized form of a [`Transform`]. The hierarchy is not part of the transform
+/// itself, but of the serialized entity that contains it
+#[derive(Debug, Clone, Copy, serde::Serialize, serde::Deserialize)]
+pub struct SerializedTransform {
+    /// The local position
+    pub translation: Vec3,
+
+    /// The local rotation
+    pub rotation: Quat,
+
+    /// The local scale
+    pub scale: Vec3,
+}
+
+impl SerializableComponent for Transform {
+    type Serialized = SerializedTransform;
+
+    fn to_serialized(&self) -> Self::Serialized {
+        SerializedTransform {
+            translation: self.translation,
+            rotation: self.rotation,
+            scale: self.scale,
+        }
+    }
+
+    fn from_serialized(serialized: Self::Serialized) -> Self {
+        Self::new_at_local(
+            serialized.translation,
+            serialized.rotation,
+            serialized.scale,
+        )
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use wutengine_math::Quat;
+    use wutengine_math::Vec3;
+
+    use super::Transform;
+    use crate::entity::Entity;
+    use crate::world::World;
+
+    fn spawn(world: &mut World, position: Vec3, rotation: Quat) -> Entity {
+        Entity(
+            world
+                .ecs
+                .spawn((Transform::new_at_local(position, rotation, Vec3::ONE),)),
+        )
+    }
+
+    fn get(world: &World, entity: Entity) -> hecs::Ref<'_, Transform> {
+        world.ecs.get::<&Transform>(entity.0).unwrap()
+    }
+
+    #[test]
+    fn test_child_follows_parent() {
+        let mut world = World::new();
+
+        let parent = spawn(&mut world, Vec3::new(1.0, 2.0, 3.0), Quat::IDENTITY);
+        let child = spawn(&mut world, Vec3::X, Quat::IDENTITY);
+
+        super::set_parent(&mut world, child, Some(parent));
+
+        assert_eq!(Some(parent), get(&world, child).parent());
+        assert_eq!(&[child], get(&world, parent).children());
+        assert!(
+            get(&world, child)
+                .world_position()
+                .abs_diff_eq(Vec3::new(2.0, 2.0, 3.0), 1e-5),
+            "Child not offset by parent"
+        );
+
+        world
+            .ecs
+            .get::<&mut Transform>(parent.0)
+            .unwrap()
+            .set_local_position(Vec3::ZERO);
+
+        super::propagate_transforms(&mut world);
+
+        assert!(
+            get(&world, child)
+                .world_position()
+                .abs_diff_eq(Vec3::X, 1e-5),
+            "Child did not follow parent"
+        );
+    }
+
+    #[test]
+    fn test_replace_keeps_hierarchy() {
+        // Registered up front, as queueing the default systems needs a running runtime
+        crate::component::should_insert_default_component_systems::<Transform>();
+
+        let mut world = World::new();
+
+        let parent = spawn(&mut world, Vec3::new(1.0, 2.0, 3.0), Quat::IDENTITY);
+        let child = spawn(&mut world, Vec3::X, Quat::IDENTITY);
+
+        super::set_parent(&mut world, child, Some(parent));
+
+        assert!(world.add_component(
+            child,
+            Transform::new_at_local(Vec3::Y, Quat::IDENTITY, Vec3::ONE)
+        ));
+        assert!(world.add_component(parent, Transform::new()));
+
+        assert_eq!(Some(parent), get(&world, child).parent());
+        assert_eq!(&[child], get(&world, parent).children());
+
+        super::propagate_transforms(&mut world);
+
+        assert!(
+            get(&world, child)
+                .world_position()
+                .abs_diff_eq(Vec3::Y, 1e-5),
+            "Child did not follow the replaced parent"
+        );
+    }
+
+    #[test]
+    fn test_propagation_marks_moved_children() {
+        use crate::system::ComponentTicks;
+
+        let mut world = World::new();
+
+        let parent = spawn(&mut world, Vec3::ZERO, Quat::IDENTITY);
+        let child = spawn(&mut world, Vec3::X, Quat::IDENTITY);
+
+        world
+            .ecs
+            .insert_one(child.0, ComponentTicks::<Transform>::new(0))
+            .unwrap();
+
+        super::set_parent(&mut world, child, Some(parent));
+        super::propagate_transforms(&mut world);
+
+        let is_changed = |world: &World| {
+            world
+                .ecs
+                .get::<&ComponentTicks<Transform>>(child.0)
+                .unwrap()
+                .is_changed_after(0)
+        };
+
+        assert!(
+            !is_changed(&world),
+            "Child marked without its parent moving"
+        );
+
+        world
+            .ecs
+            .get::<&mut Transform>(parent.0)
+            .unwrap()
+            .set_local_position(Vec3::Y);
+
+        super::propagate_transforms(&mut world);
+
+        assert!(
+            is_changed(&world),
+            "Child not marked after moving with its parent"
+        );
+    }
+
+    #[test]
+    fn test_world_setters_with_rotated_parent() {
+        let mut world = World::new();
+
+        let parent_rot = Quat::from_rotation_y(core::f32::consts::FRAC_PI_2);
+        let parent = spawn(&mut world, Vec3::new(0.0, 5.0, 0.0), parent_rot);
+        let child = spawn(&mut world, Vec3::ZERO, Quat::IDENTITY);
+
+        super::set_parent(&mut world, child, Some(parent));
+
+        let target_pos = Vec3::new(3.0, -1.0, 2.0);
+        let target_rot = Quat::from_rotation_x(0.3);
+
+        {
+            let mut child_transform = world.ecs.get::<&mut Transform>(child.0).unwrap();
+            child_transform.set_world_position(target_pos);
+            child_transform.set_world_rotation(target_rot);
+        }
+
+        let child_transform = get(&world, child);
+
+        assert!(
+            child_transform
+                .world_position()
+                .abs_diff_eq(target_pos, 1e-5),
+            "Incorrect world position"
+        );
+        assert!(
+            child_transform
+                .world_rotation()
+                .abs_diff_eq(target_rot, 1e-5),
+            "Incorrect world rotation"
+        );
+        assert!(
+            child_transform.local_position().abs_diff_eq(
+                parent_rot.inverse() * (target_pos - Vec3::new(0.0, 5.0, 0.0)),
+                1e-5
+            ),
+            "Incorrect local position"
+        );
+    }
+
+    #[test]
+    fn test_world_rotation_with_non_uniformly_scaled_ancestor() {
+        let mut world = World::new();
+
+        let grandparent = Entity(world.ecs.spawn((Transform::new_at_local(
+            Vec3::ZERO,
+            Quat::IDENTITY,
+            Vec3::new(1.0, 4.0, 1.0),
+        ),)));
+        let parent_rot = Quat::from_rotation_z(0.5);
+        let parent = spawn(&mut world, Vec3::ZERO, parent_rot);
+        let child_rot = Quat::from_rotation_z(0.7);
+        let child = spawn(&mut world, Vec3::ZERO, child_rot);
+
+        // The local-to-world matrix of the parent is sheared, so contains no pure rotation
+        super::set_parent(&mut world, parent, Some(grandparent));
+        super::set_parent(&mut world, child, Some(parent));
+
+        assert!(
+            get(&world, child)
+                .world_rotation()
+                .abs_diff_eq(parent_rot * child_rot, 1e-5),
+            "Incorrect world rotation"
+        );
+
+        let target_rot = Quat::from_rotation_x(0.3);
+
+        world
+            .ecs
+            .get::<&mut Transform>(child.0)
+            .unwrap()
+            .set_world_rotation(target_rot);
+
+        assert!(
+            get(&world, child)
+                .world_rotation()
+                .abs_diff_eq(target_rot, 1e-5),
+            "Incorrect world rotation after setting it"
+        );
+    }
+
+    #[test]
+    fn test_reject_cycles() {
+        let mut world = World::new();
+
+        let a = spawn(&mut world, Vec3::ZERO, Quat::IDENTITY);
+        let b = spawn(&mut world, Vec3::ZERO, Quat::IDENTITY);
+
+        super::set_parent(&mut world, b, Some(a));
+        super::set_parent(&mut world, a, Some(b));
+        super::set_parent(&mut world, a, Some(a));
+
+        assert_eq!(None, get(&world, a).parent());
+        assert_eq!(Some(a), get(&world, b).parent());
+
+        super::set_parent(&mut world, b, None);
+
+        assert_eq!(None, get(&world, b).parent());
+        assert!(get(&world, a).children().is_empty());
+    }
+}
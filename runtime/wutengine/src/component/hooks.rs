@@ -0,0 +1,101 @@
+//! Lifecycle hooks of components, called when they are added, removed, or their entity is destroyed
+
+use core::any::TypeId;
+use std::collections::HashMap;
+use std::sync::LazyLock;
+use std::sync::RwLock;
+
+use super::Component;
+use crate::entity::Entity;
+
+/// Calls [`Component::on_destroy`] on the component of a specific type of an entity, if it has one
+type DestroyHookFn = fn(&mut hecs::World, Entity);
+
+/// The destroy hooks of all component types that were added to an entity through WutEngine, by their [`TypeId`]
+static DESTROY_HOOKS: LazyLock<RwLock<HashMap<TypeId, DestroyHookFn>>> =
+    LazyLock::new(|| RwLock::new(HashMap::new()));
+
+/// Registers the hooks of `C`, so that they can be found when an entity is destroyed
+pub(crate) fn register_hooks<C: Component>() {
+    let ty = TypeId::of::<C>();
+
+    if DESTROY_HOOKS.read().unwrap().contains_key(&ty) {
+        return;
+    }
+
+    DESTROY_HOOKS
+        .write()
+        .unwrap()
+        .insert(ty, run_destroy_hook::<C>);
+}
+
+/// Calls [`Component::on_destroy`] for every component of `entity` with registered hooks
+pub(crate) fn run_destroy_hooks(world: &mut hecs::World, entity: Entity) {
+    let Ok(entity_ref) = world.entity(entity.0) else {
+        return;
+    };
+
+    let hooks: Vec<DestroyHookFn> = {
+        let registered = DESTROY_HOOKS.read().unwrap();
+
+        entity_ref
+            .component_types()
+            .filter_map(|ty| registered.get(&ty).copied())
+            .collect()
+    };
+
+    for hook in hooks {
+        hook(world, entity);
+    }
+}
+
+fn run_destroy_hook<C: Component>(world: &mut hecs::World, entity: Entity) {
+    if let Ok(component) = world.query_one_mut::<&mut C>(entity.0) {
+        component.on_destroy(entity);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use core::sync::atomic::AtomicU32;
+    use core::sync::atomic::Ordering;
+
+    use super::register_hooks;
+    use crate::component::Component;
+    use crate::entity::Entity;
+    use crate::world::World;
+
+    static REMOVED: AtomicU32 = AtomicU32::new(0);
+    static DESTROYED: AtomicU32 = AtomicU32::new(0);
+
+    struct Hooked;
+
+    impl Component for Hooked {
+        const ID: uuid::NonNilUuid =
+            uuid::NonNilUuid::new(uuid::uuid!("b5f0b6a4-2c0e-4d55-9a6c-7d38b2f1c0e9")).unwrap();
+
+        fn on_remove(&mut self, _entity: Entity) {
+            REMOVED.fetch_add(1, Ordering::Relaxed);
+        }
+
+        fn on_destroy(&mut self, _entity: Entity) {
+            DESTROYED.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    #[test]
+    fn test_remove_and_destroy_hooks() {
+        let mut world = World::new();
+
+        register_hooks::<Hooked>();
+
+        let removed = Entity(world.ecs.spawn((Hooked,)));
+        let destroyed = Entity(world.ecs.spawn((Hooked,)));
+
+        assert!(world.remove_component::<Hooked>(removed).is_some());
+        world.destroy(destroyed);
+
+        assert_eq!(1, REMOVED.load(Ordering::Relaxed));
+        assert_eq!(1, DESTROYED.load(Ordering::Relaxed));
+    }
+}
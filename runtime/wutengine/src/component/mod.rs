@@ -6,6 +6,12 @@ use std::collections::HashSet;
 use std::sync::LazyLock;
 use std::sync::RwLock;
 
+mod hooks;
+mod registry;
+
+pub(crate) use hooks::{register_hooks, run_destroy_hooks};
+pub use registry::*;
+
 static ADDED_DEFAULT_COMPONENT_SYSTEMS: LazyLock<RwLock<HashSet<TypeId>>> =
     LazyLock::new(|| RwLock::new(HashSet::default()));
 
@@ -32,6 +38,25 @@ pub(crate) fn should_insert_default_component_systems<T: Component>() -> bool {
     ADDED_DEFAULT_COMPONENT_SYSTEMS.write().unwrap().insert(ty)
 }
 
+/// Queues the default component systems of `C` for insertion into the global system schedule, if that
+/// was not done before
+pub(crate) fn queue_default_component_systems<C: Component>() {
+    if !should_insert_default_component_systems::<C>() {
+        return;
+    }
+
+    log::debug!(
+        "Adding default component systems for {}",
+        core::any::type_name::<C>()
+    );
+
+    let mut manifest = crate::runtime::SystemManifest::empty();
+
+    C::insert_default_component_systems(&mut manifest);
+
+    crate::system::insert_systems(manifest);
+}
+
 /// Trait that should be implemented by types that can be
 /// used as components in the WutEngine ECS
 pub trait Component: Any + Send + Sync {
@@ -50,4 +75,17 @@ pub trait Component: Any + Send + Sync {
         Self: Sized,
     {
     }
+
+    /// Called right after the component was added to `entity`.
+    ///
+    /// Like the other hooks, it is called while the world is locked, so it must not use the immediate
+    /// [`crate::entity::Entity`] accessors. Deferred changes like adding components are fine
+    fn on_add(&mut self, _entity: crate::entity::Entity) {}
+
+    /// Called right after the component was removed from `entity`, or replaced by another component of
+    /// the same type. Not called when the entity is destroyed, see [`Self::on_destroy`]
+    fn on_remove(&mut self, _entity: crate::entity::Entity) {}
+
+    /// Called right before `entity` is destroyed, along with this component
+    fn on_destroy(&mut self, _entity: crate::entity::Entity) {}
 }
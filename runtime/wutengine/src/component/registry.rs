@@ -0,0 +1,375 @@
+//! Registry of serializable component types, keyed by their [`Component::ID`]
+
+use core::any::TypeId;
+use std::collections::BTreeMap;
+use std::sync::LazyLock;
+use std::sync::RwLock;
+
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use wutengine_assets::assets::component::SerializedComponent;
+use wutengine_assets::assets::entity::EntityEntry;
+use wutengine_assets::assets::entity::SerializedEntity;
+
+use super::Component;
+use crate::builtins::components::Name;
+use crate::builtins::components::Transform;
+use crate::builtins::components::rendering::Camera;
+use crate::builtins::components::rendering::StaticMeshRenderer;
+use crate::entity::Entity;
+use crate::world::EntityMap;
+use crate::world::Persistent;
+use crate::world::World;
+
+static COMPONENT_REGISTRY: LazyLock<RwLock<BTreeMap<uuid::NonNilUuid, RegisteredComponent>>> =
+    LazyLock::new(|| {
+        let mut registry = BTreeMap::new();
+
+        insert_into_registry::<Name>(&mut registry);
+        insert_into_registry::<Transform>(&mut registry);
+        insert_into_registry::<Camera>(&mut registry);
+        insert_into_registry::<StaticMeshRenderer>(&mut registry);
+        insert_into_registry::<Persistent>(&mut registry);
+
+        RwLock::new(registry)
+    });
+
+/// Trait for components that can be converted to and from a [`SerializedComponent`].
+///
+/// Implementors must be registered with [`register_serializable_component`] before they
+/// can be (de)serialized by the runtime. The builtin components are registered automatically
+pub trait SerializableComponent: Component + Sized {
+    /// The serialized form of this component
+    type Serialized: Serialize + DeserializeOwned;
+
+    /// Converts this component into its serialized form
+    fn to_serialized(&self) -> Self::Serialized;
+
+    /// Creates a new component from its serialized form
+    fn from_serialized(serialized: Self::Serialized) -> Self;
+
+    /// Replaces the entities this component references with their counterparts in `map`. Called
+    /// when restoring a save game, after all saved entities were spawned
+    fn map_entities(&mut self, _map: &EntityMap) {}
+}
+
+/// An error while serializing or deserializing a component
+#[derive(Debug, derive_more::Error, derive_more::Display, derive_more::From)]
+pub enum ComponentSerializationErr {
+    /// No serializable component was registered with the given ID
+    #[display("No serializable component registered with ID {_0}")]
+    #[from(skip)]
+    UnknownComponent(#[error(not(source))] uuid::NonNilUuid),
+
+    /// The entity does not exist in the world
+    #[display("Entity {_0} does not exist in the world")]
+    #[from(skip)]
+    NoSuchEntity(#[error(not(source))] Entity),
+
+    /// (De)serializing the component data failed
+    #[display("Failed to (de)serialize component data: {_0}")]
+    Postcard(postcard::Error),
+}
+
+/// Type-erased function returning whether an entity has a single component
+type HasFn = fn(&hecs::World, hecs::Entity) -> bool;
+
+/// Type-erased function serializing a single component of an entity, if it has one
+type SerializeFn = fn(&hecs::World, hecs::Entity) -> Option<Result<Vec<u8>, postcard::Error>>;
+
+/// Type-erased function deserializing a single component and queueing it for an entity
+type DeserializeFn = fn(Entity, &[u8]) -> Result<(), postcard::Error>;
+
+/// Type-erased function deserializing a single component and adding it to an entity immediately
+type InsertFn = fn(&mut World, Entity, &[u8]) -> Result<(), postcard::Error>;
+
+/// Type-erased function remapping the entity references of a single component of an entity, if it has one
+type MapEntitiesFn = fn(&mut hecs::World, hecs::Entity, &EntityMap);
+
+/// The type-erased (de)serialization functions of a single registered component type
+#[derive(Debug, Clone, Copy)]
+struct RegisteredComponent {
+    /// The name of the component type
+    name: &'static str,
+
+    /// The [`TypeId`] of the component type
+    type_id: TypeId,
+
+    /// Returns whether the given entity has the component
+    has: HasFn,
+
+    /// Serializes the component on the given entity, if it has one
+    serialize: SerializeFn,
+
+    /// Deserializes the component and queues it to be added to the given entity
+    deserialize: DeserializeFn,
+
+    /// Deserializes the component and adds it to the given entity in the given world
+    insert: InsertFn,
+
+    /// Remaps the entity references of the component on the given entity, if it has one
+    map_entities: MapEntitiesFn,
+}
+
+/// Registers the component type `C`, so that it can be (de)serialized using its [`Component::ID`]
+pub fn register_serializable_component<C: SerializableComponent>() {
+    insert_into_registry::<C>(&mut COMPONENT_REGISTRY.write().unwrap());
+}
+
+/// Returns whether a serializable component type with the given ID was registered
+pub fn is_serializable_component_registered(id: uuid::NonNilUuid) -> bool {
+    COMPONENT_REGISTRY.read().unwrap().contains_key(&id)
+}
+
+/// Deserializes the given component and adds it to `entity` using [`Entity::add_component`].
+pub fn add_serialized_component(
+    entity: Entity,
+    component: &SerializedComponent,
+) -> Result<(), ComponentSerializationErr> {
+    let registered = COMPONENT_REGISTRY
+        .read()
+        .unwrap()
+        .get(&component.component_type)
+        .copied()
+        .ok_or(ComponentSerializationErr::UnknownComponent(
+            component.component_type,
+        ))?;
+
+    log::trace!(
+        "Deserializing component {} for entity {entity}",
+        registered.name
+    );
+
+    (registered.deserialize)(entity, &component.data)?;
+
+    Ok(())
+}
+
+/// Deserializes the given component and adds it to `entity` in `world` immediately
+pub(crate) fn insert_serialized_component(
+    world: &mut World,
+    entity: Entity,
+    component: &SerializedComponent,
+) -> Result<(), ComponentSerializationErr> {
+    let registered = COMPONENT_REGISTRY
+        .read()
+        .unwrap()
+        .get(&component.component_type)
+        .copied()
+        .ok_or(ComponentSerializationErr::UnknownComponent(
+            component.component_type,
+        ))?;
+
+    (registered.insert)(world, entity, &component.data)?;
+
+    Ok(())
+}
+
+/// Calls [`SerializableComponent::map_entities`] on all registered serializable components of `entity`
+pub(crate) fn map_component_entities(world: &mut World, entity: Entity, map: &EntityMap) {
+    let map_fns: Vec<MapEntitiesFn> = COMPONENT_REGISTRY
+        .read()
+        .unwrap()
+        .values()
+        .map(|registered| registered.map_entities)
+        .collect();
+
+    for map_entities in map_fns {
+        map_entities(&mut world.ecs, entity.0, map);
+    }
+}
+
+/// Dumps a live entity, including all its registered serializable components and its children,
+/// into a [`SerializedEntity`].
+///
+/// Components that were not registered with [`register_serializable_component`] are skipped.
+/// Must not be called from a system that mutably borrows any registered component.
+pub fn serialize_entity(entity: Entity) -> Result<SerializedEntity, ComponentSerializationErr> {
+    serialize_entity_in(&crate::world::get_world(), entity)
+}
+
+/// Like [`serialize_entity`], but for an entity in the given world
+pub(crate) fn serialize_entity_in(
+    world: &World,
+    entity: Entity,
+) -> Result<SerializedEntity, ComponentSerializationErr> {
+    if !world.ecs.contains(entity.0) {
+        return Err(ComponentSerializationErr::NoSuchEntity(entity));
+    }
+
+    let name = world
+        .ecs
+        .get::<&Name>(entity.0)
+        .map(|name| name.to_string())
+        .unwrap_or_default();
+
+    let children = world
+        .ecs
+        .get::<&Transform>(entity.0)
+        .map(|transform| transform.children().to_vec())
+        .unwrap_or_default();
+
+    let components = serialize_components_in(world, entity)?;
+
+    let children = children
+        .into_iter()
+        .map(|child| serialize_entity_in(world, child).map(EntityEntry::Entity))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    Ok(SerializedEntity {
+        name,
+        components,
+        children,
+    })
+}
+
+/// Returns whether `entity` has any registered serializable component other than its [`Name`]
+pub(crate) fn has_serializable_components(world: &World, entity: Entity) -> bool {
+    COMPONENT_REGISTRY
+        .read()
+        .unwrap()
+        .values()
+        .any(|registered| {
+            registered.type_id != TypeId::of::<Name>() && (registered.has)(&world.ecs, entity.0)
+        })
+}
+
+/// Serializes all registered serializable components of `entity`, except for its [`Name`]
+pub(crate) fn serialize_components_in(
+    world: &World,
+    entity: Entity,
+) -> Result<Vec<SerializedComponent>, ComponentSerializationErr> {
+    let mut components = Vec::new();
+
+    for (id, registered) in COMPONENT_REGISTRY.read().unwrap().iter() {
+        // The name is stored by the caller itself
+        if registered.type_id == TypeId::of::<Name>() {
+            continue;
+        }
+
+        if let Some(data) = (registered.serialize)(&world.ecs, entity.0) {
+            components.push(SerializedComponent {
+                component_type: *id,
+                data: data?,
+            });
+        }
+    }
+
+    Ok(components)
+}
+
+/// Inserts the (de)serialization functions of `C` into `registry`
+fn insert_into_registry<C: SerializableComponent>(
+    registry: &mut BTreeMap<uuid::NonNilUuid, RegisteredComponent>,
+) {
+    let name = core::any::type_name::<C>();
+
+    if let Some(existing) = registry.get(&C::ID) {
+        if existing.type_id != TypeId::of::<C>() {
+            log::error!(
+                "Cannot register serializable component {name} because its ID {} is already used by {}",
+                C::ID,
+                existing.name
+            );
+        }
+
+        return;
+    }
+
+    log::debug!("Registering serializable component {name} ({})", C::ID);
+
+    registry.insert(
+        C::ID,
+        RegisteredComponent {
+            name,
+            type_id: TypeId::of::<C>(),
+            has: |world, entity| world.satisfies::<&C>(entity),
+            serialize: serialize_component::<C>,
+            deserialize: deserialize_component::<C>,
+            insert: insert_component::<C>,
+            map_entities: map_component_entities_of::<C>,
+        },
+    );
+}
+
+/// Serializes the `C` component of `entity`, if it has one
+fn serialize_component<C: SerializableComponent>(
+    world: &hecs::World,
+    entity: hecs::Entity,
+) -> Option<Result<Vec<u8>, postcard::Error>> {
+    let component = world.get::<&C>(entity).ok()?;
+
+    Some(postcard::to_allocvec(&component.to_serialized()))
+}
+
+/// Deserializes a `C` component and adds it to `entity`
+fn deserialize_component<C: SerializableComponent>(
+    entity: Entity,
+    data: &[u8],
+) -> Result<(), postcard::Error> {
+    let serialized = postcard::from_bytes::<C::Serialized>(data)?;
+
+    entity.add_component(C::from_serialized(serialized));
+
+    Ok(())
+}
+
+/// Deserializes a `C` component and adds it to `entity` in `world`
+fn insert_component<C: SerializableComponent>(
+    world: &mut World,
+    entity: Entity,
+    data: &[u8],
+) -> Result<(), postcard::Error> {
+    let serialized = postcard::from_bytes::<C::Serialized>(data)?;
+
+    world.add_component(entity, C::from_serialized(serialized));
+
+    Ok(())
+}
+
+/// Remaps the entity references of the `C` component of `entity`, if it has one
+fn map_component_entities_of<C: SerializableComponent>(
+    world: &mut hecs::World,
+    entity: hecs::Entity,
+    map: &EntityMap,
+) {
+    if let Ok(component) = world.query_one_mut::<&mut C>(entity) {
+        component.map_entities(map);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use wutengine_math::Quat;
+    use wutengine_math::Vec3;
+
+    use super::SerializableComponent;
+    use super::serialize_entity_in;
+    use crate::builtins::components::Name;
+    use crate::builtins::components::SerializedTransform;
+    use crate::builtins::components::Transform;
+    use crate::component::Component;
+    use crate::entity::Entity;
+    use crate::world::World;
+
+    #[test]
+    fn test_serialize_entity() {
+        let mut world = World::new();
+
+        let transform = Transform::new_at_local(Vec3::X, Quat::from_rotation_z(1.0), Vec3::ONE);
+        let entity = Entity(world.ecs.spawn((Name::new("Test".to_string()), transform)));
+
+        let serialized = serialize_entity_in(&world, entity).unwrap();
+
+        assert_eq!("Test", serialized.name);
+        assert_eq!(1, serialized.components.len());
+        assert_eq!(Transform::ID, serialized.components[0].component_type);
+
+        let decoded: SerializedTransform =
+            postcard::from_bytes(&serialized.components[0].data).unwrap();
+        let decoded = Transform::from_serialized(decoded);
+
+        assert_eq!(Vec3::X, decoded.local_position());
+        assert_eq!(Quat::from_rotation_z(1.0), decoded.local_rotation());
+    }
+}
@@ -237,3 +237,92 @@ impl DevelopmentOverlayWindow for GamepadOverlay {
         }
     }
 }
+
+#[derive(Default)]
+pub(super) struct SystemStatsOverlay {
+    sort_by_average: bool,
+}
+
+impl SystemStatsOverlay {
+    fn show_phase(&self, phase: &crate::system::PhaseStats, ui: &mut egui::Ui) {
+        let mut systems: Vec<_> = phase.systems.iter().collect();
+
+        if self.sort_by_average {
+            systems.sort_by(|a, b| b.average.cmp(&a.average));
+        }
+
+        egui::CollapsingHeader::new(format!(
+            "{} ({:.3} ms)",
+            phase.phase,
+            phase.total_average().as_secs_f64() * 1000.0
+        ))
+        .id_salt(phase.phase.str())
+        .default_open(true)
+        .show(ui, |ui| {
+            egui::Grid::new(phase.phase.str())
+                .striped(true)
+                .num_columns(5)
+                .show(ui, |ui| {
+                    ui.strong("System");
+                    ui.strong("Average (ms)");
+                    ui.strong("Max (ms)");
+                    ui.strong("Runs");
+                    ui.strong("Entities");
+                    ui.end_row();
+
+                    for system in systems {
+                        ui.label(system.name);
+                        ui.label(format!("{:.3}", system.average.as_secs_f64() * 1000.0));
+                        ui.label(format!("{:.3}", system.max.as_secs_f64() * 1000.0));
+                        ui.label(system.invocations.to_string());
+                        ui.label(if system.exclusive {
+                            "exclusive".to_string()
+                        } else {
+                            system
+                                .matching_entities
+                                .map_or_else(|| "-".to_string(), |count| count.to_string())
+                        });
+                        ui.end_row();
+                    }
+                });
+        });
+    }
+}
+
+impl DevelopmentOverlayWindow for SystemStatsOverlay {
+    fn name(&self) -> &str {
+        "Systems"
+    }
+
+    fn icon(&self) -> Option<&str> {
+        Some("⏱")
+    }
+
+    fn show(&mut self, ui: &mut egui::Ui) {
+        let stats = crate::system::current_system_stats();
+
+        if stats.phases.is_empty() {
+            ui.label("No systems scheduled");
+            return;
+        }
+
+        ui.label(format!(
+            "Times over the last {} runs of each system",
+            crate::system::STATS_WINDOW
+        ));
+        ui.checkbox(&mut self.sort_by_average, "Sort by average time");
+
+        ui.separator();
+
+        egui::ScrollArea::vertical().show(ui, |ui| {
+            for phase in &stats.phases {
+                self.show_phase(phase, ui);
+            }
+        });
+    }
+
+    fn window_state_changed(&mut self, opened: bool) {
+        // Only counted while shown, as it runs the query of every system each frame
+        crate::system::set_count_matching_entities(opened);
+    }
+}
@@ -6,10 +6,10 @@ use std::sync::mpsc::{Receiver, Sender, channel};
 
 use crate::builtins::components::Name;
 use crate::builtins::components::Transform;
+use crate::builtins::components::transform;
 use crate::component;
 use crate::component::Component;
-use crate::runtime::MainThreadEvent;
-use crate::runtime::SystemManifest;
+use crate::system;
 use crate::world::World;
 use wutengine_util::InitOnce;
 
@@ -18,32 +18,70 @@ static ENTITY_QUEUES: InitOnce<EntityCommandQueues> = InitOnce::new_checked();
 /// Utility struct containing the global entity command queues
 #[derive(Debug)]
 struct EntityCommandQueues {
-    new_component_queue: Sender<(Entity, hecs::EntityBuilder)>,
+    component_change_queue: Sender<ComponentChange>,
+    set_parent_queue: Sender<(Entity, Option<Entity>)>,
     destroy_entities_queue: Sender<Entity>,
 }
 
+/// Adds a queued component to its entity. Returns `false` if the entity does not exist
+type AddComponentFn = Box<dyn FnOnce(&mut World) -> bool + Send>;
+
+/// Removes a component of a specific type from the entity
+type RemoveComponentFn = fn(&mut World, Entity);
+
+/// A queued addition or removal of a component. Additions and removals share a queue, so that
+/// they are applied in the order they were requested in
+#[derive(derive_more::Debug)]
+enum ComponentChange {
+    /// Adds a component
+    Add(#[debug(skip)] AddComponentFn),
+
+    /// Removes a component from the entity
+    Remove(Entity, RemoveComponentFn),
+}
+
+impl ComponentChange {
+    /// Returns the change adding `component` to `entity`
+    fn add<C: Component>(entity: Entity, component: C) -> Self {
+        Self::Add(Box::new(move |world| {
+            world.add_component(entity, component)
+        }))
+    }
+
+    /// Returns the change removing the component of type `C` from `entity`
+    fn remove<C: Component>(entity: Entity) -> Self {
+        Self::Remove(entity, |world, entity| {
+            world.remove_component::<C>(entity);
+        })
+    }
+}
+
 /// The entity manager
 #[derive(Debug)]
 pub(crate) struct EntityManager {
-    new_components: Receiver<(Entity, hecs::EntityBuilder)>,
+    component_changes: Receiver<ComponentChange>,
+    new_parents: Receiver<(Entity, Option<Entity>)>,
     entities_to_destroy: Receiver<Entity>,
 }
 
 /// Initializes the global entity command queues and returns
 /// the entity manager
 pub(crate) fn initialize() -> EntityManager {
-    let (new_components_send, new_components_recv) = channel::<(Entity, hecs::EntityBuilder)>();
+    let (component_changes_send, component_changes_recv) = channel::<ComponentChange>();
+    let (new_parents_send, new_parents_recv) = channel::<(Entity, Option<Entity>)>();
     let (entities_to_destroy_send, entites_to_destroy_recv) = channel::<Entity>();
 
     let entity_command_queues = EntityCommandQueues {
-        new_component_queue: new_components_send,
+        component_change_queue: component_changes_send,
+        set_parent_queue: new_parents_send,
         destroy_entities_queue: entities_to_destroy_send,
     };
 
     InitOnce::init(&ENTITY_QUEUES, entity_command_queues);
 
     EntityManager {
-        new_components: new_components_recv,
+        component_changes: component_changes_recv,
+        new_parents: new_parents_recv,
         entities_to_destroy: entites_to_destroy_recv,
     }
 }
@@ -57,27 +95,62 @@ pub(crate) fn process_changes(world: &mut World, manager: &EntityManager) {
     // First we make sure all reserved entities are inserted
     world.ecs.flush();
 
-    // Now we add all new components
+    // Names changed in place by the systems that already ran are looked up by their new name from now on
+    world.refresh_names();
+
+    // Now we add and remove components in the order they were requested in, marking new components
+    // as added after all systems that already ran
+    system::advance_tick();
+
     let mut num_added = 0;
 
-    for (entity, mut new_component_builder) in manager.new_components.try_iter() {
-        if let Err(hecs::NoSuchEntity) = world.ecs.insert(entity.0, new_component_builder.build()) {
-            log::error!(
-                "Failed to insert components on entity {entity} because it does not exist in the world"
-            );
-            continue;
+    for change in manager.component_changes.try_iter() {
+        match change {
+            ComponentChange::Add(add_component) => {
+                if add_component(world) {
+                    num_added += 1;
+                }
+            }
+            ComponentChange::Remove(entity, remove_component) => remove_component(world, entity),
         }
-
-        num_added += 1;
     }
 
     if num_added > 0 {
         log::debug!("Added {num_added} new components");
     }
 
+    // Then we update the transform hierarchy, now that all new transforms are present
+    for (child, parent) in manager.new_parents.try_iter() {
+        transform::set_parent(world, child, parent);
+    }
+
+    let num_destroyed = destroy_entities(world, manager.entities_to_destroy.try_iter().collect());
+
+    if num_destroyed > 0 {
+        log::debug!("Destroyed {num_destroyed} entities");
+    }
+}
+
+/// Destroys the given entities and all their children immediately. Returns the amount of destroyed entities
+pub(crate) fn destroy_entities(world: &mut World, mut to_destroy: Vec<Entity>) -> usize {
     let mut num_destroyed = 0;
 
-    for entity in manager.entities_to_destroy.try_iter() {
+    // Children are destroyed along with their parent, so we keep a stack of entities still to destroy
+    while let Some(entity) = to_destroy.pop() {
+        if let Ok(transform) = world.ecs.query_one_mut::<&mut Transform>(entity.0) {
+            let parent = transform.parent.take();
+            to_destroy.append(&mut transform.children);
+
+            if let Some(parent) = parent
+                && let Ok(parent_transform) = world.ecs.query_one_mut::<&mut Transform>(parent.0)
+            {
+                parent_transform.children.retain(|c| *c != entity);
+            }
+        }
+
+        world.unindex_name(entity);
+        component::run_destroy_hooks(&mut world.ecs, entity);
+
         if let Err(hecs::NoSuchEntity) = world.ecs.despawn(entity.0) {
             log::error!("Failed to destroy entity {entity} because it does not exist in the world");
             continue;
@@ -86,9 +159,7 @@ pub(crate) fn process_changes(world: &mut World, manager: &EntityManager) {
         num_destroyed += 1;
     }
 
-    if num_destroyed > 0 {
-        log::debug!("Destroyed {num_destroyed} entities");
-    }
+    num_destroyed
 }
 
 /// The ID of a WutEngine entity
@@ -111,6 +182,43 @@ impl Hash for Entity {
 
 impl nohash_hasher::IsEnabled for Entity {}
 
+/// Entities are serialized as their raw ID, so that serialized references to entities can be
+/// remapped when they are restored, see [`crate::world::EntityMap`]
+impl serde::Serialize for Entity {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_u64(self.0.to_bits().get())
+    }
+}
+
+impl<'de> serde::Deserialize<'de> for Entity {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let bits = u64::deserialize(deserializer)?;
+
+        hecs::Entity::from_bits(bits)
+            .map(Self)
+            .ok_or_else(|| serde::de::Error::custom(format!("Invalid entity ID {bits:016x}")))
+    }
+}
+
+impl From<hecs::Entity> for Entity {
+    #[inline]
+    fn from(value: hecs::Entity) -> Self {
+        Self(value)
+    }
+}
+
+/// Returns the first entity that was given the name `name`, if any. See [`World::find_by_name`]
+#[inline]
+pub fn find_by_name(name: &str) -> Option<Entity> {
+    crate::world::get_world().find_by_name(name)
+}
+
+/// Returns all entities with the name `name`. See [`World::find_all_by_name`]
+#[inline]
+pub fn find_all_by_name(name: &str) -> Vec<Entity> {
+    crate::world::get_world().find_all_by_name(name)
+}
+
 impl Entity {
     /// Spawns a new entity in the game world with an identity rotation and position, and position 0
     #[inline]
@@ -149,7 +257,78 @@ impl Entity {
         new_entity
     }
 
-    /// Destroys an entity and removes its components
+    /// Makes `parent` the parent of this entity in the transform hierarchy. If `parent` is [`None`],
+    /// the entity is detached from its current parent and becomes a root entity.
+    ///
+    /// Both entities must have a [`Transform`]. The local transform of this entity is kept, meaning
+    /// that it will move along with its new parent. Like components, the change is not applied immediately,
+    /// but is processed right before the next frame-phase callback.
+    #[expect(
+        clippy::return_self_not_must_use,
+        reason = "Not required, just useful for chaining"
+    )]
+    pub fn set_parent(self, parent: Option<Entity>) -> Self {
+        match parent {
+            Some(parent) => log::debug!("Setting parent of entity {self} to {parent}"),
+            None => log::debug!("Removing parent of entity {self}"),
+        }
+
+        ENTITY_QUEUES
+            .set_parent_queue
+            .send((self, parent))
+            .expect("Runtime stopped");
+
+        self
+    }
+
+    /// Removes the component of type `C` from the entity. Like adding components, the removal is not
+    /// applied immediately, but is processed right before the next frame-phase callback, in the order
+    /// additions and removals were requested in.
+    ///
+    /// Removing a [`Transform`] detaches the entity from its parent and children
+    #[expect(
+        clippy::return_self_not_must_use,
+        reason = "Not required, just useful for chaining"
+    )]
+    pub fn remove_component<C: Component>(self) -> Self {
+        log::debug!(
+            "Removing component {} from entity {self}",
+            core::any::type_name::<C>()
+        );
+
+        ENTITY_QUEUES
+            .component_change_queue
+            .send(ComponentChange::remove::<C>(self))
+            .expect("Runtime stopped");
+
+        self
+    }
+
+    /// Returns whether the entity currently exists in the world. Entities that were spawned
+    /// this frame are alive right away, but do not have their components yet
+    #[inline]
+    pub fn is_alive(self) -> bool {
+        crate::world::get_world().contains(self)
+    }
+
+    /// Returns whether the entity currently has a component of type `C`
+    #[inline]
+    pub fn has_component<C: Component>(self) -> bool {
+        crate::world::get_world().has_component::<C>(self)
+    }
+
+    /// Calls `f` with the component of type `C` of this entity, and returns its result.
+    /// Returns [`None`] if the entity has no such component.
+    ///
+    /// Reads the world immediately, so it must not be called from exclusive systems, nor from
+    /// systems that mutably borrow `C`
+    pub fn with_component<C: Component, R>(self, f: impl FnOnce(&C) -> R) -> Option<R> {
+        crate::world::get_world()
+            .component::<C>(self)
+            .map(|component| f(&component))
+    }
+
+    /// Destroys an entity and removes its components. Any children of the entity are destroyed as well
     pub fn destroy(self) {
         let entity = self;
         log::debug!("Destroying entity {entity}");
@@ -168,28 +347,69 @@ impl Entity {
         reason = "Not required, just useful for chaining"
     )]
     pub fn add_component<C: Component>(self, component: C) -> Self {
-        let mut builder = hecs::EntityBuilder::new();
+        component::queue_default_component_systems::<C>();
 
-        builder.add(component);
+        ENTITY_QUEUES
+            .component_change_queue
+            .send(ComponentChange::add(self, component))
+            .expect("Runtime stopped");
 
-        if component::should_insert_default_component_systems::<C>() {
-            log::debug!(
-                "Adding default component systems for {}",
-                core::any::type_name::<C>()
-            );
+        self
+    }
+}
 
-            let mut manifest = SystemManifest::empty();
+#[cfg(test)]
+mod test {
+    use std::sync::mpsc::channel;
 
-            C::insert_default_component_systems(&mut manifest);
+    use super::ComponentChange;
+    use super::Entity;
+    use super::EntityManager;
+    use super::process_changes;
+    use crate::component::Component;
+    use crate::world::World;
 
-            crate::runtime::send_to_main_thread(MainThreadEvent::AddSystem(manifest));
-        }
+    struct CompA;
 
-        ENTITY_QUEUES
-            .new_component_queue
-            .send((self, builder))
-            .expect("Runtime stopped");
+    impl Component for CompA {
+        const ID: uuid::NonNilUuid =
+            uuid::NonNilUuid::new(uuid::uuid!("9b1f4c1e-7d0a-4c36-8a55-2f5d3c9e8b12")).unwrap();
+    }
 
-        self
+    #[test]
+    fn test_component_changes_in_request_order() {
+        // Registered up front, as queueing the default systems needs a running runtime
+        crate::component::should_insert_default_component_systems::<CompA>();
+
+        let (changes, component_changes) = channel();
+        let manager = EntityManager {
+            component_changes,
+            new_parents: channel().1,
+            entities_to_destroy: channel().1,
+        };
+
+        let mut world = World::new();
+        let entity = Entity(world.ecs.spawn(()));
+
+        changes
+            .send(ComponentChange::remove::<CompA>(entity))
+            .unwrap();
+        changes.send(ComponentChange::add(entity, CompA)).unwrap();
+        process_changes(&mut world, &manager);
+        assert!(world.has_component::<CompA>(entity));
+
+        changes
+            .send(ComponentChange::remove::<CompA>(entity))
+            .unwrap();
+        changes.send(ComponentChange::add(entity, CompA)).unwrap();
+        process_changes(&mut world, &manager);
+        assert!(world.has_component::<CompA>(entity));
+
+        changes.send(ComponentChange::add(entity, CompA)).unwrap();
+        changes
+            .send(ComponentChange::remove::<CompA>(entity))
+            .unwrap();
+        process_changes(&mut world, &manager);
+        assert!(!world.has_component::<CompA>(entity));
     }
 }
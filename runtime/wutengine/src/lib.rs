@@ -9,14 +9,20 @@ pub mod component;
 pub mod entity;
 pub mod graphics;
 pub mod profiling;
+pub mod recording;
 pub mod runtime;
+pub mod state;
 pub mod system;
+pub mod timer;
 pub mod window;
 pub mod world;
 
 #[cfg(feature = "development_overlay")]
 pub mod development_overlay;
 
+#[cfg(test)]
+mod test_util;
+
 #[doc(inline)]
 pub use hecs;
 
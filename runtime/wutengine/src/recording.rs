@@ -0,0 +1,374 @@
+//! Deterministic recording and replaying of input and frame times, for reproducing bugs.
+//!
+//! A [`Recording`] contains the raw input and real-time delta of every frame, and the [`rng_seed`]
+//! of the session. Replaying it feeds the frames back in order, ignoring live input and measured time.
+//! As long as the game only uses engine time, engine input and random number generators seeded with
+//! [`rng_seed`], a replay reproduces the recorded session frame by frame.
+//!
+//! Set the config key `wutengine.recording.record` to a file path to record the whole session to
+//! that file, or set `wutengine.recording.replay` to the path of a recording to replay it
+
+use core::hash::BuildHasher;
+use core::sync::atomic::AtomicU64;
+use core::sync::atomic::Ordering;
+use core::time::Duration;
+use std::hash::RandomState;
+use std::path::Path;
+use std::sync::LazyLock;
+use std::sync::Mutex;
+use std::time::Instant;
+use std::time::SystemTime;
+
+use crate::input::recording::RecordedInput;
+
+/// The magic bytes every recording starts with
+const RECORDING_MAGIC: [u8; 4] = *b"WERC";
+
+/// The version of the recording format written by this version of WutEngine
+pub const RECORDING_FORMAT_VERSION: u32 = 1;
+
+/// The config key of the file the session is recorded to
+const RECORD_PATH_KEY: &str = "wutengine.recording.record";
+
+/// The config key of the recording that is replayed
+const REPLAY_PATH_KEY: &str = "wutengine.recording.replay";
+
+/// The RNG seed of this session
+static SESSION_SEED: LazyLock<AtomicU64> =
+    LazyLock::new(|| AtomicU64::new(RandomState::new().hash_one(SystemTime::now())));
+
+/// The active recording or replay, if any
+static RECORDER: Mutex<RecorderState> = Mutex::new(RecorderState::Idle);
+
+/// An error while reading or writing a [`Recording`]
+#[derive(Debug, derive_more::Error, derive_more::Display, derive_more::From)]
+pub enum RecordingErr {
+    /// The recording could not be encoded or decoded
+    #[display("Failed to encode or decode recording: {_0}")]
+    Encoding(postcard::Error),
+
+    /// The recording file could not be read or written
+    #[display("Failed to read or write recording file: {_0}")]
+    Io(std::io::Error),
+
+    /// The data is not a WutEngine recording
+    #[display("Data is not a WutEngine recording")]
+    InvalidHeader,
+
+    /// The recording was written with an unsupported format version
+    #[display(
+        "Unsupported recording format version {_0}. Expected at most {RECORDING_FORMAT_VERSION}"
+    )]
+    #[from(skip)]
+    UnsupportedVersion(#[error(not(source))] u32),
+}
+
+/// A recorded session, see the [module documentation](self)
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct Recording {
+    /// The RNG seed of the recorded session
+    seed: u64,
+
+    /// The recorded frames, in order
+    frames: Vec<RecordedFrame>,
+}
+
+/// A single frame of a [`Recording`]
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct RecordedFrame {
+    /// The real time that passed since the previous frame, in nanoseconds. See
+    /// [`crate::time::unscaled_delta_nanos`]
+    pub delta_nanos: u64,
+
+    /// The raw input applied right before the frame's simulation
+    pub input: RecordedInput,
+}
+
+impl Recording {
+    /// Returns the RNG seed of the recorded session. See [`rng_seed`]
+    #[inline]
+    pub const fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    /// Returns the recorded frames, in order
+    #[inline]
+    pub fn frames(&self) -> &[RecordedFrame] {
+        &self.frames
+    }
+
+    /// Encodes the recording as a versioned binary blob
+    pub fn to_bytes(&self) -> Result<Vec<u8>, RecordingErr> {
+        let mut bytes = Vec::from(RECORDING_MAGIC);
+        bytes.extend_from_slice(&RECORDING_FORMAT_VERSION.to_le_bytes());
+
+        Ok(postcard::to_extend(self, bytes)?)
+    }
+
+    /// Decodes a recording from a binary blob written by [`Self::to_bytes`]
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, RecordingErr> {
+        let (magic, rest) = bytes
+            .split_first_chunk::<4>()
+            .ok_or(RecordingErr::InvalidHeader)?;
+
+        if *magic != RECORDING_MAGIC {
+            return Err(RecordingErr::InvalidHeader);
+        }
+
+        let (version, data) = rest
+            .split_first_chunk::<4>()
+            .ok_or(RecordingErr::InvalidHeader)?;
+
+        let version = u32::from_le_bytes(*version);
+
+        if version > RECORDING_FORMAT_VERSION {
+            return Err(RecordingErr::UnsupportedVersion(version));
+        }
+
+        Ok(postcard::from_bytes(data)?)
+    }
+
+    /// Writes the recording to the file at `path`, creating its parent directories if needed
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), RecordingErr> {
+        let path = path.as_ref();
+
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+
+        std::fs::write(path, self.to_bytes()?)?;
+
+        log::info!(
+            "Saved recording of {} frames to {}",
+            self.frames.len(),
+            path.display()
+        );
+
+        Ok(())
+    }
+
+    /// Reads a recording written by [`Self::save`] from the file at `path`
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, RecordingErr> {
+        Self::from_bytes(&std::fs::read(path)?)
+    }
+}
+
+/// Whether a session is being recorded or replayed
+#[derive(Debug)]
+enum RecorderState {
+    /// Neither recording nor replaying
+    Idle,
+
+    /// Recording the frames of the session
+    Recording(Recording),
+
+    /// Replaying a recording
+    Replaying {
+        /// The recording being replayed
+        recording: Recording,
+
+        /// The index of the next frame to replay
+        next: usize,
+    },
+}
+
+/// Returns the seed that random number generators used by gameplay code should be seeded with.
+/// The seed is random for each session, unless a recording is being replayed, in which case it
+/// is the seed of the recorded session
+pub fn rng_seed() -> u64 {
+    SESSION_SEED.load(Ordering::Acquire)
+}
+
+/// Starts recording every following frame. Stops any active replay. Should be called before
+/// the first frame, or input state from before the recording may be missing from replays
+pub fn start_recording() {
+    stop_replay();
+
+    crate::input::recording::start_recording();
+
+    *RECORDER.lock().unwrap() = RecorderState::Recording(Recording {
+        seed: rng_seed(),
+        frames: Vec::new(),
+    });
+}
+
+/// Stops recording, and returns the recorded frames. Returns [`None`] if nothing was being recorded
+pub fn stop_recording() -> Option<Recording> {
+    let mut state = RECORDER.lock().unwrap();
+
+    if !matches!(*state, RecorderState::Recording(_)) {
+        return None;
+    }
+
+    crate::input::recording::stop_recording();
+
+    match core::mem::replace(&mut *state, RecorderState::Idle) {
+        RecorderState::Recording(recording) => Some(recording),
+        _ => unreachable!("Checked above"),
+    }
+}
+
+/// Returns `true` if frames are being recorded
+pub fn is_recording() -> bool {
+    matches!(*RECORDER.lock().unwrap(), RecorderState::Recording(_))
+}
+
+/// Starts replaying `recording` from its first frame, and sets [`rng_seed`] to its seed. Stops
+/// any active recording. When all frames were replayed, live input and time are used again
+pub fn start_replay(recording: Recording) {
+    drop(stop_recording());
+
+    log::info!("Replaying recording of {} frames", recording.frames.len());
+
+    SESSION_SEED.store(recording.seed, Ordering::Release);
+
+    crate::input::recording::start_replay();
+
+    *RECORDER.lock().unwrap() = RecorderState::Replaying { recording, next: 0 };
+}
+
+/// Stops replaying, after which live input and time are used again
+pub fn stop_replay() {
+    let mut state = RECORDER.lock().unwrap();
+
+    if matches!(*state, RecorderState::Replaying { .. }) {
+        crate::input::recording::stop_replay();
+
+        *state = RecorderState::Idle;
+    }
+}
+
+/// Returns `true` if a recording is being replayed
+pub fn is_replaying() -> bool {
+    matches!(*RECORDER.lock().unwrap(), RecorderState::Replaying { .. })
+}
+
+/// How the time advances at the start of a frame
+#[derive(Debug, Clone, Copy)]
+pub(crate) enum FrameTime {
+    /// By the real time passed until the given start of the frame
+    Measured(Instant),
+
+    /// By exactly the given duration, regardless of the real time
+    Fixed(Duration),
+}
+
+impl FrameTime {
+    /// Updates the time for a new frame. Returns the amount of fixed updates to run this frame
+    fn update_time(self) -> u64 {
+        match self {
+            Self::Measured(now) => crate::time::update_frame(now),
+            Self::Fixed(delta) => crate::time::update_frame_with_delta(
+                u64::try_from(delta.as_nanos()).unwrap_or(u64::MAX),
+            ),
+        }
+    }
+}
+
+/// Updates the time for a new frame, and records or replays the frame's input. Returns the amount
+/// of fixed updates to run this frame
+pub(crate) fn update_frame(frame_time: FrameTime) -> u64 {
+    profiling::function_scope!();
+
+    let mut state = RECORDER.lock().unwrap();
+
+    match &mut *state {
+        RecorderState::Idle => frame_time.update_time(),
+        RecorderState::Recording(recording) => {
+            let input = crate::input::recording::take_recorded_frame();
+            let num_fixed_updates = frame_time.update_time();
+
+            recording.frames.push(RecordedFrame {
+                delta_nanos: crate::time::unscaled_delta_nanos(),
+                input,
+            });
+
+            num_fixed_updates
+        }
+        RecorderState::Replaying { recording, next } => {
+            if let Some(frame) = recording.frames.get(*next) {
+                *next += 1;
+
+                crate::input::recording::replay_frame(&frame.input);
+
+                return crate::time::update_frame_with_delta(frame.delta_nanos);
+            }
+
+            log::info!("Finished replaying recording of {} frames", *next);
+
+            crate::input::recording::stop_replay();
+
+            *state = RecorderState::Idle;
+
+            frame_time.update_time()
+        }
+    }
+}
+
+/// Starts recording or replaying as configured. Called by the runtime once, right before the first frame
+pub(crate) fn start_configured() {
+    if let Some(path) = crate::config::try_get::<String>(REPLAY_PATH_KEY) {
+        match Recording::load(&path) {
+            Ok(recording) => start_replay(recording),
+            Err(e) => log::error!("Failed to load recording {path}: {e}"),
+        }
+    } else if crate::config::try_get::<String>(RECORD_PATH_KEY).is_some() {
+        start_recording();
+    }
+}
+
+/// Saves the configured recording, if any. Called by the runtime once, right before it exits
+pub(crate) fn finish_configured() {
+    let Some(path) = crate::config::try_get::<String>(RECORD_PATH_KEY) else {
+        return;
+    };
+
+    if let Some(recording) = stop_recording()
+        && let Err(e) = recording.save(&path)
+    {
+        log::error!("Failed to save recording to {path}: {e}");
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::RecordedFrame;
+    use super::RecordedInput;
+    use super::Recording;
+    use super::RecordingErr;
+
+    #[test]
+    fn test_recording_roundtrip() {
+        let recording = Recording {
+            seed: 0xdead_beef,
+            frames: vec![
+                RecordedFrame {
+                    delta_nanos: 16_666_667,
+                    input: RecordedInput::default(),
+                },
+                RecordedFrame {
+                    delta_nanos: 17_000_000,
+                    input: RecordedInput::default(),
+                },
+            ],
+        };
+
+        let bytes = recording.to_bytes().unwrap();
+        let decoded = Recording::from_bytes(&bytes).unwrap();
+
+        assert_eq!(recording.seed(), decoded.seed());
+        assert_eq!(
+            vec![16_666_667, 17_000_000],
+            decoded
+                .frames()
+                .iter()
+                .map(|frame| frame.delta_nanos)
+                .collect::<Vec<_>>()
+        );
+
+        assert!(matches!(
+            Recording::from_bytes(b"WESV\x01\x00\x00\x00"),
+            Err(RecordingErr::InvalidHeader)
+        ));
+    }
+}
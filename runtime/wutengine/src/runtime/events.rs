@@ -19,13 +19,9 @@ fn send_to_main_runtime_collector<T: MainRuntimeEvent + Clone>(event: &T) {
     MAIN_RUNTIME_EVENTS.borrow_mut().push(cloned);
 }
 
-pub(super) fn add_event_listeners(runtime: &mut Runtime) {
+pub(super) fn add_event_listeners() {
     _ = wutengine_event::subscribe::<AddOnExitHandler>(send_to_main_runtime_collector);
     _ = wutengine_event::subscribe::<AddOnExitRequestedHandler>(send_to_main_runtime_collector);
-
-    runtime
-        .on_exit_handlers
-        .push(Arc::new(wutengine_graphics::persist_pipeline_cache));
 }
 
 pub(crate) trait MainRuntimeEvent: wutengine_event::Event {
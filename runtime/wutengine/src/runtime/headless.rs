@@ -0,0 +1,121 @@
+//! The headless frame loop. Drives the [`Runtime`] without [`winit`], windows or a graphics context
+
+use std::sync::mpsc::Receiver;
+use std::time::Instant;
+
+use wutengine_util::InitOnce;
+use wutengine_util::assert_main_thread;
+
+use crate::input;
+use crate::recording::FrameTime;
+
+use super::HeadlessConfig;
+use super::MAIN_THREAD_EVENTS;
+use super::MainThreadEvent;
+use super::MainThreadEventTarget;
+use super::Runtime;
+
+/// Runs the runtime headless until the configured amount of frames have passed, or until an exit
+/// was requested. Blocks until the runtime stops
+pub(super) fn run_headless(mut runtime: Runtime, config: HeadlessConfig) {
+    let (sender, receiver) = std::sync::mpsc::channel();
+
+    InitOnce::init(&MAIN_THREAD_EVENTS, MainThreadEventTarget::Headless(sender));
+
+    log::info!("Running WutEngine headless");
+
+    runtime.finish_initialization();
+
+    let mut frames: u64 = 0;
+
+    loop {
+        if config
+            .max_frames
+            .is_some_and(|max_frames| frames >= max_frames)
+        {
+            log::info!("Stopping the headless WutEngine runtime after {frames} frames");
+            break;
+        }
+
+        if !runtime.handle_headless_events(&receiver) {
+            break;
+        }
+
+        frames += 1;
+
+        let frame_time = config
+            .frame_delta
+            .map_or_else(|| FrameTime::Measured(Instant::now()), FrameTime::Fixed);
+
+        runtime.run_headless_frame(frame_time);
+    }
+
+    runtime.run_exit_handlers();
+}
+
+impl Runtime {
+    /// Handles all queued [`MainThreadEvent`]s. Returns `false` if the runtime should exit
+    fn handle_headless_events(&mut self, receiver: &Receiver<MainThreadEvent>) -> bool {
+        profiling::function_scope!();
+
+        let mut keep_running = true;
+
+        for event in receiver.try_iter() {
+            match event {
+                MainThreadEvent::AddSystem(manifest, insertion) => {
+                    self.systems.queue_system(manifest, insertion);
+                }
+                MainThreadEvent::UpdateSystem(id, update) => {
+                    self.systems.update_system(id, update);
+                }
+                MainThreadEvent::RunTask(task) => {
+                    task();
+                }
+                MainThreadEvent::RuntimeExitRequested(force) => {
+                    keep_running = keep_running && !self.should_exit(force);
+                }
+                MainThreadEvent::Wake => {}
+                MainThreadEvent::NewWindowRequested(window, _)
+                | MainThreadEvent::CloseWindow(window)
+                | MainThreadEvent::UpdateWindow(window, _)
+                | MainThreadEvent::ForceSurfaceReconfigure(window) => {
+                    log::warn!(
+                        "Ignoring event for window {window} because the runtime is headless"
+                    );
+                }
+            }
+        }
+
+        keep_running
+    }
+
+    /// Runs a single frame advancing the time by `frame_time`, skipping everything related to
+    /// windows and rendering
+    fn run_headless_frame(&mut self, frame_time: FrameTime) {
+        {
+            profiling::function_scope!();
+
+            assert_main_thread!();
+
+            crate::profiling::change_scope_active_status();
+
+            crate::event::handle_events();
+
+            self.handle_main_runtime_events();
+
+            self.run_simulation(frame_time);
+
+            self.publish_system_stats();
+
+            // No renderer consumes the draw commands, so drop them to keep the queue from growing
+            self.draw_commands.try_iter().for_each(drop);
+
+            input::end_frame();
+
+            self.frame_pacer.frame_rendered();
+            self.frame_pacer.wait_for_limit();
+        }
+
+        profiling::finish_frame!();
+    }
+}
@@ -1,5 +1,6 @@
 use alloc::sync::Arc;
 use core::sync::atomic::Ordering;
+use core::time::Duration;
 use std::collections::HashMap;
 use std::path::PathBuf;
 
@@ -8,14 +9,16 @@ use wutengine_util::InitOnce;
 
 use crate::entity;
 use crate::graphics;
-use crate::runtime::EVENT_LOOP_PROXY;
+use crate::runtime::MAIN_THREAD_EVENTS;
 use crate::runtime::MainThreadEvent;
+use crate::runtime::MainThreadEventTarget;
 use crate::runtime::Runtime;
 use crate::runtime::WUTENGINE_RUNNING;
 use crate::system;
+use crate::system::ScheduleErr;
 use crate::window;
 
-use super::SystemManifest;
+use super::Plugin;
 
 /// An error while starting the WutEngine runtime with [`run`]
 #[derive(Debug, derive_more::Error, derive_more::Display, derive_more::From)]
@@ -27,6 +30,31 @@ pub enum RuntimeStartErr {
     /// Error running the winit event loop
     #[display("Error running the winit event loop: {_0}")]
     EventLoop(EventLoopError),
+
+    /// The systems added by the plugins could not be scheduled
+    #[display("Failed to schedule plugin systems: {_0}")]
+    Schedule(ScheduleErr),
+
+    /// Two plugins with the same name were added
+    #[display("Plugin `{_0}` was added more than once")]
+    #[from(ignore)]
+    DuplicatePlugin(#[error(not(source))] &'static str),
+
+    /// A plugin depends on a plugin that was not added
+    #[display("Plugin `{plugin}` depends on plugin `{dependency}`, which was not added")]
+    #[from(ignore)]
+    MissingPluginDependency {
+        /// The name of the depending plugin
+        plugin: &'static str,
+
+        /// The name of the missing dependency
+        dependency: &'static str,
+    },
+
+    /// The dependencies of the plugins form a cycle
+    #[display("Plugins form a dependency cycle: {}", _0.join(", "))]
+    #[from(ignore)]
+    PluginDependencyCycle(#[error(not(source))] Vec<&'static str>),
 }
 
 /// How often the frame loop runs
@@ -43,8 +71,35 @@ pub enum FrameFrequency {
     WaitIndefinitely,
 }
 
+/// How the runtime drives its frames
+#[derive(Debug, Clone, Copy, Default)]
+pub enum RuntimeMode {
+    /// Run a [`winit`] event loop with windows and a graphics context. Default for games
+    #[default]
+    Windowed,
+
+    /// Run without any windows, surfaces or graphics context. Only the systems (excluding
+    /// [`crate::system::Phase::PreRender`] and the custom phases around it), physics, events
+    /// and entity changes are processed.
+    ///
+    /// Useful for dedicated servers, simulation tests and batch tooling
+    Headless(HeadlessConfig),
+}
+
+/// Configuration for [`RuntimeMode::Headless`]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct HeadlessConfig {
+    /// The number of frames to run before exiting. If [`None`], runs until [`crate::runtime::exit`]
+    /// is called
+    pub max_frames: Option<u64>,
+
+    /// If set, each frame advances the engine time by exactly this duration instead of the measured
+    /// real time, making the simulation independent of how fast the host runs it
+    pub frame_delta: Option<Duration>,
+}
+
 /// The configuration used to start the WutEngine runtime
-#[derive(Debug, Clone)]
+#[derive(derive_more::Debug, Clone)]
 pub struct InitRuntimeConfig {
     /// The path to a config file, used for population the initial values of the [`crate::config`] module
     pub config_file: Option<PathBuf>,
@@ -53,8 +108,15 @@ pub struct InitRuntimeConfig {
     /// thus overrides its values
     pub config_overrides: HashMap<String, crate::config::toml::Value>,
 
-    /// How the engine handles frame updates
+    /// How the engine handles frame updates. Ignored in [`RuntimeMode::Headless`]
     pub frame_frequency: FrameFrequency,
+
+    /// Whether the engine runs windowed or headless
+    pub mode: RuntimeMode,
+
+    /// The plugins to build during startup. Built in dependency order, otherwise in the given order
+    #[debug("{:?}", plugins.iter().map(|plugin| plugin.name()).collect::<Vec<_>>())]
+    pub plugins: Vec<Arc<dyn Plugin>>,
 }
 
 impl Default for InitRuntimeConfig {
@@ -63,13 +125,19 @@ impl Default for InitRuntimeConfig {
             config_file: Some(PathBuf::from("wutengine.toml")),
             config_overrides: HashMap::default(),
             frame_frequency: FrameFrequency::default(),
+            mode: RuntimeMode::default(),
+            plugins: Vec::new(),
         }
     }
 }
 
-/// Data only relevant before/during application initialization in [`winit::application::ApplicationHandler::resumed`]
+/// Data only relevant before/during application initialization in [`winit::application::ApplicationHandler::resumed`],
+/// or at the start of the headless frame loop
 pub(super) struct InitializationData {
     pub(super) post_start_callback: Option<Box<dyn FnOnce()>>,
+
+    /// Plugin setup that requires a running runtime, run right before [`Self::post_start_callback`]
+    pub(super) plugin_startup: Vec<Box<dyn FnOnce()>>,
 }
 
 /// Starts and runs the WutEngine runtime. MUST be called from the main thread
@@ -107,10 +175,14 @@ pub fn run(
 
     log::info!("Starting WutEngine");
 
+    let headless = matches!(config.mode, RuntimeMode::Headless(_));
+
+    // Nothing renders the overlay in a headless runtime, so its windows are never added
     #[cfg(feature = "development_overlay")]
-    {
+    if !headless {
         use crate::development_overlay::ConfigOverlay;
         use crate::development_overlay::GamepadOverlay;
+        use crate::development_overlay::SystemStatsOverlay;
 
         crate::development_overlay::init(Some(|_| {
             crate::runtime::request_frame();
@@ -121,14 +193,18 @@ pub fn run(
         crate::development_overlay::add_development_overlay_window(
             crate::profiling::development_overlay::ProfilingOverlay::default(),
         );
+        crate::development_overlay::add_development_overlay_window(SystemStatsOverlay::default());
     }
 
+    let (plugins, plugin_context) = super::plugin::build_plugins(config.plugins)?;
+
     wutengine_task::init_thread_pool();
 
     let mut runtime = Runtime {
         frame_pacer: window::pacer::FramePacer::default(),
         initialization_data: Some(Box::new(InitializationData {
             post_start_callback: post_start,
+            plugin_startup: plugin_context.startup,
         })),
         entity_manager: entity::initialize(),
         systems: system::SystemManager::new(),
@@ -137,25 +213,54 @@ pub fn run(
         frame_frequency: config.frame_frequency,
         on_exit_requested_handlers: Vec::new(),
         on_exit_handlers: Vec::new(),
+        plugins,
+        last_physics_sync: 0,
     };
 
-    runtime.systems.build_schedule(SystemManifest::empty());
+    runtime
+        .systems
+        .build_schedule(plugin_context.manifest)
+        .map_err(|e| Box::new(e.into()))?;
 
-    log::debug!("Final schedule:\n{}", runtime.systems.dump());
+    let schedule = runtime.systems.schedule_info();
+    log::debug!("Final schedule:\n{schedule}");
+    crate::system::publish_schedule(schedule);
 
     window::manager::init();
-    crate::input::init();
+
+    // A headless runtime has no input devices or audio output, so don't open them
+    if headless {
+        crate::input::init_without_gamepads();
+    } else {
+        crate::input::init();
+    }
+
     crate::physics::init();
-    crate::audio::init();
+
+    if headless {
+        crate::audio::init_without_output();
+    } else {
+        crate::audio::init();
+    }
+
     crate::world::init();
 
+    if let RuntimeMode::Headless(headless_config) = config.mode {
+        super::headless::run_headless(runtime, headless_config);
+
+        return Ok(());
+    }
+
     let event_loop = winit::event_loop::EventLoop::<MainThreadEvent>::with_user_event()
         .build()
         .map_err(|e| Box::new(e.into()))?;
 
     let event_loop_proxy = event_loop.create_proxy();
 
-    InitOnce::init(&EVENT_LOOP_PROXY, event_loop_proxy);
+    InitOnce::init(
+        &MAIN_THREAD_EVENTS,
+        MainThreadEventTarget::EventLoop(event_loop_proxy),
+    );
 
     let control_flow = match runtime.frame_frequency {
         FrameFrequency::Fast => winit::event_loop::ControlFlow::Poll,
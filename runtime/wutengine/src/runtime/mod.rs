@@ -1,8 +1,6 @@
 //! The main WutEngine runtime, responsible for the application lifecycle
 
 use crate::builtins::components::Transform;
-use crate::builtins::components::physics::ColliderSet2D;
-use crate::builtins::components::physics::ColliderSet3D;
 use crate::builtins::components::rendering::ActiveCameraRenderPass;
 use crate::builtins::components::rendering::Camera;
 use crate::builtins::components::rendering::CameraRenderPass;
@@ -13,6 +11,8 @@ use crate::graphics;
 use crate::graphics::DrawCommand;
 use crate::graphics::RenderPassInfo;
 use crate::input;
+use crate::recording::FrameTime;
+use crate::system;
 use crate::system::Phase;
 use crate::system::SystemManager;
 use crate::time;
@@ -22,9 +22,11 @@ use crate::world;
 use alloc::sync::Arc;
 use core::any::TypeId;
 use core::sync::atomic::AtomicBool;
+use core::time::Duration;
 use events::AddOnExitHandler;
 use events::AddOnExitRequestedHandler;
 use std::sync::mpsc::Receiver;
+use std::sync::mpsc::Sender;
 use std::time::Instant;
 use wutengine_graphics::label;
 use wutengine_graphics::renderpass::RenderPass;
@@ -36,29 +38,47 @@ use rayon::prelude::*;
 
 mod api;
 mod events;
+mod headless;
 mod init;
+mod plugin;
 mod system_builder;
+mod wait;
 mod winit_app;
 
 pub use api::*;
 pub use init::*;
+pub use plugin::*;
 
 pub use system_builder::*;
+pub use wait::*;
 
 pub(crate) use winit_app::MainThreadEvent;
 
-static EVENT_LOOP_PROXY: InitOnce<winit::event_loop::EventLoopProxy<MainThreadEvent>> =
-    InitOnce::new_checked();
+static MAIN_THREAD_EVENTS: InitOnce<MainThreadEventTarget> = InitOnce::new_checked();
 static WUTENGINE_RUNNING: AtomicBool = AtomicBool::new(false);
 
-/// Notifies the main [`winit`] event loop of a given event.
+/// The receiver of [`MainThreadEvent`]s, depending on how the runtime was started
+#[derive(Debug)]
+enum MainThreadEventTarget {
+    /// The main [`winit`] event loop
+    EventLoop(winit::event_loop::EventLoopProxy<MainThreadEvent>),
+
+    /// The headless frame loop
+    Headless(Sender<MainThreadEvent>),
+}
+
+/// Notifies the main [`winit`] event loop, or the headless frame loop, of a given event.
 ///
 /// If the loop was already closed, does nothing and logs an error
 pub(crate) fn send_to_main_thread(event: MainThreadEvent) {
-    if let Err(e) = EVENT_LOOP_PROXY.send_event(event) {
+    let unsent = match &*MAIN_THREAD_EVENTS {
+        MainThreadEventTarget::EventLoop(proxy) => proxy.send_event(event).err().map(|e| e.0),
+        MainThreadEventTarget::Headless(sender) => sender.send(event).err().map(|e| e.0),
+    };
+
+    if let Some(event) = unsent {
         log::error!(
-            "Failed to notify event loop of event {:#?} because it was already closed",
-            e.0
+            "Failed to notify event loop of event {event:#?} because it was already closed"
         );
     }
 }
@@ -90,6 +110,13 @@ pub(crate) struct Runtime {
 
     /// On-exit handlers
     on_exit_handlers: Vec<Arc<dyn Fn() + Send + Sync + 'static>>,
+
+    /// The plugins, in the order they were built
+    plugins: Vec<Arc<dyn Plugin>>,
+
+    /// The change tick at which the entities were last synced to the physics world. Only colliders
+    /// and transforms changed after it are synced again
+    last_physics_sync: u64,
 }
 
 ///TODO: Combine with [`ActiveCameraRenderPass`] with a generic?
@@ -137,6 +164,8 @@ impl Runtime {
 
             self.run_systems_and_logic();
 
+            self.publish_system_stats();
+
             self.render_all_windows(&surfaces);
 
             for (_, surface) in surfaces {
@@ -152,6 +181,77 @@ impl Runtime {
         profiling::finish_frame!();
     }
 
+    /// Runs the initialization shared by all runtime modes. Must be called once, after the
+    /// mode-specific subsystems were initialized, but before the first frame
+    fn finish_initialization(&mut self) {
+        let Some(mut init_data) = self.initialization_data.take() else {
+            log::error!("Runtime was already initialized");
+            return;
+        };
+
+        // Initialize the time manager later here, right before the runtime starts running frames
+        time::init();
+
+        // Started before any user code runs, so that it sees the replayed RNG seed
+        crate::recording::start_configured();
+
+        if let Some(fps_limit) = crate::config::try_get::<u64>("wutengine.window.fps_limit")
+            && fps_limit != 0
+        {
+            self.frame_pacer
+                .set_frame_interval(Some(Duration::from_secs_f64(1.0 / (fps_limit as f64))));
+        }
+
+        events::add_event_listeners();
+
+        for startup in init_data.plugin_startup.drain(..) {
+            startup();
+        }
+
+        // Must be called last, so we know the engine setup is done
+        if let Some(post_init_callback) = init_data.post_start_callback.take() {
+            post_init_callback();
+        }
+    }
+
+    /// Checks whether a requested exit should go through, by running the on-exit-requested handlers if the
+    /// exit is not forced
+    fn should_exit(&self, force: bool) -> bool {
+        log::debug!("Runtime exit was requested. Force: {force}.");
+
+        let mut should_exit = true;
+
+        if !force {
+            // If any of the handlers return true (cancel exit), we do not actually exit if not forced
+
+            for on_exit_requested_handler in self.on_exit_requested_handlers.iter().map(Arc::as_ref)
+            {
+                should_exit = should_exit && !(on_exit_requested_handler());
+            }
+        }
+
+        should_exit
+    }
+
+    /// Runs the on-exit handlers. Called once, right before the runtime stops
+    fn run_exit_handlers(&mut self) {
+        log::info!("Exiting WutEngine");
+
+        for handler in self.on_exit_handlers.drain(..) {
+            handler();
+        }
+
+        crate::recording::finish_configured();
+
+        for plugin in self.plugins.drain(..).rev() {
+            log::debug!("Cleaning up plugin {}", plugin.name());
+
+            plugin.cleanup();
+        }
+
+        log::logger().flush();
+    }
+
     fn handle_main_runtime_events(&mut self) {
         profiling::function_scope!();
 
@@ -163,25 +263,54 @@ impl Runtime {
     fn run_systems_and_logic(&mut self) {
         profiling::function_scope!();
 
-        let num_fixed_updates = time::update_frame(Instant::now());
+        self.run_simulation(FrameTime::Measured(Instant::now()));
+
+        self.run_phase_with_custom_phases(Phase::PreRender);
+    }
+
+    /// Runs all non-rendering systems and logic for a frame starting at `now`
+    fn run_simulation(&mut self, frame_time: FrameTime) {
+        profiling::function_scope!();
+
+        // Transitions are applied first, so the whole frame runs in the new state
+        crate::state::apply_transitions(&mut world::get_world_mut());
+
+        world::get_world().update_events();
+
+        let num_fixed_updates = crate::recording::update_frame(frame_time);
+
+        wait::wake_frame_waiters();
 
         for _ in 0..num_fixed_updates {
             self.run_physics_pipeline();
         }
 
-        self.run_phase_systems(Phase::Update);
+        crate::timer::run_timers(time::delta_nanos());
+
+        self.run_phase_with_custom_phases(Phase::Update);
+        self.run_phase_with_custom_phases(Phase::LateUpdate);
+
+        crate::builtins::components::transform::propagate_transforms(&mut world::get_world_mut());
+    }
+
+    /// Publishes the execution statistics of the systems, see [`crate::system::current_system_stats`]
+    fn publish_system_stats(&self) {
+        profiling::function_scope!();
 
-        self.run_phase_systems(Phase::LateUpdate);
+        let stats = self
+            .systems
+            .stats(&world::get_world(), system::count_matching_entities());
 
-        self.run_phase_systems(Phase::PreRender);
+        system::publish_stats(stats);
     }
 
     fn run_physics_pipeline(&mut self) {
         profiling::function_scope!();
 
+        self.run_custom_phases(Phase::FixedUpdate, false);
         self.run_phase_systems(Phase::FixedUpdate);
 
-        Self::write_physics_state();
+        self.write_physics_state();
 
         {
             profiling::scope!("Run physics step");
@@ -191,41 +320,75 @@ impl Runtime {
 
         Self::read_physics_state();
 
+        {
+            let mut world = world::get_world_mut();
+
+            crate::builtins::components::transform::propagate_transforms(&mut world);
+            crate::builtins::components::interpolated_transform::capture_fixed_poses(&mut world);
+        }
+
+        self.run_custom_phases(Phase::FixedUpdate, true);
+
         time::update_fixed();
+
+        wait::wake_fixed_update_waiters();
     }
 
-    fn write_physics_state() {
+    fn write_physics_state(&mut self) {
         profiling::function_scope!();
 
+        let last_sync = core::mem::replace(&mut self.last_physics_sync, system::current_tick());
+
+        // Changes made after this sync, like those by the transform propagation after the step, get a later tick
+        system::advance_tick();
+
         crate::physics::update_physics_world(
             #[cfg(feature = "phys2d")]
             |updater_2d| {
                 use crate::builtins::components::Transform;
                 use crate::builtins::components::physics::ColliderSet2D;
+                use crate::system::ComponentTicks;
 
                 let world = world::get_world();
 
-                let mut query = world
-                    .ecs
-                    .query::<(&mut ColliderSet2D, Option<&Transform>)>();
+                let mut query = world.ecs.query::<(
+                    &mut ColliderSet2D,
+                    Option<&Transform>,
+                    Option<&ComponentTicks<ColliderSet2D>>,
+                    Option<&ComponentTicks<Transform>>,
+                )>();
+
+                for (set2d, xform, set_ticks, xform_ticks) in &mut query {
+                    let changed = set_ticks.is_none_or(|ticks| ticks.is_changed_after(last_sync))
+                        || xform_ticks.is_none_or(|ticks| ticks.is_changed_after(last_sync));
 
-                for (set2d, xform) in &mut query {
-                    set2d.sync_to_physics_world(xform, updater_2d);
+                    if changed {
+                        set2d.sync_to_physics_world(xform, updater_2d);
+                    }
                 }
             },
             #[cfg(feature = "phys3d")]
             |updater_3d| {
                 use crate::builtins::components::Transform;
                 use crate::builtins::components::physics::ColliderSet3D;
+                use crate::system::ComponentTicks;
 
                 let world = world::get_world();
 
-                let mut query = world
-                    .ecs
-                    .query::<(&mut ColliderSet3D, Option<&Transform>)>();
+                let mut query = world.ecs.query::<(
+                    &mut ColliderSet3D,
+                    Option<&Transform>,
+                    Option<&ComponentTicks<ColliderSet3D>>,
+                    Option<&ComponentTicks<Transform>>,
+                )>();
 
-                for (set3d, xform) in &mut query {
-                    set3d.sync_to_physics_world(xform, updater_3d);
+                for (set3d, xform, set_ticks, xform_ticks) in &mut query {
+                    let changed = set_ticks.is_none_or(|ticks| ticks.is_changed_after(last_sync))
+                        || xform_ticks.is_none_or(|ticks| ticks.is_changed_after(last_sync));
+
+                    if changed {
+                        set3d.sync_to_physics_world(xform, updater_3d);
+                    }
                 }
             },
         );
@@ -234,34 +397,71 @@ impl Runtime {
     fn read_physics_state() {
         profiling::function_scope!();
 
-        let mut world = world::get_world_mut();
-
+        #[cfg(feature = "phys2d")]
         {
+            use crate::builtins::components::physics::ColliderSet2D;
+            use crate::system::Mut;
+
             profiling::scope!("Read 2D state");
+
+            let mut world = world::get_world_mut();
+
+            // Through `Mut`, so that only the transforms that were actually moved are marked as changed
             let query = world
                 .ecs
-                .query_mut::<(&mut ColliderSet2D, Option<&mut Transform>)>();
+                .query_mut::<(&mut ColliderSet2D, Mut<Transform>)>();
 
-            for _val in query {}
+            crate::physics::phys2d::read_physics_world(|reader_2d| {
+                for (set2d, mut xform) in query {
+                    set2d.sync_from_physics_world(&mut xform, reader_2d);
+                }
+            });
         }
 
+        #[cfg(feature = "phys3d")]
         {
+            use crate::builtins::components::physics::ColliderSet3D;
+            use crate::system::Mut;
+
             profiling::scope!("Read 3D state");
+
+            let mut world = world::get_world_mut();
+
+            // Through `Mut`, so that only the transforms that were actually moved are marked as changed
             let query = world
                 .ecs
-                .query_mut::<(&mut ColliderSet3D, Option<&mut Transform>)>();
+                .query_mut::<(&mut ColliderSet3D, Mut<Transform>)>();
 
-            for _val in query {}
+            crate::physics::phys3d::read_physics_world(|reader_3d| {
+                for (set3d, mut xform) in query {
+                    set3d.sync_from_physics_world(&mut xform, reader_3d);
+                }
+            });
+        }
+    }
+
+    /// Runs the custom phases right before `phase`, then `phase` itself, then the custom phases right after it
+    fn run_phase_with_custom_phases(&mut self, phase: Phase) {
+        self.run_custom_phases(phase, false);
+        self.run_phase_systems(phase);
+        self.run_custom_phases(phase, true);
+    }
+
+    /// Runs the custom phases right before (`after == false`) or right after (`after == true`) the builtin `anchor` phase
+    fn run_custom_phases(&mut self, anchor: Phase, after: bool) {
+        for phase in self.systems.custom_phases(anchor, after) {
+            self.run_phase_systems(phase);
         }
     }
 
     fn run_phase_systems(&mut self, phase: Phase) {
         profiling::function_scope!(phase.str());
 
-        self.systems.update_schedule();
+        if self.systems.update_schedule() {
+            crate::system::publish_schedule(self.systems.schedule_info());
+        }
 
-        self.systems
-            .run_systems_for_phase(phase, &world::get_world());
+        self.systems.run_systems_for_phase(phase);
 
         entity::process_changes(&mut world::get_world_mut(), &self.entity_manager);
 
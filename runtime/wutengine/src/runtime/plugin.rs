@@ -0,0 +1,240 @@
+//! Plugins, packaging systems, render passes, overlays and config defaults into a single unit
+//! that can be passed to [`super::run`]
+
+use alloc::sync::Arc;
+use std::collections::HashMap;
+
+use crate::builtins::components::rendering::Camera;
+use crate::builtins::components::rendering::CameraRenderPass;
+use crate::builtins::components::rendering::OverlayRenderPass;
+use crate::entity::Entity;
+use crate::graphics::DrawCommand;
+use crate::graphics::renderpass::RenderPass;
+use crate::window::Window;
+use wutengine_graphics::wgpu;
+
+use super::RuntimeStartErr;
+use super::SystemManifest;
+
+/// A feature that can be added to the runtime with [`super::InitRuntimeConfig::plugins`].
+///
+/// Plugins are built in dependency order during runtime startup, and cleaned up in reverse order
+/// right before the runtime exits
+pub trait Plugin: Send + Sync + 'static {
+    /// The unique name of the plugin, used to declare dependencies. Defaults to the type name
+    fn name(&self) -> &'static str {
+        core::any::type_name::<Self>()
+    }
+
+    /// The names of the plugins that must be built before this plugin
+    fn dependencies(&self) -> Vec<&'static str> {
+        Vec::new()
+    }
+
+    /// Adds the plugin's systems, render passes, overlays and config defaults to `context`. Called once,
+    /// after the config and logger were initialized, but before any other engine subsystem
+    fn build(&self, context: &mut PluginContext);
+
+    /// Called once, right before the runtime exits and after the on-exit handlers were run
+    fn cleanup(&self) {}
+}
+
+/// Runs after the runtime was initialized, right before the post-start callback
+type StartupFn = Box<dyn FnOnce()>;
+
+/// What the plugins added during [`Plugin::build`]
+#[derive(derive_more::Debug)]
+pub struct PluginContext {
+    /// The systems added by all plugins
+    pub(super) manifest: SystemManifest,
+
+    /// Deferred setup that requires a running runtime, such as spawning entities
+    #[debug(skip)]
+    pub(super) startup: Vec<StartupFn>,
+
+    /// The config defaults added by the plugin that is currently being built
+    config_defaults: Vec<(String, crate::config::toml::Value)>,
+}
+
+impl PluginContext {
+    /// Returns the manifest the plugin's systems are added to. The systems are part of the initial schedule
+    #[inline]
+    pub fn systems(&mut self) -> &mut SystemManifest {
+        &mut self.manifest
+    }
+
+    /// Sets the config key `key` to `value`, unless it was already set by the config file, the config
+    /// overrides or an earlier plugin
+    pub fn set_config_default(&mut self, key: &str, value: impl Into<crate::config::toml::Value>) {
+        self.config_defaults.push((key.to_string(), value.into()));
+    }
+
+    /// Spawns an entity named `name` with a [`CameraRenderPass`] of type `T` once the runtime started
+    pub fn add_camera_render_pass<T: RenderPass<Camera, [DrawCommand]>>(
+        &mut self,
+        name: &'static str,
+    ) {
+        self.startup.push(Box::new(move || {
+            Entity::spawn_transformless(name).add_component(CameraRenderPass::new::<T>());
+        }));
+    }
+
+    /// Spawns an entity named `name` with an [`OverlayRenderPass`] of type `T` once the runtime started
+    pub fn add_overlay_render_pass<T: RenderPass<(Window, wgpu::Texture), hecs::World>>(
+        &mut self,
+        name: &'static str,
+    ) {
+        self.startup.push(Box::new(move || {
+            Entity::spawn_transformless(name).add_component(OverlayRenderPass::new::<T>());
+        }));
+    }
+
+    /// Adds a window to the development overlay once the runtime started
+    #[cfg(feature = "development_overlay")]
+    pub fn add_development_overlay_window<
+        T: crate::development_overlay::DevelopmentOverlayWindow,
+    >(
+        &mut self,
+        window: T,
+    ) {
+        self.startup.push(Box::new(move || {
+            crate::development_overlay::add_development_overlay_window(window);
+        }));
+    }
+
+    /// Runs `f` once the runtime was initialized, right before the post-start callback. Use for setup
+    /// that requires a running runtime, such as spawning entities
+    pub fn on_startup(&mut self, f: impl FnOnce() + 'static) {
+        self.startup.push(Box::new(f));
+    }
+}
+
+/// Builds all plugins in dependency order, returning the plugins in that order along with what they added
+pub(super) fn build_plugins(
+    plugins: Vec<Arc<dyn Plugin>>,
+) -> Result<(Vec<Arc<dyn Plugin>>, PluginContext), RuntimeStartErr> {
+    let ordered = order_plugins(plugins)?;
+
+    let mut context = PluginContext {
+        manifest: SystemManifest::empty(),
+        startup: Vec::new(),
+        config_defaults: Vec::new(),
+    };
+
+    for plugin in &ordered {
+        log::info!("Building plugin {}", plugin.name());
+
+        plugin.build(&mut context);
+
+        for (key, value) in context.config_defaults.drain(..) {
+            if crate::config::get_raw(&key).is_some() {
+                continue;
+            }
+
+            if let Err(e) = crate::config::set_raw(&key, value) {
+                log::error!(
+                    "Failed to set config default `{key}` of plugin {}: {e}",
+                    plugin.name()
+                );
+            }
+        }
+    }
+
+    Ok((ordered, context))
+}
+
+/// Sorts `plugins` so that each plugin comes after its dependencies. Plugins without a dependency
+/// relation keep the order they were given in
+fn order_plugins(plugins: Vec<Arc<dyn Plugin>>) -> Result<Vec<Arc<dyn Plugin>>, RuntimeStartErr> {
+    let mut by_name: HashMap<&'static str, usize> = HashMap::with_capacity(plugins.len());
+
+    for (i, plugin) in plugins.iter().enumerate() {
+        if by_name.insert(plugin.name(), i).is_some() {
+            return Err(RuntimeStartErr::DuplicatePlugin(plugin.name()));
+        }
+    }
+
+    for plugin in &plugins {
+        for dependency in plugin.dependencies() {
+            if !by_name.contains_key(dependency) {
+                return Err(RuntimeStartErr::MissingPluginDependency {
+                    plugin: plugin.name(),
+                    dependency,
+                });
+            }
+        }
+    }
+
+    let mut ordered: Vec<Arc<dyn Plugin>> = Vec::with_capacity(plugins.len());
+    let mut remaining = plugins;
+
+    while !remaining.is_empty() {
+        let next = remaining.iter().position(|plugin| {
+            plugin
+                .dependencies()
+                .iter()
+                .all(|dependency| ordered.iter().any(|built| built.name() == *dependency))
+        });
+
+        let Some(next) = next else {
+            return Err(RuntimeStartErr::PluginDependencyCycle(
+                remaining.iter().map(|plugin| plugin.name()).collect(),
+            ));
+        };
+
+        ordered.push(remaining.remove(next));
+    }
+
+    Ok(ordered)
+}
+
+#[cfg(test)]
+mod test {
+    use alloc::sync::Arc;
+
+    use super::Plugin;
+    use super::PluginContext;
+    use crate::runtime::RuntimeStartErr;
+
+    struct Named(&'static str, &'static [&'static str]);
+
+    impl Plugin for Named {
+        fn name(&self) -> &'static str {
+            self.0
+        }
+
+        fn dependencies(&self) -> Vec<&'static str> {
+            self.1.to_vec()
+        }
+
+        fn build(&self, _context: &mut PluginContext) {}
+    }
+
+    fn names(plugins: &[Arc<dyn Plugin>]) -> Vec<&'static str> {
+        plugins.iter().map(|plugin| plugin.name()).collect()
+    }
+
+    #[test]
+    fn test_plugin_order() {
+        let ordered = super::order_plugins(vec![
+            Arc::new(Named("physics_debug", &["physics", "render"])),
+            Arc::new(Named("render", &[])),
+            Arc::new(Named("physics", &[])),
+        ])
+        .unwrap();
+
+        assert_eq!(vec!["render", "physics", "physics_debug"], names(&ordered));
+
+        assert!(matches!(
+            super::order_plugins(vec![Arc::new(Named("a", &["missing"]))]),
+            Err(RuntimeStartErr::MissingPluginDependency { .. })
+        ));
+        assert!(matches!(
+            super::order_plugins(vec![
+                Arc::new(Named("a", &["b"])),
+                Arc::new(Named("b", &["a"]))
+            ]),
+            Err(RuntimeStartErr::PluginDependencyCycle(_))
+        ));
+    }
+}
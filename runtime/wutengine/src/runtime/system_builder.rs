@@ -1,11 +1,16 @@
 use alloc::sync::Arc;
-use core::any::TypeId;
 use core::num::NonZero;
-use std::collections::HashSet;
+use core::sync::atomic::AtomicBool;
+use core::sync::atomic::AtomicU64;
+use core::sync::atomic::Ordering;
 
 use rayon::prelude::*;
 
-use crate::system::{GenericSystem, Phase, Queryable, SystemId};
+use crate::system::{
+    BorrowSet, Commands, EntityCountFn, GenericSystem, Phase, Queryable, Resources, RunCondition,
+    SystemCallback, SystemId, SystemLabel, current_tick, enter_system_tick, find_duplicate_borrow,
+    find_exclusive_borrow,
+};
 
 /// A collection of systems, used during WutEngine runtime initialization to build a
 /// system schedule.
@@ -20,11 +25,25 @@ pub struct SystemManifest {
 /// A configuration for a system added to a [`SystemManifest`]
 #[derive(Debug, Default)]
 pub struct SystemConfig<'a> {
-    /// Any dependencies on previously insteded systems
+    /// Systems that must run before this system. Systems in earlier phases always run before, and
+    /// systems in later phases can not be depended on
     pub dependencies: &'a [SystemId],
 
+    /// The labels of this system, which other systems can use to order themselves relative to it
+    pub labels: &'a [SystemLabel],
+
+    /// This system runs before all systems in the same phase with any of these labels
+    pub before: &'a [SystemLabel],
+
+    /// This system runs after all systems in the same phase with any of these labels
+    pub after: &'a [SystemLabel],
+
     /// How many query results are processed on a single thread, before the work is split onto another.
     pub parallel_batch_size: Option<NonZero<u32>>,
+
+    /// If set, the system only runs when this condition is `true`. The system gets its own copy of the
+    /// state of the condition, see [`RunCondition`]
+    pub run_condition: Option<RunCondition>,
 }
 
 impl SystemManifest {
@@ -42,7 +61,11 @@ impl SystemManifest {
         C::insert_default_component_systems(self);
     }
 
-    /// Adds a system to the manifest
+    /// Adds a system to the manifest.
+    ///
+    /// The query can be filtered on [`crate::system::Added`] and [`crate::system::Changed`]. Every
+    /// component the system borrows as `&mut T` counts as changed for other systems, whether it is
+    /// written to or not. Borrow it as [`crate::system::Mut`] to only mark actual writes
     #[inline]
     pub fn add_system<Q>(
         &mut self,
@@ -68,36 +91,153 @@ impl SystemManifest {
     where
         Q: crate::hecs::Query + Queryable,
         for<'a> Q::Item<'a>: Send,
+    {
+        self.add_query_system::<(), Q>(phase, name, config, false, move |_, (), entity, item| {
+            sys(entity, item);
+        })
+    }
+
+    /// Adds a system to the manifest that can record deferred world changes in a [`Commands`] buffer.
+    ///
+    /// The commands are applied after the system finished, before any system ordered after it or in a later stage starts
+    pub fn add_system_with_commands<Q>(
+        &mut self,
+        phase: Phase,
+        name: &'static str,
+        config: &SystemConfig,
+        sys: impl for<'a, 'w> Fn(&mut Commands<'w>, crate::entity::Entity, Q::Item<'a>)
+        + Send
+        + Sync
+        + 'static,
+    ) -> SystemId
+    where
+        Q: crate::hecs::Query + Queryable,
+        for<'a> Q::Item<'a>: Send,
+    {
+        self.add_system_with_resources::<(), Q>(
+            phase,
+            name,
+            config,
+            move |commands, (), entity, item| {
+                sys(commands, entity, item);
+            },
+        )
+    }
+
+    /// Adds a system to the manifest that borrows the resources `R`, and can record deferred world changes
+    /// in a [`Commands`] buffer.
+    ///
+    /// The resources are borrowed once per system run, or once per batch if a parallel batch size was given.
+    /// A system with a parallel batch size can only borrow resources shared, because batches borrowing a
+    /// resource exclusively would run one after another. Otherwise it is rejected with
+    /// [`crate::system::ScheduleErr::BatchedExclusiveResourceBorrow`] when it is scheduled.
+    /// Resource borrows are scheduled like component borrows, so systems with clashing resource borrows never run
+    /// in parallel. If any of the resources does not exist, the system is skipped. Each resource can be borrowed
+    /// only once per system, otherwise the system is rejected with [`crate::system::ScheduleErr::DuplicateResourceBorrow`]
+    /// when it is scheduled. Events are read and sent with
+    /// [`crate::system::EventReader`] and [`crate::system::EventWriter`] in the same way.
+    ///
+    /// The system is invoked once per matching entity, so it does nothing while no entities match. Use
+    /// [`Self::add_resource_system`] to work on resources or events once per run instead
+    pub fn add_system_with_resources<R, Q>(
+        &mut self,
+        phase: Phase,
+        name: &'static str,
+        config: &SystemConfig,
+        sys: impl for<'a, 'w> Fn(
+            &mut Commands<'w>,
+            &mut R::Item<'w>,
+            crate::entity::Entity,
+            Q::Item<'a>,
+        ) + Send
+        + Sync
+        + 'static,
+    ) -> SystemId
+    where
+        R: Resources,
+        Q: crate::hecs::Query + Queryable,
+        for<'a> Q::Item<'a>: Send,
+    {
+        self.add_query_system::<R, Q>(phase, name, config, true, sys)
+    }
+
+    /// Adds a system running `sys` for every entity matching the query `Q`. `records_commands` is
+    /// `false` for systems that never record commands, so that later stages do not have to wait for them
+    #[expect(
+        clippy::too_many_lines,
+        reason = "Runs the query both batched and unbatched"
+    )]
+    fn add_query_system<R, Q>(
+        &mut self,
+        phase: Phase,
+        name: &'static str,
+        config: &SystemConfig,
+        records_commands: bool,
+        sys: impl for<'a, 'w> Fn(
+            &mut Commands<'w>,
+            &mut R::Item<'w>,
+            crate::entity::Entity,
+            Q::Item<'a>,
+        ) + Send
+        + Sync
+        + 'static,
+    ) -> SystemId
+    where
+        R: Resources,
+        Q: crate::hecs::Query + Queryable,
+        for<'a> Q::Item<'a>: Send,
     {
         let system_id = SystemId::next(phase);
 
-        for dependency in config.dependencies {
-            assert_eq!(
-                phase,
-                dependency.phase(),
-                "Cannot depend on system in another phase"
-            );
+        let mut shared_borrows =
+            BorrowSet::with_capacity(Q::NUM_SHARED_BORROWS + R::NUM_SHARED_BORROWS);
+        let mut exclusive_borrows =
+            BorrowSet::with_capacity(Q::NUM_EXCLUSIVE_BORROWS + R::NUM_EXCLUSIVE_BORROWS);
+
+        Q::register_borrows(&mut shared_borrows, &mut exclusive_borrows);
+        R::register_borrows(&mut shared_borrows, &mut exclusive_borrows);
 
-            // Not really required due to ordering, but a useful sanity check
-            debug_assert!(
-                dependency.id() < system_id.id(),
-                "Incorrect atomic ordering?"
+        let duplicate_resource = find_duplicate_borrow::<R>();
+
+        if let Some(resource) = duplicate_resource {
+            log::error!(
+                "System {name} borrows resource {resource} more than once, and will be rejected when scheduled"
             );
         }
 
-        let mut shared_borrows = HashSet::with_capacity(Q::NUM_SHARED_BORROWS);
-        let mut exclusive_borrows = HashSet::with_capacity(Q::NUM_EXCLUSIVE_BORROWS);
+        let batch_size = config.parallel_batch_size;
 
-        Q::register_borrows(&mut shared_borrows, &mut exclusive_borrows);
+        let batched_exclusive_resource = batch_size.and_then(|_| find_exclusive_borrow::<R>());
 
-        let batch_size = config.parallel_batch_size;
+        if let Some(resource) = batched_exclusive_resource {
+            log::error!(
+                "System {name} has a parallel batch size but borrows resource {resource} exclusively, and will be rejected when scheduled"
+            );
+        }
+
+        let last_run = AtomicU64::new(0);
+        let resource_state = R::State::default();
 
         let callback: Arc<GenericSystem> = Arc::new(move |world: &crate::world::World| {
             profiling::scope!("System callback", name);
 
-            let mut query_borrowed = world.ecs.query::<(hecs::Entity, Q)>();
+            // Checked once up front, so a missing resource skips the system as a whole
+            if let Err(missing) = R::fetch(world, &resource_state) {
+                log::warn!("Skipping system {name} because resource {missing} does not exist");
+                return Vec::new();
+            }
+
+            // Changes are tracked since the last time the system actually ran. Components the system
+            // borrows mutably are marked as changed at the tick it started at, so it does not see its own changes
+            let tick = current_tick();
+            let last_run = last_run.swap(tick, Ordering::Relaxed);
 
-            if let Some(batch_size) = batch_size {
+            // Whether the system was invoked for any entity. If not, its resources were not used
+            let invoked = AtomicBool::new(false);
+
+            let mut query_borrowed = world.ecs.query::<(hecs::Entity, Q, Q::ChangeTicks)>();
+
+            let buffer = if let Some(batch_size) = batch_size {
                 // If a parallel batch size was given, we first split the main query
                 // into appropriately sized batches
                 let par_batches = query_borrowed
@@ -105,23 +245,158 @@ impl SystemManifest {
                     .enumerate()
                     .par_bridge();
 
-                // Here we send the batches to rayon, which automatically distributes them into the thread pool
-                par_batches.for_each(|(_i, batch)| {
-                    profiling::scope!("System batch", _i.to_string());
+                // Here we send the batches to rayon, which automatically distributes them into the thread pool.
+                // Each batch records its own commands, which are then ordered by batch index to keep them deterministic
+                let mut batch_commands: Vec<_> = par_batches
+                    .map(|(i, batch)| {
+                        profiling::scope!("System batch", i.to_string());
 
-                    // Finally, we process the batch on the same thread
-                    for (entity, query_return) in batch {
-                        profiling::scope!("System invocation");
-                        sys(crate::entity::Entity(entity), query_return);
-                    }
-                });
+                        let mut commands = Commands::new(world);
+
+                        let Ok(mut resources) = R::fetch(world, &resource_state) else {
+                            return (i, commands.into_buffer());
+                        };
+
+                        let _tick_scope = enter_system_tick(tick);
+
+                        // Finally, we process the batch on the same thread
+                        for (entity, query_return, ticks) in batch {
+                            if !Q::passes_change_filters(&ticks, last_run) {
+                                continue;
+                            }
+
+                            Q::mark_changed(&ticks, tick);
+                            invoked.store(true, Ordering::Relaxed);
+
+                            profiling::scope!("System invocation");
+                            sys(
+                                &mut commands,
+                                &mut resources,
+                                crate::entity::Entity(entity),
+                                query_return,
+                            );
+                        }
+
+                        (i, commands.into_buffer())
+                    })
+                    .collect();
+
+                batch_commands.sort_unstable_by_key(|(i, _)| *i);
+
+                batch_commands
+                    .into_iter()
+                    .flat_map(|(_, buffer)| buffer)
+                    .collect()
             } else {
+                let mut commands = Commands::new(world);
+
                 // If a batch size was not given, we process the batch fully on this thread
-                for (entity, query_return) in &mut query_borrowed {
+                if let Ok(mut resources) = R::fetch(world, &resource_state) {
+                    let _tick_scope = enter_system_tick(tick);
+
+                    for (entity, query_return, ticks) in &mut query_borrowed {
+                        if !Q::passes_change_filters(&ticks, last_run) {
+                            continue;
+                        }
+
+                        Q::mark_changed(&ticks, tick);
+                        invoked.store(true, Ordering::Relaxed);
+
+                        profiling::scope!("System invocation");
+                        sys(
+                            &mut commands,
+                            &mut resources,
+                            crate::entity::Entity(entity),
+                            query_return,
+                        );
+                    }
+                }
+
+                commands.into_buffer()
+            };
+
+            // Only finished if invoked, so an event reader does not skip events while no entities match
+            if invoked.into_inner() {
+                R::finish_run(world, &resource_state);
+            }
+
+            buffer
+        });
+
+        self.systems.push(PendingSystem {
+            name,
+            system_id,
+            phase,
+            shared_borrows,
+            exclusive_borrows,
+            dependencies: config.dependencies.to_vec(),
+            labels: config.labels.to_vec(),
+            before: config.before.to_vec(),
+            after: config.after.to_vec(),
+            run_condition: config.run_condition.as_ref().map(RunCondition::for_system),
+            duplicate_resource,
+            batched_exclusive_resource,
+            records_commands,
+            entity_count: Some(Arc::new(|world: &crate::world::World| {
+                world.ecs.query::<Q>().iter().len()
+            })),
+            callback: SystemCallback::Parallel(callback),
+        });
+
+        system_id
+    }
+
+    /// Adds a system to the manifest that borrows the resources `R`, and is invoked once per run regardless
+    /// of the entities in the world. Use it to read and send events, or to update resources.
+    ///
+    /// Resources are borrowed and scheduled like with [`Self::add_system_with_resources`], and if any of them does
+    /// not exist the system is skipped. The parallel batch size of the config is ignored
+    pub fn add_resource_system<R>(
+        &mut self,
+        phase: Phase,
+        name: &'static str,
+        config: &SystemConfig,
+        sys: impl for<'w> Fn(&mut Commands<'w>, &mut R::Item<'w>) + Send + Sync + 'static,
+    ) -> SystemId
+    where
+        R: Resources,
+    {
+        let system_id = SystemId::next(phase);
+
+        let mut shared_borrows = BorrowSet::with_capacity(R::NUM_SHARED_BORROWS);
+        let mut exclusive_borrows = BorrowSet::with_capacity(R::NUM_EXCLUSIVE_BORROWS);
+
+        R::register_borrows(&mut shared_borrows, &mut exclusive_borrows);
+
+        let duplicate_resource = find_duplicate_borrow::<R>();
+
+        if let Some(resource) = duplicate_resource {
+            log::error!(
+                "System {name} borrows resource {resource} more than once, and will be rejected when scheduled"
+            );
+        }
+
+        let resource_state = R::State::default();
+
+        let callback: Arc<GenericSystem> = Arc::new(move |world: &crate::world::World| {
+            profiling::scope!("System callback", name);
+
+            let mut commands = Commands::new(world);
+
+            match R::fetch(world, &resource_state) {
+                Ok(mut resources) => {
                     profiling::scope!("System invocation");
-                    sys(crate::entity::Entity(entity), query_return);
+                    sys(&mut commands, &mut resources);
+                }
+                Err(missing) => {
+                    log::warn!("Skipping system {name} because resource {missing} does not exist");
+                    return Vec::new();
                 }
             }
+
+            R::finish_run(world, &resource_state);
+
+            commands.into_buffer()
         });
 
         self.systems.push(PendingSystem {
@@ -131,7 +406,70 @@ impl SystemManifest {
             shared_borrows,
             exclusive_borrows,
             dependencies: config.dependencies.to_vec(),
-            callback,
+            labels: config.labels.to_vec(),
+            before: config.before.to_vec(),
+            after: config.after.to_vec(),
+            run_condition: config.run_condition.as_ref().map(RunCondition::for_system),
+            duplicate_resource,
+            batched_exclusive_resource: None,
+            records_commands: true,
+            entity_count: None,
+            callback: SystemCallback::Parallel(callback),
+        });
+
+        system_id
+    }
+
+    /// Adds a system with exclusive access to the [`crate::world::World`]. It runs once per phase,
+    /// in a stage of its own, and never in parallel with any other system.
+    ///
+    /// The world stays locked while the system runs, so the [`crate::entity::Entity`] APIs panic when
+    /// called from it. Use the methods of the given world instead, like [`crate::world::World::spawn`]
+    /// instead of [`crate::entity::Entity::spawn`]
+    #[inline]
+    pub fn add_exclusive_system(
+        &mut self,
+        phase: Phase,
+        name: &'static str,
+        sys: impl Fn(&mut crate::world::World) + Send + Sync + 'static,
+    ) -> SystemId {
+        self.add_exclusive_system_with_config(phase, name, &SystemConfig::default(), sys)
+    }
+
+    /// Adds a system with exclusive access to the [`crate::world::World`], that is dependent on one or more
+    /// previously inserted systems. The parallel batch size of the config is ignored. Like with
+    /// [`Self::add_exclusive_system`], use the given world instead of the [`crate::entity::Entity`] APIs
+    pub fn add_exclusive_system_with_config(
+        &mut self,
+        phase: Phase,
+        name: &'static str,
+        config: &SystemConfig,
+        sys: impl Fn(&mut crate::world::World) + Send + Sync + 'static,
+    ) -> SystemId {
+        let system_id = SystemId::next(phase);
+
+        let callback = Arc::new(move |world: &mut crate::world::World| {
+            profiling::scope!("Exclusive system callback", name);
+
+            sys(world);
+        });
+
+        self.systems.push(PendingSystem {
+            name,
+            system_id,
+            phase,
+            shared_borrows: BorrowSet::default(),
+            exclusive_borrows: BorrowSet::default(),
+            dependencies: config.dependencies.to_vec(),
+            labels: config.labels.to_vec(),
+            before: config.before.to_vec(),
+            after: config.after.to_vec(),
+            run_condition: config.run_condition.as_ref().map(RunCondition::for_system),
+            duplicate_resource: None,
+            batched_exclusive_resource: None,
+            records_commands: false,
+            entity_count: None,
+            callback: SystemCallback::Exclusive(callback),
         });
 
         system_id
@@ -163,15 +501,41 @@ pub(crate) struct PendingSystem {
     pub(crate) phase: Phase,
 
     /// What component types the system borrows immutably
-    pub(crate) shared_borrows: HashSet<TypeId>,
+    pub(crate) shared_borrows: BorrowSet,
 
     /// What component types the system borrows mutable
-    pub(crate) exclusive_borrows: HashSet<TypeId>,
+    pub(crate) exclusive_borrows: BorrowSet,
 
     /// What dependencies the system has, if any
     pub(crate) dependencies: Vec<SystemId>,
 
+    /// The labels of the system
+    pub(crate) labels: Vec<SystemLabel>,
+
+    /// The labels of the systems this system must run before
+    pub(crate) before: Vec<SystemLabel>,
+
+    /// The labels of the systems this system must run after
+    pub(crate) after: Vec<SystemLabel>,
+
+    /// The condition deciding whether the system runs, if any
+    pub(crate) run_condition: Option<RunCondition>,
+
+    /// A resource borrowed more than once by the system, if any. Such a system can not be scheduled
+    pub(crate) duplicate_resource: Option<&'static str>,
+
+    /// A resource borrowed exclusively by a system with a parallel batch size, if any. Such a system
+    /// can not be scheduled
+    pub(crate) batched_exclusive_resource: Option<&'static str>,
+
+    /// Whether the system can record commands
+    pub(crate) records_commands: bool,
+
+    /// Counts the entities matching the query of the system. [`None`] for systems without a query
+    #[debug(skip)]
+    pub(crate) entity_count: Option<Arc<EntityCountFn>>,
+
     /// The actual system-running callback
     #[debug(skip)]
-    pub(crate) callback: Arc<GenericSystem>,
+    pub(crate) callback: SystemCallback,
 }
@@ -0,0 +1,252 @@
+//! Futures that resolve at points in the frame loop, for writing multi-frame
+//! gameplay sequences as async functions
+
+use core::pin::Pin;
+use core::task::Context;
+use core::task::Poll;
+use core::task::Waker;
+use std::sync::Mutex;
+
+use crate::time;
+
+/// The wakers of pending [`Wait`] futures, by the runtime event that wakes them
+static WAITERS: Mutex<Waiters> = Mutex::new(Waiters {
+    frame: Vec::new(),
+    fixed_update: Vec::new(),
+});
+
+/// Pending [`Wait`] wakers
+#[derive(Debug)]
+struct Waiters {
+    /// Woken at the start of each frame, after the frame time was updated
+    frame: Vec<Waker>,
+
+    /// Woken after each fixed update
+    fixed_update: Vec<Waker>,
+}
+
+/// The point in the frame loop a [`Wait`] resolves at
+#[derive(Debug, Clone, Copy)]
+enum WaitUntil {
+    /// Resolves once [`time::frame_num`] reaches the given frame
+    Frame(usize),
+
+    /// Resolves once [`time::time_nanos`] reaches the given game time
+    Time(u64),
+
+    /// Resolves once [`time::fixed_step_num`] reaches the given fixed step
+    FixedStep(u64),
+}
+
+impl WaitUntil {
+    /// Returns whether the awaited point in the frame loop was reached
+    fn reached(self) -> bool {
+        match self {
+            Self::Frame(frame) => time::frame_num() >= frame,
+            Self::Time(nanos) => time::time_nanos() >= nanos,
+            Self::FixedStep(step) => time::fixed_step_num() >= step,
+        }
+    }
+}
+
+/// A future that resolves at a point in the WutEngine frame loop. Created with [`next_frame`],
+/// [`wait_frames`], [`wait_seconds`] and [`next_fixed_update`].
+///
+/// The future is woken by the runtime, so it can be awaited from any executor, such as
+/// [`crate::task::spawn_async`]
+#[derive(Debug)]
+#[must_use = "futures do nothing unless awaited"]
+pub struct Wait {
+    until: WaitUntil,
+}
+
+impl Future for Wait {
+    type Output = ();
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        // Checked while holding the lock, so that the runtime cannot advance the time
+        // and wake the waiters in between the check and the registration
+        let mut waiters = WAITERS.lock().unwrap();
+
+        if self.until.reached() {
+            return Poll::Ready(());
+        }
+
+        let wakers = match self.until {
+            WaitUntil::Frame(_) | WaitUntil::Time(_) => &mut waiters.frame,
+            WaitUntil::FixedStep(_) => &mut waiters.fixed_update,
+        };
+
+        if !wakers.iter().any(|waker| waker.will_wake(cx.waker())) {
+            wakers.push(cx.waker().clone());
+        }
+
+        Poll::Pending
+    }
+}
+
+/// Returns a future that resolves at the start of the next frame
+#[inline]
+pub fn next_frame() -> Wait {
+    wait_frames(1)
+}
+
+/// Returns a future that resolves at the start of the `frames`th frame from now
+pub fn wait_frames(frames: usize) -> Wait {
+    Wait {
+        until: WaitUntil::Frame(time::frame_num() + frames),
+    }
+}
+
+/// Returns a future that resolves at the start of the first frame at least `seconds` of game
+/// time from now. Game time is affected by the time scale, see [`time::set_time_scale`]
+#[expect(
+    clippy::cast_precision_loss,
+    clippy::cast_sign_loss,
+    clippy::cast_possible_truncation,
+    reason = "Inherent to operation"
+)]
+pub fn wait_seconds(seconds: f64) -> Wait {
+    let nanos = (seconds.max(0.0) * (time::NANOS_PER_SECOND as f64)) as u64;
+
+    Wait {
+        until: WaitUntil::Time(time::time_nanos() + nanos),
+    }
+}
+
+/// Returns a future that resolves after the next fixed update, including the physics step,
+/// has completed
+pub fn next_fixed_update() -> Wait {
+    Wait {
+        until: WaitUntil::FixedStep(time::fixed_step_num() + 1),
+    }
+}
+
+/// Wakes the futures waiting for a frame or for game time. Called by the runtime at the start of
+/// each frame, after the frame time was updated
+pub(super) fn wake_frame_waiters() {
+    let wakers = core::mem::take(&mut WAITERS.lock().unwrap().frame);
+
+    for waker in wakers {
+        waker.wake();
+    }
+}
+
+/// Wakes the futures waiting for a fixed update. Called by the runtime after each fixed update
+pub(super) fn wake_fixed_update_waiters() {
+    let wakers = core::mem::take(&mut WAITERS.lock().unwrap().fixed_update);
+
+    for waker in wakers {
+        waker.wake();
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use alloc::sync::Arc;
+    use core::pin::Pin;
+    use core::sync::atomic::AtomicUsize;
+    use core::sync::atomic::Ordering;
+    use core::task::Context;
+    use core::task::Poll;
+    use std::sync::Mutex;
+    use std::task::Wake;
+
+    use super::Wait;
+    use crate::time;
+
+    /// The time and the waiters are global, so the tests must not advance them concurrently
+    static SERIAL: Mutex<()> = Mutex::new(());
+
+    /// Counts how often it was woken
+    #[derive(Default)]
+    struct CountingWaker(AtomicUsize);
+
+    impl Wake for CountingWaker {
+        fn wake(self: Arc<Self>) {
+            self.0.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    impl CountingWaker {
+        fn count(&self) -> usize {
+            self.0.load(Ordering::Relaxed)
+        }
+    }
+
+    fn poll(wait: &mut Wait, waker: &Arc<CountingWaker>) -> Poll<()> {
+        let waker = Arc::clone(waker).into();
+        Pin::new(wait).poll(&mut Context::from_waker(&waker))
+    }
+
+    /// Advances the frame like the runtime does, by `nanos` of real time
+    fn advance_frame(nanos: u64) {
+        time::update_frame_with_delta(nanos);
+        super::wake_frame_waiters();
+    }
+
+    #[test]
+    fn test_wait_frames() {
+        let _serial = SERIAL.lock().unwrap();
+        crate::test_util::init_globals();
+
+        let waker = Arc::new(CountingWaker::default());
+        let mut wait = super::wait_frames(2);
+
+        assert_eq!(Poll::Pending, poll(&mut wait, &waker));
+
+        advance_frame(1000);
+
+        assert_eq!(1, waker.count(), "Not woken at the start of the frame");
+        assert_eq!(Poll::Pending, poll(&mut wait, &waker));
+
+        advance_frame(1000);
+
+        assert_eq!(2, waker.count());
+        assert_eq!(Poll::Ready(()), poll(&mut wait, &waker));
+    }
+
+    #[test]
+    fn test_wait_seconds() {
+        let _serial = SERIAL.lock().unwrap();
+        crate::test_util::init_globals();
+
+        let waker = Arc::new(CountingWaker::default());
+        let mut wait = super::wait_seconds(0.05);
+
+        assert_eq!(Poll::Pending, poll(&mut wait, &waker));
+
+        for _ in 0..2 {
+            advance_frame(time::NANOS_PER_SECOND / 50);
+            assert_eq!(Poll::Pending, poll(&mut wait, &waker));
+        }
+
+        advance_frame(time::NANOS_PER_SECOND / 50);
+
+        assert_eq!(3, waker.count());
+        assert_eq!(Poll::Ready(()), poll(&mut wait, &waker));
+    }
+
+    #[test]
+    fn test_next_fixed_update() {
+        let _serial = SERIAL.lock().unwrap();
+        crate::test_util::init_globals();
+
+        let waker = Arc::new(CountingWaker::default());
+        let mut wait = super::next_fixed_update();
+
+        assert_eq!(Poll::Pending, poll(&mut wait, &waker));
+
+        // Frames without a fixed update neither wake nor resolve it
+        advance_frame(1000);
+
+        assert_eq!(0, waker.count());
+        assert_eq!(Poll::Pending, poll(&mut wait, &waker));
+
+        time::update_fixed();
+        super::wake_fixed_update_waiters();
+
+        assert_eq!(1, waker.count());
+        assert_eq!(Poll::Ready(()), poll(&mut wait, &waker));
+    }
+}
@@ -6,13 +6,13 @@ use core::time::Duration;
 use alloc::sync::Arc;
 use wutengine_util_macro::VariantName;
 
-use crate::config;
 use crate::graphics;
 use crate::input;
 use crate::runtime;
-use crate::runtime::events;
 use crate::runtime::send_to_main_thread;
-use crate::time;
+use crate::system::SystemId;
+use crate::system::SystemInsertion;
+use crate::system::SystemUpdate;
 use crate::window;
 use crate::window::Window;
 use crate::window::WindowConfig;
@@ -39,8 +39,12 @@ pub(crate) enum MainThreadEvent {
     /// Surface should be reconfigured
     ForceSurfaceReconfigure(Window),
 
-    /// Request to add one or more systems to the main system schedule
-    AddSystem(SystemManifest),
+    /// Request to add one or more systems to the main system schedule, reporting the result
+    /// through the [`SystemInsertion`]
+    AddSystem(SystemManifest, SystemInsertion),
+
+    /// Request to remove, pause or resume a system in the main system schedule
+    UpdateSystem(SystemId, SystemUpdate),
 
     /// Run a task on the main thread
     #[debug("RunTask(...)")]
@@ -58,10 +62,10 @@ impl winit::application::ApplicationHandler<MainThreadEvent> for Runtime {
     fn resumed(&mut self, event_loop: &winit::event_loop::ActiveEventLoop) {
         _ = event_loop;
 
-        let Some(mut post_init) = self.initialization_data.take() else {
+        if self.initialization_data.is_none() {
             // Already initialized
             return;
-        };
+        }
 
         profiling::scope!("Initialize");
 
@@ -81,22 +85,10 @@ impl winit::application::ApplicationHandler<MainThreadEvent> for Runtime {
             graphics::dev_overlays::insert_all();
         }
 
-        // Initialize the time manager later here, right before the runtime starts running frames
-        time::init();
+        self.on_exit_handlers
+            .push(Arc::new(wutengine_graphics::persist_pipeline_cache));
 
-        if let Some(fps_limit) = config::try_get::<u64>("wutengine.window.fps_limit")
-            && fps_limit != 0
-        {
-            self.frame_pacer
-                .set_frame_interval(Some(Duration::from_secs_f64(1.0 / (fps_limit as f64))));
-        }
-
-        events::add_event_listeners(self);
-
-        // Must be called last, so we know the engine setup is done
-        if let Some(post_init_callback) = post_init.post_start_callback.take() {
-            post_init_callback();
-        }
+        self.finish_initialization();
     }
 
     fn window_event(
@@ -251,21 +243,7 @@ impl winit::application::ApplicationHandler<MainThreadEvent> for Runtime {
                 window::manager::refresh_window(window_id, false);
             }
             MainThreadEvent::RuntimeExitRequested(force) => {
-                log::debug!("Runtime exit was requested. Force: {force}.");
-
-                let mut should_exit = true;
-
-                if !force {
-                    // If any of the handlers return true (cancel exit), we do not actually exit if not forced
-
-                    for on_exit_requested_handler in
-                        self.on_exit_requested_handlers.iter().map(Arc::as_ref)
-                    {
-                        should_exit = should_exit && !(on_exit_requested_handler());
-                    }
-                }
-
-                if should_exit {
+                if self.should_exit(force) {
                     event_loop.exit();
                 }
 
@@ -275,8 +253,11 @@ impl winit::application::ApplicationHandler<MainThreadEvent> for Runtime {
                 window::manager::refresh_window(window_id, true);
                 window::manager::request_redraws();
             }
-            MainThreadEvent::AddSystem(manifest) => {
-                self.systems.queue_system(manifest);
+            MainThreadEvent::AddSystem(manifest, insertion) => {
+                self.systems.queue_system(manifest, insertion);
+            }
+            MainThreadEvent::UpdateSystem(id, update) => {
+                self.systems.update_system(id, update);
             }
             MainThreadEvent::RunTask(task) => {
                 task();
@@ -315,13 +296,7 @@ impl winit::application::ApplicationHandler<MainThreadEvent> for Runtime {
 
         _ = event_loop;
 
-        log::info!("Exiting WutEngine");
-
-        for handler in self.on_exit_handlers.drain(..) {
-            handler();
-        }
-
-        log::logger().flush();
+        self.run_exit_handlers();
     }
 
     fn memory_warning(&mut self, event_loop: &winit::event_loop::ActiveEventLoop) {
@@ -0,0 +1,300 @@
+//! Application state machines, with on-enter/on-exit callbacks and state-scoped entities.
+//!
+//! A state machine is started with [`init_state`], and changed with [`set_state`]. Transitions are
+//! applied by the runtime once per frame, right before the simulation of that frame runs. Systems
+//! can be bound to a state with [`crate::system::RunCondition::in_state`]
+
+use alloc::sync::Arc;
+use core::any::Any;
+use core::any::TypeId;
+use core::fmt::Debug;
+use core::hash::Hash;
+use std::collections::HashMap;
+use std::sync::LazyLock;
+use std::sync::RwLock;
+
+use crate::component::Component;
+use crate::entity::Entity;
+use crate::world::World;
+
+/// The state machines, by the [`TypeId`] of their [`States`] type
+static STATE_MACHINES: LazyLock<RwLock<StateMachines>> =
+    LazyLock::new(|| RwLock::new(StateMachines::default()));
+
+/// Trait for types used as the states of an application state machine, usually fieldless enums
+pub trait States: Debug + Clone + Eq + Hash + Send + Sync + 'static {
+    /// The identifier for this state type. Used to derive the [`Component::ID`] of [`StateScoped`]
+    const ID: uuid::NonNilUuid;
+}
+
+/// Resource containing the current state of the state machine of `S`. Inserted once the initial
+/// state was entered
+#[derive(Debug, Clone)]
+pub struct State<S: States> {
+    current: S,
+    previous: Option<S>,
+}
+
+impl<S: States> State<S> {
+    /// Returns the current state
+    #[inline]
+    pub const fn get(&self) -> &S {
+        &self.current
+    }
+
+    /// Returns the state before the last transition, or [`None`] if no transition happened since the initial state
+    #[inline]
+    pub const fn previous(&self) -> Option<&S> {
+        self.previous.as_ref()
+    }
+}
+
+/// Marks an entity as belonging to a state. The entity is destroyed when its state is exited
+#[derive(Debug, Clone)]
+pub struct StateScoped<S: States>(pub S);
+
+impl<S: States> Component for StateScoped<S> {
+    const ID: uuid::NonNilUuid = crate::util::combine_uuid(
+        uuid::NonNilUuid::new(uuid::uuid!("d3a8f2c1-6e4b-4a97-8c05-b1f7e9d2a364")).unwrap(),
+        S::ID,
+    );
+}
+
+/// A callback run when a state is entered or exited
+type StateCallback = Arc<dyn Fn(&mut World) + Send + Sync + 'static>;
+
+/// Applies the pending transition of a single state machine
+type ApplyTransitionFn = fn(&mut World);
+
+/// All state machines
+#[derive(Default)]
+struct StateMachines {
+    /// The type-erased [`StateMachine`] of each state type
+    machines: HashMap<TypeId, Box<dyn Any + Send + Sync>>,
+
+    /// Applies the transitions of each state machine, in the order the machines were created
+    apply_order: Vec<ApplyTransitionFn>,
+}
+
+/// The pending transition and callbacks of the state machine of `S`
+struct StateMachine<S: States> {
+    /// The state to transition to when transitions are next applied
+    pending: Option<S>,
+
+    /// Callbacks run when a state is entered, in registration order
+    on_enter: HashMap<S, Vec<StateCallback>>,
+
+    /// Callbacks run when a state is exited, in registration order
+    on_exit: HashMap<S, Vec<StateCallback>>,
+}
+
+/// Starts the state machine of `S` in `initial`. The initial state is entered, and its on-enter
+/// callbacks are run, right before the next frame's simulation
+pub fn init_state<S: States>(initial: S) {
+    set_state(initial);
+}
+
+/// Requests a transition of the state machine of `S` to `next`. The transition is applied right
+/// before the next frame's simulation. If multiple transitions are requested in one frame, only the
+/// last one is applied. Transitions to the current state do nothing
+pub fn set_state<S: States>(next: S) {
+    log::debug!("Requested transition to state {next:?}");
+
+    with_machine::<S, _>(|machine| machine.pending = Some(next));
+}
+
+/// Registers `callback` to run on the main thread whenever `state` is entered, after the exited state's
+/// callbacks and before the systems of the new state run.
+///
+/// The world stays locked while the callback runs, so the [`Entity`] APIs panic when called from it.
+/// Use the given world instead, like [`World::spawn`] instead of [`Entity::spawn`]
+pub fn on_enter<S: States>(state: S, callback: impl Fn(&mut World) + Send + Sync + 'static) {
+    with_machine::<S, _>(|machine| {
+        machine
+            .on_enter
+            .entry(state)
+            .or_default()
+            .push(Arc::new(callback));
+    });
+}
+
+/// Registers `callback` to run on the main thread whenever `state` is exited, before its
+/// state-scoped entities are destroyed. Like with [`on_enter`], use the given world instead of the
+/// [`Entity`] APIs
+pub fn on_exit<S: States>(state: S, callback: impl Fn(&mut World) + Send + Sync + 'static) {
+    with_machine::<S, _>(|machine| {
+        machine
+            .on_exit
+            .entry(state)
+            .or_default()
+            .push(Arc::new(callback));
+    });
+}
+
+/// Applies the pending transitions of all state machines. Called by the runtime once per frame
+pub(crate) fn apply_transitions(world: &mut World) {
+    profiling::function_scope!();
+
+    let apply_order = STATE_MACHINES.read().unwrap().apply_order.clone();
+
+    for apply in apply_order {
+        apply(world);
+    }
+}
+
+/// Runs `f` on the state machine of `S`, creating it if it does not exist yet
+fn with_machine<S: States, R>(f: impl FnOnce(&mut StateMachine<S>) -> R) -> R {
+    let mut machines = STATE_MACHINES.write().unwrap();
+    let machines = &mut *machines;
+
+    let machine = machines
+        .machines
+        .entry(TypeId::of::<S>())
+        .or_insert_with(|| {
+            machines.apply_order.push(apply_transition::<S>);
+
+            Box::new(StateMachine::<S> {
+                pending: None,
+                on_enter: HashMap::new(),
+                on_exit: HashMap::new(),
+            })
+        });
+
+    f(machine
+        .downcast_mut()
+        .expect("State machine stored with wrong type"))
+}
+
+fn apply_transition<S: States>(world: &mut World) {
+    let Some(next) = with_machine::<S, _>(|machine| machine.pending.take()) else {
+        return;
+    };
+
+    let current = world
+        .resource::<State<S>>()
+        .map(|state| state.current.clone());
+
+    if current.as_ref() == Some(&next) {
+        return;
+    }
+
+    log::info!("Transitioning from state {current:?} to {next:?}");
+
+    // Cloned out of the registry, so that callbacks can register callbacks and request transitions themselves
+    let (on_exit, on_enter) = with_machine::<S, _>(|machine| {
+        let on_exit = current
+            .as_ref()
+            .and_then(|current| machine.on_exit.get(current))
+            .cloned()
+            .unwrap_or_default();
+        let on_enter = machine.on_enter.get(&next).cloned().unwrap_or_default();
+
+        (on_exit, on_enter)
+    });
+
+    for callback in on_exit {
+        callback(world);
+    }
+
+    if let Some(current) = &current {
+        let scoped: Vec<Entity> = world
+            .ecs
+            .query_mut::<(hecs::Entity, &StateScoped<S>)>()
+            .into_iter()
+            .filter(|(_, scoped)| scoped.0 == *current)
+            .map(|(entity, _)| Entity(entity))
+            .collect();
+
+        log::debug!(
+            "Destroying {} entities scoped to state {current:?}",
+            scoped.len()
+        );
+
+        for entity in scoped {
+            if world.contains(entity) {
+                world.destroy(entity);
+            }
+        }
+    }
+
+    world.insert_resource(State {
+        current: next,
+        previous: current,
+    });
+
+    for callback in on_enter {
+        callback(world);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use alloc::sync::Arc;
+    use core::sync::atomic::AtomicU32;
+    use core::sync::atomic::Ordering;
+
+    use super::State;
+    use super::StateScoped;
+    use super::States;
+    use crate::entity::Entity;
+    use crate::system::RunCondition;
+    use crate::world::World;
+
+    #[derive(Debug, Clone, PartialEq, Eq, Hash)]
+    enum GameState {
+        Menu,
+        Playing,
+    }
+
+    impl States for GameState {
+        const ID: uuid::NonNilUuid =
+            uuid::NonNilUuid::new(uuid::uuid!("5c9e0b7a-2f18-4d63-a4e2-97b1c3d8f056")).unwrap();
+    }
+
+    #[test]
+    fn test_state_transitions() {
+        let mut world = World::new();
+
+        let entered_playing = Arc::new(AtomicU32::new(0));
+        let exited_menu = Arc::new(AtomicU32::new(0));
+
+        {
+            let entered_playing = entered_playing.clone();
+            super::on_enter(GameState::Playing, move |_| {
+                entered_playing.fetch_add(1, Ordering::Relaxed);
+            });
+        }
+
+        {
+            let exited_menu = exited_menu.clone();
+            super::on_exit(GameState::Menu, move |_| {
+                exited_menu.fetch_add(1, Ordering::Relaxed);
+            });
+        }
+
+        super::init_state(GameState::Menu);
+        super::apply_transitions(&mut world);
+
+        let in_menu = RunCondition::in_state(GameState::Menu);
+
+        assert!(in_menu.evaluate(&world));
+
+        let menu_entity = Entity(world.ecs.spawn((StateScoped(GameState::Menu),)));
+        let playing_entity = Entity(world.ecs.spawn((StateScoped(GameState::Playing),)));
+
+        super::set_state(GameState::Playing);
+        super::apply_transitions(&mut world);
+        super::set_state(GameState::Playing);
+        super::apply_transitions(&mut world);
+
+        assert!(!in_menu.evaluate(&world));
+        assert_eq!(1, entered_playing.load(Ordering::Relaxed));
+        assert_eq!(1, exited_menu.load(Ordering::Relaxed));
+        assert!(!world.contains(menu_entity));
+        assert!(world.contains(playing_entity));
+        assert_eq!(
+            Some(&GameState::Menu),
+            world.resource::<State<GameState>>().unwrap().previous()
+        );
+    }
+}
@@ -0,0 +1,337 @@
+//! Change detection for components, with the [`Added`] and [`Changed`] query filters
+//!
+//! Only changes made through a system query are tracked. A system querying `&mut T` marks every
+//! `T` it is handed as changed, whether it writes to it or not. Querying [`Mut<T>`] instead marks
+//! a component only when it is actually written to, and works outside of systems as well.
+//! Changes made through [`crate::world::World::query_mut`] or other direct world access with
+//! `&mut T` are **not** tracked
+
+use core::cell::Cell;
+use core::marker::PhantomData;
+use core::ops::Deref;
+use core::ops::DerefMut;
+use core::sync::atomic::AtomicU64;
+use core::sync::atomic::Ordering;
+
+use crate::component::Component;
+use crate::system::BorrowSet;
+use crate::system::Queryable;
+
+/// The current change tick. Advanced before each system starts and before each batch of
+/// deferred world changes, so that systems can tell which changes happened after they last ran.
+/// 64 bits wide, so that it never wraps around
+static CHANGE_TICK: AtomicU64 = AtomicU64::new(1);
+
+/// Returns the current change tick
+#[inline]
+pub(crate) fn current_tick() -> u64 {
+    CHANGE_TICK.load(Ordering::Acquire)
+}
+
+/// Advances the change tick, and returns the new tick
+#[inline]
+pub(crate) fn advance_tick() -> u64 {
+    CHANGE_TICK.fetch_add(1, Ordering::AcqRel) + 1
+}
+
+thread_local! {
+    /// The tick the system running its query on this thread started at, if any
+    static SYSTEM_TICK: Cell<Option<u64>> = const { Cell::new(None) };
+}
+
+/// Makes [`Mut`] fetched on the current thread mark changes at the tick of the running system,
+/// instead of the current tick, until dropped. See [`enter_system_tick`]
+#[derive(Debug)]
+pub(crate) struct SystemTickScope {
+    /// The tick of the enclosing scope, restored when this one ends
+    previous: Option<u64>,
+}
+
+impl Drop for SystemTickScope {
+    #[inline]
+    fn drop(&mut self) {
+        SYSTEM_TICK.set(self.previous);
+    }
+}
+
+/// Enters the scope of a system started at `tick`, while running its query on the current thread.
+/// The current tick keeps advancing while the system runs, as other systems start, so without the
+/// scope a system would see its own changes on its next run
+#[inline]
+pub(crate) fn enter_system_tick(tick: u64) -> SystemTickScope {
+    SystemTickScope {
+        previous: SYSTEM_TICK.replace(Some(tick)),
+    }
+}
+
+/// Returns the tick changes made on the current thread are marked at
+#[inline]
+fn change_tick() -> u64 {
+    SYSTEM_TICK.get().unwrap_or_else(current_tick)
+}
+
+/// The ticks at which a component of type `T` was added to, and last changed on, its entity.
+/// Stored alongside the component itself. The changed tick is atomic, so components can be marked
+/// as changed through a shared borrow of their ticks
+#[derive(Debug)]
+pub struct ComponentTicks<T> {
+    added: u64,
+    changed: AtomicU64,
+    _component: PhantomData<fn() -> T>,
+}
+
+impl<T> ComponentTicks<T> {
+    /// Returns the ticks of a component added at `tick`
+    #[inline]
+    pub(crate) const fn new(tick: u64) -> Self {
+        Self {
+            added: tick,
+            changed: AtomicU64::new(tick),
+            _component: PhantomData,
+        }
+    }
+
+    /// Returns whether the component was added after `tick`
+    #[inline]
+    pub(crate) const fn is_added_after(&self, tick: u64) -> bool {
+        self.added > tick
+    }
+
+    /// Returns whether the component was added or changed after `tick`
+    #[inline]
+    pub(crate) fn is_changed_after(&self, tick: u64) -> bool {
+        self.changed.load(Ordering::Relaxed) > tick
+    }
+
+    /// Marks the component as changed at `tick`
+    #[inline]
+    pub(crate) fn mark_changed(&self, tick: u64) {
+        self.changed.store(tick, Ordering::Relaxed);
+    }
+}
+
+/// Query for mutable access to a component of type `T`, that marks the component as changed only
+/// when it is mutably dereferenced. Unlike `&mut T`, this also tracks changes made outside of
+/// systems, like through [`crate::world::World::query_mut`]
+#[derive(Debug)]
+pub struct Mut<'a, T> {
+    value: &'a mut T,
+    ticks: Option<&'a ComponentTicks<T>>,
+
+    /// The tick changes are marked at. The start tick of the system if fetched by one, or the
+    /// current tick when fetched
+    tick: u64,
+}
+
+impl<T> Deref for Mut<'_, T> {
+    type Target = T;
+
+    #[inline]
+    fn deref(&self) -> &Self::Target {
+        self.value
+    }
+}
+
+impl<T> DerefMut for Mut<'_, T> {
+    #[inline]
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        if let Some(ticks) = self.ticks {
+            ticks.mark_changed(self.tick);
+        }
+
+        self.value
+    }
+}
+
+impl<T: Component> hecs::Query for Mut<'_, T> {
+    type Item<'q> = Mut<'q, T>;
+
+    type Fetch = <(&'static mut T, Option<&'static ComponentTicks<T>>) as hecs::Query>::Fetch;
+
+    unsafe fn get<'q>(meta: &[hecs::EntityMeta], fetch: &Self::Fetch, n: usize) -> Self::Item<'q> {
+        // SAFETY: Forwarded to the query we took the fetch from, with the same guarantees
+        let (value, ticks) =
+            unsafe { <(&mut T, Option<&ComponentTicks<T>>) as hecs::Query>::get(meta, fetch, n) };
+
+        Mut {
+            value,
+            ticks,
+            tick: change_tick(),
+        }
+    }
+}
+
+impl<T: Component> Queryable for Mut<'_, T> {
+    const NUM_SHARED_BORROWS: usize = 0;
+    const NUM_EXCLUSIVE_BORROWS: usize = 1;
+
+    type ChangeTicks = ();
+
+    #[inline]
+    fn register_borrows(_shared: &mut BorrowSet, exclusive: &mut BorrowSet) {
+        exclusive.insert::<T>();
+    }
+}
+
+/// Query filter that only matches entities whose `T` component was added since the system last ran
+#[derive(Debug)]
+pub struct Added<T>(PhantomData<fn() -> T>);
+
+/// Query filter that only matches entities whose `T` component was added or changed since the
+/// system last ran.
+///
+/// A component only counts as changed if it was handed to a system as `&mut T` or written through
+/// [`Mut<T>`]. Writes through [`crate::world::World::query_mut`] with `&mut T` go unnoticed, so use
+/// [`Mut<T>`] there for components that are filtered on
+#[derive(Debug)]
+pub struct Changed<T>(PhantomData<fn() -> T>);
+
+/// Generates the [`hecs::Query`] and [`Queryable`] implementations of a change filter
+macro_rules! change_filter {
+    ($filter:ident, $check:ident) => {
+        impl<T: Component> hecs::Query for $filter<T> {
+            type Item<'q> = ();
+
+            type Fetch = <hecs::With<(), &'static T> as hecs::Query>::Fetch;
+
+            #[inline]
+            unsafe fn get<'q>(_: &[hecs::EntityMeta], _: &Self::Fetch, _: usize) -> Self::Item<'q> {
+            }
+        }
+
+        impl<T: Component> Queryable for $filter<T> {
+            const NUM_SHARED_BORROWS: usize = 1;
+            const NUM_EXCLUSIVE_BORROWS: usize = 0;
+
+            type ChangeTicks = Option<&'static ComponentTicks<T>>;
+
+            #[inline]
+            fn register_borrows(shared: &mut BorrowSet, _exclusive: &mut BorrowSet) {
+                shared.insert::<T>();
+            }
+
+            /// Components without ticks were not added through the world, so their changes are
+            /// unknown and they always pass
+            #[inline]
+            fn passes_change_filters(
+                ticks: &<Self::ChangeTicks as hecs::Query>::Item<'_>,
+                last_run: u64,
+            ) -> bool {
+                ticks.is_none_or(|ticks| ticks.$check(last_run))
+            }
+        }
+    };
+}
+
+change_filter!(Added, is_added_after);
+change_filter!(Changed, is_changed_after);
+
+#[cfg(test)]
+mod test {
+    use super::Added;
+    use super::Changed;
+    use super::ComponentTicks;
+    use super::Mut;
+    use super::advance_tick;
+    use super::enter_system_tick;
+    use crate::component::Component;
+    use crate::system::Queryable;
+
+    struct CompA(u32);
+
+    impl Component for CompA {
+        const ID: uuid::NonNilUuid =
+            uuid::NonNilUuid::new(uuid::uuid!("3cbe5d6f-37f2-4ba0-a11f-0c4e7a8b0c61")).unwrap();
+    }
+
+    /// Returns the entities passing the change filters of `Q`, marking them as changed at `tick` like a
+    /// system would
+    fn run<Q: hecs::Query + Queryable>(
+        world: &hecs::World,
+        last_run: u64,
+        tick: u64,
+    ) -> Vec<hecs::Entity> {
+        let mut passing = Vec::new();
+
+        for (entity, _, ticks) in &mut world.query::<(hecs::Entity, Q, Q::ChangeTicks)>() {
+            if Q::passes_change_filters(&ticks, last_run) {
+                Q::mark_changed(&ticks, tick);
+                passing.push(entity);
+            }
+        }
+
+        passing.sort_unstable();
+        passing
+    }
+
+    #[test]
+    fn test_change_filters() {
+        let mut world = hecs::World::new();
+
+        let old = world.spawn((CompA(0), ComponentTicks::<CompA>::new(0)));
+        let untouched = world.spawn((CompA(0), ComponentTicks::<CompA>::new(0)));
+        let new = world.spawn((CompA(0), ComponentTicks::<CompA>::new(5)));
+        let untracked = world.spawn((CompA(0),));
+
+        assert_eq!(
+            vec![old, untouched, new, untracked],
+            run::<&CompA>(&world, 0, 0)
+        );
+        assert_eq!(vec![new, untracked], run::<Added<CompA>>(&world, 0, 0));
+
+        for (entity, mut comp) in world.query_mut::<(hecs::Entity, Mut<CompA>)>() {
+            if entity == old {
+                comp.0 += 1;
+            } else {
+                assert_eq!(0, comp.0, "Reading does not mark a change");
+            }
+        }
+
+        assert_eq!(
+            vec![old, new, untracked],
+            run::<(&CompA, Changed<CompA>)>(&world, 0, 0)
+        );
+        assert_eq!(vec![new, untracked], run::<Added<CompA>>(&world, 0, 0));
+    }
+
+    #[test]
+    fn test_mutable_query_marks_changes() {
+        let mut world = hecs::World::new();
+
+        let first = world.spawn((CompA(0), ComponentTicks::<CompA>::new(1)));
+        let second = world.spawn((CompA(0), ComponentTicks::<CompA>::new(1)));
+
+        // Filters are checked before marking, so a system does not pass its own filter
+        assert!(run::<(&mut CompA, Changed<CompA>)>(&world, 2, 3).is_empty());
+        assert!(run::<Changed<CompA>>(&world, 2, 3).is_empty());
+
+        assert_eq!(vec![first, second], run::<&mut CompA>(&world, 2, 3));
+
+        assert_eq!(vec![first, second], run::<Changed<CompA>>(&world, 2, 4));
+        assert!(run::<Changed<CompA>>(&world, 3, 4).is_empty());
+    }
+
+    #[test]
+    fn test_mut_marks_system_tick() {
+        let mut world = hecs::World::new();
+
+        let entity = world.spawn((CompA(0), ComponentTicks::<CompA>::new(0)));
+
+        let start = advance_tick();
+
+        {
+            let _scope = enter_system_tick(start);
+
+            // Other systems starting in the meantime advance the current tick
+            advance_tick();
+
+            for (_, mut comp) in &mut world.query::<(hecs::Entity, Mut<CompA>)>() {
+                comp.0 += 1;
+            }
+        }
+
+        // The writes are marked at the start of the system, so it does not see them on its next run
+        assert!(run::<Changed<CompA>>(&world, start, 0).is_empty());
+        assert_eq!(vec![entity], run::<Changed<CompA>>(&world, start - 1, 0));
+    }
+}
@@ -0,0 +1,103 @@
+//! Deferred per-system command buffers
+
+use crate::builtins::components::Name;
+use crate::builtins::components::Transform;
+use crate::component::Component;
+use crate::entity::Entity;
+use crate::world::World;
+
+/// A single deferred command
+type Command = Box<dyn FnOnce(&mut World) + Send + 'static>;
+
+/// The commands recorded by a single system invocation, in the order they were recorded
+pub(crate) type CommandBuffer = Vec<Command>;
+
+/// A buffer of deferred world changes, scoped to a single system.
+///
+/// The commands are applied after the system finished, before any system that depends on it or clashes
+/// with it starts. Buffers that are applied together are applied in schedule order, and the commands
+/// within a buffer in the order they were recorded, so the result does not depend on how the systems
+/// were distributed over threads.
+/// For systems with a parallel batch size, the buffers of the batches are applied in batch order
+#[derive(derive_more::Debug)]
+pub struct Commands<'w> {
+    #[debug(skip)]
+    world: &'w World,
+
+    #[debug("{} commands", buffer.len())]
+    buffer: CommandBuffer,
+}
+
+impl<'w> Commands<'w> {
+    /// Creates a new empty command buffer for the given world
+    pub(crate) const fn new(world: &'w World) -> Self {
+        Self {
+            world,
+            buffer: Vec::new(),
+        }
+    }
+
+    /// Returns the recorded commands
+    pub(crate) fn into_buffer(self) -> CommandBuffer {
+        self.buffer
+    }
+
+    /// Spawns a new entity with the given name and a default [`Transform`]. The returned entity
+    /// is reserved immediately, so it can be used in further commands
+    pub fn spawn(&mut self, name: impl Into<String>) -> Entity {
+        let entity = Entity(self.world.ecs.reserve_entity());
+
+        self.add_component(entity, Name::new(name.into()));
+        self.add_component(entity, Transform::new());
+
+        entity
+    }
+
+    /// Adds a component to the given entity
+    pub fn add_component<C: Component>(&mut self, entity: Entity, component: C) {
+        self.add(move |world| {
+            world.add_component(entity, component);
+        });
+    }
+
+    /// Removes the component of type `C` from the given entity
+    pub fn remove_component<C: Component>(&mut self, entity: Entity) {
+        self.add(move |world| {
+            world.remove_component::<C>(entity);
+        });
+    }
+
+    /// Makes `parent` the parent of `child` in the transform hierarchy. See [`Entity::set_parent`]
+    pub fn set_parent(&mut self, child: Entity, parent: Option<Entity>) {
+        self.add(move |world| world.set_parent(child, parent));
+    }
+
+    /// Destroys the given entity and all its children
+    pub fn destroy(&mut self, entity: Entity) {
+        self.add(move |world| world.destroy(entity));
+    }
+
+    /// Inserts a resource into the world, replacing any existing resource of the same type
+    pub fn insert_resource<T: Send + Sync + 'static>(&mut self, resource: T) {
+        self.add(move |world| {
+            world.insert_resource(resource);
+        });
+    }
+
+    /// Adds a custom command, which gets exclusive access to the world once applied
+    pub fn add(&mut self, command: impl FnOnce(&mut World) + Send + 'static) {
+        self.buffer.push(Box::new(command));
+    }
+}
+
+/// Applies all command buffers to `world`, in order
+pub(crate) fn apply_command_buffers(
+    world: &mut World,
+    buffers: impl IntoIterator<Item = CommandBuffer>,
+) {
+    profiling::function_scope!();
+
+    for command in buffers.into_iter().flatten() {
+        command(world);
+    }
+}
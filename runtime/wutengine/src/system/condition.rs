@@ -0,0 +1,174 @@
+//! Run conditions, deciding whether a system runs on a given invocation
+
+use alloc::sync::Arc;
+use core::sync::atomic::AtomicU64;
+use core::sync::atomic::Ordering;
+
+use crate::state::State;
+use crate::state::States;
+use crate::world::World;
+
+/// The callback of a run condition
+type ConditionFn = dyn Fn(&World) -> bool + Send + Sync + 'static;
+
+/// Creates the callback of a run condition, with fresh state
+type MakeConditionFn = dyn Fn() -> Arc<ConditionFn> + Send + Sync + 'static;
+
+/// A condition that is evaluated each time right before its system would run. If it returns `false`,
+/// the system is skipped for that invocation.
+///
+/// Conditions are evaluated on the main thread, in schedule order, so stateful conditions behave
+/// deterministically. Every system a condition is given to keeps its own state, so a single
+/// [`Self::every`] condition can be shared by multiple systems through their [`crate::runtime::SystemConfig`]
+#[derive(derive_more::Debug, Clone)]
+#[debug("RunCondition(...)")]
+pub struct RunCondition {
+    /// The callback, with the state used when evaluating this condition directly
+    condition: Arc<ConditionFn>,
+
+    /// Creates the callback with fresh state, for each system the condition is given to
+    make: Arc<MakeConditionFn>,
+}
+
+impl RunCondition {
+    /// Creates a new run condition from the given callback. The callback is shared by all systems
+    /// the condition is given to, so it should not keep any state. Use [`Self::new_stateful`] for that
+    pub fn new(condition: impl Fn(&World) -> bool + Send + Sync + 'static) -> Self {
+        let condition: Arc<ConditionFn> = Arc::new(condition);
+
+        Self::new_stateful(move || {
+            let condition = condition.clone();
+
+            move |world| condition(world)
+        })
+    }
+
+    /// Creates a new run condition with state. `make` is called once for each system the condition
+    /// is given to, and returns the callback with the initial state for that system
+    pub fn new_stateful<F: Fn(&World) -> bool + Send + Sync + 'static>(
+        make: impl Fn() -> F + Send + Sync + 'static,
+    ) -> Self {
+        let make: Arc<MakeConditionFn> = Arc::new(move || Arc::new(make()));
+
+        Self {
+            condition: make(),
+            make,
+        }
+    }
+
+    /// A condition that is `true` once every `n` invocations, starting with the first. For a
+    /// [`super::Phase::FixedUpdate`] system, this means once every `n` fixed ticks. The invocations
+    /// are counted for each system separately
+    pub fn every(n: u64) -> Self {
+        assert_ne!(0, n, "Run condition interval cannot be zero");
+
+        Self::new_stateful(move || {
+            let counter = AtomicU64::new(0);
+
+            move |_: &World| counter.fetch_add(1, Ordering::Relaxed).is_multiple_of(n)
+        })
+    }
+
+    /// A condition that is `true` while the resource of type `T` exists and equals `value`
+    pub fn resource_equals<T: PartialEq + Send + Sync + 'static>(value: T) -> Self {
+        Self::new(move |world| world.resource::<T>().is_some_and(|res| *res == value))
+    }
+
+    /// A condition that is `true` while the resource of type `T` exists
+    pub fn resource_exists<T: Send + Sync + 'static>() -> Self {
+        Self::new(World::contains_resource::<T>)
+    }
+
+    /// A condition that is `true` while the state machine of `S` is in `state`. Use to bind
+    /// systems to an application state, see [`crate::state`]
+    pub fn in_state<S: States>(state: S) -> Self {
+        Self::new(move |world| {
+            world
+                .resource::<State<S>>()
+                .is_some_and(|current| *current.get() == state)
+        })
+    }
+
+    /// Returns a condition that is `true` only if both `self` and `other` are. `other` is not evaluated
+    /// if `self` is `false`
+    #[must_use]
+    pub fn and(self, other: Self) -> Self {
+        Self::new_stateful(move || {
+            let lhs = self.for_system();
+            let rhs = other.for_system();
+
+            move |world: &World| lhs.evaluate(world) && rhs.evaluate(world)
+        })
+    }
+
+    /// Returns a copy of this condition with fresh state, for a single system
+    #[must_use]
+    pub(crate) fn for_system(&self) -> Self {
+        Self {
+            condition: (self.make)(),
+            make: self.make.clone(),
+        }
+    }
+
+    /// Evaluates the condition
+    #[inline]
+    pub(crate) fn evaluate(&self, world: &World) -> bool {
+        (self.condition)(world)
+    }
+}
+
+impl core::ops::Not for RunCondition {
+    type Output = Self;
+
+    /// Returns a condition that is `true` if `self` is `false`, and the other way around
+    fn not(self) -> Self::Output {
+        Self::new_stateful(move || {
+            let inner = self.for_system();
+
+            move |world: &World| !inner.evaluate(world)
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::RunCondition;
+    use crate::runtime::SystemConfig;
+    use crate::runtime::SystemManifest;
+    use crate::system::Phase;
+    use crate::world::World;
+
+    #[test]
+    fn test_every() {
+        let world = World::new();
+        let condition = RunCondition::every(3);
+
+        let results: Vec<bool> = (0..7).map(|_| condition.evaluate(&world)).collect();
+
+        assert_eq!(vec![true, false, false, true, false, false, true], results);
+    }
+
+    #[test]
+    fn test_every_counts_per_system() {
+        let world = World::new();
+        let condition = !!RunCondition::every(2);
+
+        let mut manifest = SystemManifest::empty();
+        let config = SystemConfig {
+            run_condition: Some(condition),
+            ..Default::default()
+        };
+
+        manifest.add_system_with_config::<()>(Phase::Update, "A", &config, |_, ()| {});
+        manifest.add_system_with_config::<()>(Phase::Update, "B", &config, |_, ()| {});
+
+        let [a, b] = [0, 1].map(|i| manifest.systems[i].run_condition.clone().unwrap());
+
+        // Each system runs every other invocation, regardless of the other
+        assert!(a.evaluate(&world));
+        assert!(b.evaluate(&world));
+        assert!(!a.evaluate(&world));
+        assert!(!b.evaluate(&world));
+        assert!(a.evaluate(&world));
+    }
+}
@@ -0,0 +1,312 @@
+//! Event parameters for systems
+
+use core::any::TypeId;
+use core::marker::PhantomData;
+use core::sync::atomic::AtomicU64;
+use core::sync::atomic::Ordering;
+use std::sync::RwLockReadGuard;
+use std::sync::RwLockWriteGuard;
+
+use super::resource::ResourceBorrow;
+use crate::system::BorrowSet;
+use crate::system::Queryable;
+use crate::system::Resources;
+use crate::world::Events;
+use crate::world::World;
+
+/// System parameter for reading the events of type `T`. See [`World::add_event`].
+///
+/// Each system has its own cursor, so it sees every event once: the ones sent since the system last
+/// ran, at most two frames ago. A system added with [`crate::runtime::SystemManifest::add_resource_system`]
+/// is invoked once per run. Other systems are invoked once per matching entity, and while no entities
+/// match they do not read any events, so these are kept for the next run, if still alive. Events sent
+/// with [`World::send_event`] while the system runs are kept for the next run as well.
+///
+/// Borrowed like a [`crate::system::Res`] of [`Events<T>`], so readers run in parallel with each other,
+/// but never with an [`EventWriter`] of the same type
+#[derive(Debug)]
+pub struct EventReader<T>(PhantomData<fn() -> T>);
+
+/// System parameter for sending events of type `T`. See [`World::add_event`].
+///
+/// Borrowed like a [`crate::system::ResMut`] of [`Events<T>`], so writers never run in parallel with
+/// each other or with an [`EventReader`] of the same type
+#[derive(Debug)]
+pub struct EventWriter<T>(PhantomData<fn() -> T>);
+
+/// The position of an [`EventReader`] in its event queue
+#[derive(Debug, Default)]
+pub struct EventCursor {
+    /// The sequence number of the first event that was not read yet
+    next: AtomicU64,
+
+    /// The sequence number of the first event that was not received by any run yet
+    received_until: AtomicU64,
+}
+
+/// The events received by an [`EventReader`] during a single system run
+#[derive(Debug)]
+pub struct ReceivedEvents<'w, T> {
+    /// The event queue
+    events: RwLockReadGuard<'w, Events<T>>,
+
+    /// The sequence number of the first received event
+    from: u64,
+}
+
+impl<T> ReceivedEvents<'_, T> {
+    /// Returns the received events, oldest first
+    pub fn iter(&self) -> impl Iterator<Item = &T> {
+        self.events.iter_from(self.from)
+    }
+
+    /// Returns the number of received events
+    pub fn len(&self) -> usize {
+        self.iter().count()
+    }
+
+    /// Returns `true` if no events were received
+    pub fn is_empty(&self) -> bool {
+        self.iter().next().is_none()
+    }
+}
+
+/// Sends events for an [`EventWriter`]
+#[derive(Debug)]
+pub struct EventSender<'w, T> {
+    /// The event queue
+    events: RwLockWriteGuard<'w, Events<T>>,
+}
+
+impl<T> EventSender<'_, T> {
+    /// Sends an event. It can be read by other systems once this system finished
+    #[inline]
+    pub fn send(&mut self, event: T) {
+        self.events.send(event);
+    }
+}
+
+impl<T: Send + Sync + 'static> Queryable for EventReader<T> {
+    const NUM_SHARED_BORROWS: usize = 1;
+    const NUM_EXCLUSIVE_BORROWS: usize = 0;
+
+    type ChangeTicks = ();
+
+    #[inline]
+    fn register_borrows(shared: &mut BorrowSet, _exclusive: &mut BorrowSet) {
+        shared.insert_named(
+            TypeId::of::<ResourceBorrow<Events<T>>>(),
+            core::any::type_name::<Self>(),
+        );
+    }
+}
+
+impl<T: Send + Sync + 'static> Resources for EventReader<T> {
+    type Item<'w> = ReceivedEvents<'w, T>;
+    type State = EventCursor;
+
+    #[inline]
+    fn fetch<'w>(world: &'w World, state: &'w Self::State) -> Result<Self::Item<'w>, &'static str> {
+        let events = world
+            .resource::<Events<T>>()
+            .ok_or(core::any::type_name::<Events<T>>())?;
+
+        // No events can be sent while the queue is borrowed, so these are all events that are received
+        state
+            .received_until
+            .fetch_max(events.next_sequence(), Ordering::AcqRel);
+
+        Ok(ReceivedEvents {
+            events,
+            from: state.next.load(Ordering::Acquire),
+        })
+    }
+
+    fn finish_run(_world: &World, state: &Self::State) {
+        // Events sent through the world after the last fetch were not received, so they are not skipped
+        state.next.store(
+            state.received_until.load(Ordering::Acquire),
+            Ordering::Release,
+        );
+    }
+}
+
+impl<T: Send + Sync + 'static> Queryable for EventWriter<T> {
+    const NUM_SHARED_BORROWS: usize = 0;
+    const NUM_EXCLUSIVE_BORROWS: usize = 1;
+
+    type ChangeTicks = ();
+
+    #[inline]
+    fn register_borrows(_shared: &mut BorrowSet, exclusive: &mut BorrowSet) {
+        exclusive.insert_named(
+            TypeId::of::<ResourceBorrow<Events<T>>>(),
+            core::any::type_name::<Self>(),
+        );
+    }
+}
+
+impl<T: Send + Sync + 'static> Resources for EventWriter<T> {
+    type Item<'w> = EventSender<'w, T>;
+    type State = ();
+
+    #[inline]
+    fn fetch<'w>(
+        world: &'w World,
+        _state: &'w Self::State,
+    ) -> Result<Self::Item<'w>, &'static str> {
+        let events = world
+            .resource_mut::<Events<T>>()
+            .ok_or(core::any::type_name::<Events<T>>())?;
+
+        Ok(EventSender { events })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use alloc::sync::Arc;
+    use core::sync::atomic::AtomicUsize;
+    use core::sync::atomic::Ordering;
+
+    use crate::runtime::SystemConfig;
+    use crate::runtime::SystemManifest;
+    use crate::system::EventReader;
+    use crate::system::EventWriter;
+    use crate::system::GenericSystem;
+    use crate::system::Phase;
+    use crate::system::Resources;
+    use crate::system::SystemCallback;
+    use crate::world::World;
+
+    use super::EventCursor;
+
+    struct Damage(u32);
+
+    struct Target;
+
+    /// Returns the callbacks of the two systems in the manifest, in insertion order
+    fn callbacks(manifest: &SystemManifest) -> [Arc<GenericSystem>; 2] {
+        [0, 1].map(|i| match &manifest.systems[i].callback {
+            SystemCallback::Parallel(callback) => callback.clone(),
+            SystemCallback::Exclusive(_) => unreachable!(),
+        })
+    }
+
+    #[test]
+    fn test_event_reader_cursor() {
+        let mut world = World::new();
+        world.add_event::<Damage>();
+
+        let received = Arc::new(AtomicUsize::new(0));
+        let received_by_system = received.clone();
+
+        let mut manifest = SystemManifest::empty();
+        manifest.add_resource_system::<EventWriter<Damage>>(
+            Phase::Update,
+            "Writer",
+            &SystemConfig::default(),
+            |_, events| events.send(Damage(5)),
+        );
+        manifest.add_resource_system::<EventReader<Damage>>(
+            Phase::Update,
+            "Reader",
+            &SystemConfig::default(),
+            move |_, events| {
+                let total: u32 = events.iter().map(|damage| damage.0).sum();
+                received_by_system.fetch_add(total as usize, Ordering::Relaxed);
+            },
+        );
+
+        let [writer, reader] = callbacks(&manifest);
+
+        writer(&world);
+        reader(&world);
+        assert_eq!(5, received.load(Ordering::Relaxed));
+
+        // Already read events are not read again, even while they are still alive
+        world.update_events();
+        reader(&world);
+        assert_eq!(5, received.load(Ordering::Relaxed));
+
+        writer(&world);
+        world.update_events();
+        world.update_events();
+
+        // Events older than two frames are dropped before they are read
+        reader(&world);
+        assert_eq!(5, received.load(Ordering::Relaxed));
+    }
+
+    #[test]
+    fn test_event_reader_per_entity() {
+        let mut world = World::new();
+        world.add_event::<Damage>();
+
+        let received = Arc::new(AtomicUsize::new(0));
+        let received_by_system = received.clone();
+
+        let mut manifest = SystemManifest::empty();
+        manifest.add_resource_system::<EventWriter<Damage>>(
+            Phase::Update,
+            "Writer",
+            &SystemConfig::default(),
+            |_, events| events.send(Damage(5)),
+        );
+        manifest.add_system_with_resources::<EventReader<Damage>, &Target>(
+            Phase::Update,
+            "Reader",
+            &SystemConfig::default(),
+            move |_, events, _, _| {
+                let total: u32 = events.iter().map(|damage| damage.0).sum();
+                received_by_system.fetch_add(total as usize, Ordering::Relaxed);
+            },
+        );
+
+        let [writer, reader] = callbacks(&manifest);
+
+        // Without matching entities, the events are not read and stay unread
+        writer(&world);
+        reader(&world);
+        assert_eq!(0, received.load(Ordering::Relaxed));
+
+        // Every entity sees the same events, which are read only once
+        for _ in 0..3 {
+            world.ecs.spawn((Target,));
+        }
+
+        reader(&world);
+        assert_eq!(15, received.load(Ordering::Relaxed));
+
+        reader(&world);
+        assert_eq!(15, received.load(Ordering::Relaxed));
+
+        writer(&world);
+        reader(&world);
+        assert_eq!(30, received.load(Ordering::Relaxed));
+    }
+
+    #[test]
+    fn test_event_sent_during_run() {
+        let mut world = World::new();
+        world.add_event::<Damage>();
+
+        let cursor = EventCursor::default();
+
+        world.send_event(Damage(5));
+
+        let events = EventReader::<Damage>::fetch(&world, &cursor).unwrap();
+        assert_eq!(1, events.len());
+        drop(events);
+
+        // Sent after the reader received its events, but before its run finished
+        world.send_event(Damage(7));
+        EventReader::<Damage>::finish_run(&world, &cursor);
+
+        let events = EventReader::<Damage>::fetch(&world, &cursor).unwrap();
+        assert_eq!(
+            vec![7],
+            events.iter().map(|damage| damage.0).collect::<Vec<_>>()
+        );
+    }
+}
@@ -0,0 +1,428 @@
+//! Dynamic execution of the systems of a phase. Instead of waiting for whole stages, each system
+//! starts as soon as all systems it is ordered after have finished
+
+use core::panic::AssertUnwindSafe;
+use std::collections::BTreeSet;
+use std::sync::mpsc;
+use std::time::Duration;
+use std::time::Instant;
+
+use wutengine_util::assert_main_thread;
+
+use super::{
+    CommandBuffer, Phase, SystemManager, SystemSet, advance_tick, apply_command_buffers,
+    scheduler::find_clashing_borrow,
+};
+
+/// The order in which the systems of a single phase must run. Systems are identified by their
+/// index in schedule order, which is stage by stage, and within a stage in insertion order
+#[derive(Debug, Default)]
+pub(crate) struct SystemGraph {
+    /// The stage and index within that stage of each system
+    nodes: Vec<(usize, usize)>,
+
+    /// For each system, the systems that can not start before it finished
+    successors: Vec<Vec<usize>>,
+
+    /// For each system, the number of systems that must finish before it can start
+    num_predecessors: Vec<usize>,
+}
+
+impl SystemGraph {
+    /// Builds the graph of the systems in `sets`. A system must wait for the systems it depends
+    /// on, for all systems in earlier stages whose borrows clash with its own, and for all systems
+    /// in earlier stages that can record commands. Systems with clashing borrows therefore never
+    /// run in parallel, and always run in stage order
+    pub(crate) fn build(sets: &[SystemSet]) -> Self {
+        let nodes: Vec<(usize, usize)> = sets
+            .iter()
+            .enumerate()
+            .flat_map(|(stage, set)| (0..set.infos.len()).map(move |i| (stage, i)))
+            .collect();
+
+        let mut successors = vec![Vec::new(); nodes.len()];
+        let mut num_predecessors = vec![0; nodes.len()];
+
+        for (after, &(after_stage, after_index)) in nodes.iter().enumerate() {
+            let after_info = &sets[after_stage].infos[after_index];
+
+            for (before, &(before_stage, before_index)) in nodes[..after].iter().enumerate() {
+                if before_stage == after_stage {
+                    continue;
+                }
+
+                let before_info = &sets[before_stage].infos[before_index];
+
+                if before_info.records_commands
+                    || after_info.dependencies.contains(&before_info.id)
+                    || find_clashing_borrow([before_info], after_info).is_some()
+                {
+                    successors[before].push(after);
+                    num_predecessors[after] += 1;
+                }
+            }
+        }
+
+        Self {
+            nodes,
+            successors,
+            num_predecessors,
+        }
+    }
+
+    /// Returns the number of systems in the graph
+    #[inline]
+    fn len(&self) -> usize {
+        self.nodes.len()
+    }
+}
+
+/// The progress of a single run of the systems of a phase
+struct Execution {
+    /// For each system, the number of its predecessors that have not finished yet
+    remaining_predecessors: Vec<usize>,
+
+    /// For each system, whether one of its finished predecessors recorded commands that were not applied yet
+    needs_commands: Vec<bool>,
+
+    /// The systems whose predecessors all finished, but that have not started yet
+    ready: BTreeSet<usize>,
+
+    /// The non-empty command buffers of finished systems that were not applied yet, by system
+    pending_commands: Vec<(usize, CommandBuffer)>,
+
+    /// The number of systems that finished
+    num_finished: usize,
+
+    /// The execution time of each system that ran
+    durations: Vec<(usize, Duration)>,
+}
+
+impl Execution {
+    /// Starts a new run of the systems in `graph`
+    fn new(graph: &SystemGraph) -> Self {
+        Self {
+            remaining_predecessors: graph.num_predecessors.clone(),
+            needs_commands: vec![false; graph.len()],
+            ready: (0..graph.len())
+                .filter(|&system| graph.num_predecessors[system] == 0)
+                .collect(),
+            pending_commands: Vec::new(),
+            num_finished: 0,
+            durations: Vec::new(),
+        }
+    }
+
+    /// Returns `true` if all systems finished
+    fn is_done(&self, graph: &SystemGraph) -> bool {
+        self.num_finished == graph.len()
+    }
+
+    /// Takes the first ready system that can start in parallel with the running systems
+    fn take_parallel(&mut self, graph: &SystemGraph, sets: &[SystemSet]) -> Option<usize> {
+        let system = self.ready.iter().copied().find(|&system| {
+            let (stage, _) = graph.nodes[system];
+
+            sets[stage].exclusive_system.is_none() && !self.needs_commands[system]
+        })?;
+
+        self.ready.remove(&system);
+
+        Some(system)
+    }
+
+    /// Takes the first ready system if it is exclusive. Only valid while no systems are running
+    fn take_exclusive(&mut self, graph: &SystemGraph, sets: &[SystemSet]) -> Option<usize> {
+        let system = self.ready.first().copied()?;
+        let (stage, _) = graph.nodes[system];
+
+        sets[stage].exclusive_system.as_ref()?;
+
+        self.ready.remove(&system);
+
+        Some(system)
+    }
+
+    /// Marks `system` as finished, and its successors as ready if it was their last predecessor
+    fn finish(&mut self, graph: &SystemGraph, system: usize, commands: CommandBuffer) {
+        self.num_finished += 1;
+
+        let has_commands = !commands.is_empty();
+
+        if has_commands {
+            self.pending_commands.push((system, commands));
+        }
+
+        for &successor in &graph.successors[system] {
+            self.needs_commands[successor] |= has_commands;
+            self.remaining_predecessors[successor] -= 1;
+
+            if self.remaining_predecessors[successor] == 0 {
+                self.ready.insert(successor);
+            }
+        }
+    }
+
+    /// Takes the pending command buffers, in schedule order. Only valid while no systems are running
+    fn take_pending_commands(&mut self) -> Vec<CommandBuffer> {
+        self.needs_commands.fill(false);
+
+        let mut pending = core::mem::take(&mut self.pending_commands);
+        pending.sort_unstable_by_key(|(system, _)| *system);
+
+        pending.into_iter().map(|(_, commands)| commands).collect()
+    }
+}
+
+/// System execution
+impl SystemManager {
+    /// Runs all systems for a given phase on the global world. Each system starts as soon as all systems
+    /// it is ordered after have finished, and their commands have been applied. Systems with clashing borrows
+    /// run in the order of their stages.
+    ///
+    /// Pending commands are applied when a system that is ordered after their system, or that is in a later stage,
+    /// is about to start, before an exclusive system runs, and at the end of the phase. Applying them waits for all
+    /// running systems. Systems that can not record commands, like the ones added with
+    /// [`crate::runtime::SystemManifest::add_system`], do not hold back later stages.
+    ///
+    /// The execution time of every system that ran is recorded in the system statistics
+    pub(crate) fn run_systems_for_phase(&mut self, phase: Phase) {
+        profiling::function_scope!(phase.str());
+
+        assert_main_thread!();
+
+        log::trace!("Running systems for phase {phase}");
+
+        let Some(scheduled) = self.find_phase(phase) else {
+            return;
+        };
+
+        let sets = scheduled.sets.as_slice();
+        let graph = &scheduled.graph;
+
+        let mut execution = Execution::new(graph);
+
+        while !execution.is_done(graph) {
+            self.run_parallel_systems(sets, graph, &mut execution);
+
+            // Nothing is running anymore, so the world can be borrowed mutably
+            let mut world = crate::world::get_world_mut();
+
+            if !execution.pending_commands.is_empty() {
+                advance_tick();
+                apply_command_buffers(&mut world, execution.take_pending_commands());
+            }
+
+            if let Some(system) = execution.take_exclusive(graph, sets) {
+                let (stage, i) = graph.nodes[system];
+                let set = &sets[stage];
+
+                if self.should_run(set, i, &world) {
+                    let exclusive_system = set
+                        .exclusive_system
+                        .as_ref()
+                        .expect("Checked by take_exclusive");
+
+                    advance_tick();
+
+                    let start = Instant::now();
+                    exclusive_system(&mut world);
+                    execution.durations.push((system, start.elapsed()));
+                }
+
+                execution.finish(graph, system, CommandBuffer::new());
+            }
+        }
+
+        // Commands of the last systems in the phase
+        if !execution.pending_commands.is_empty() {
+            advance_tick();
+            apply_command_buffers(
+                &mut crate::world::get_world_mut(),
+                execution.take_pending_commands(),
+            );
+        }
+
+        let durations: Vec<_> = execution
+            .durations
+            .iter()
+            .map(|&(system, duration)| {
+                let (stage, i) = graph.nodes[system];
+
+                (sets[stage].infos[i].id, duration)
+            })
+            .collect();
+
+        for (id, duration) in durations {
+            self.timings.entry(id).or_default().record(duration);
+        }
+    }
+
+    /// Runs systems on the thread pool until no more systems can start without first applying commands
+    /// or running an exclusive system. Returns once all started systems have finished
+    fn run_parallel_systems(
+        &self,
+        sets: &[SystemSet],
+        graph: &SystemGraph,
+        execution: &mut Execution,
+    ) {
+        profiling::function_scope!();
+
+        let world = crate::world::get_world();
+        let world = &*world;
+
+        let (sender, receiver) = mpsc::channel();
+
+        // Dispatched from this thread, so that run conditions are evaluated on it
+        rayon::in_place_scope(|scope| {
+            let mut num_running = 0_usize;
+
+            loop {
+                while let Some(system) = execution.take_parallel(graph, sets) {
+                    let (stage, i) = graph.nodes[system];
+                    let set = &sets[stage];
+
+                    if !self.should_run(set, i, world) {
+                        execution.finish(graph, system, CommandBuffer::new());
+                        continue;
+                    }
+
+                    // Every system gets its own tick, so it only sees changes made after it started
+                    advance_tick();
+
+                    let callback = &set.systems[i];
+                    let sender = sender.clone();
+
+                    num_running += 1;
+
+                    scope.spawn(move |_| {
+                        let start = Instant::now();
+                        let result = std::panic::catch_unwind(AssertUnwindSafe(|| callback(world)));
+
+                        // The receiver only hangs up once all systems finished
+                        sender.send((system, result, start.elapsed())).unwrap();
+                    });
+                }
+
+                if num_running == 0 {
+                    break;
+                }
+
+                let (system, result, duration) = receiver.recv().expect("Sender is kept alive");
+                num_running -= 1;
+
+                match result {
+                    Ok(commands) => {
+                        execution.durations.push((system, duration));
+                        execution.finish(graph, system, commands);
+                    }
+                    Err(panic) => std::panic::resume_unwind(panic),
+                }
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::runtime::SystemConfig;
+    use crate::runtime::SystemManifest;
+    use crate::system::Phase;
+    use crate::system::SystemManager;
+
+    struct CompA;
+    struct CompB;
+
+    /// Returns the names of the systems that `name` directly waits for
+    fn predecessors(manager: &SystemManager, name: &str) -> Vec<&'static str> {
+        let scheduled = manager.find_phase(Phase::Update).unwrap();
+        let graph = &scheduled.graph;
+
+        let name_of = |system: usize| {
+            let (stage, i) = graph.nodes[system];
+            scheduled.sets[stage].infos[i].name
+        };
+
+        let mut predecessors: Vec<_> = (0..graph.len())
+            .filter(|&system| {
+                graph.successors[system]
+                    .iter()
+                    .any(|&successor| name_of(successor) == name)
+            })
+            .map(name_of)
+            .collect();
+
+        predecessors.sort_unstable();
+        predecessors
+    }
+
+    #[test]
+    fn test_system_graph() {
+        let mut manifest = SystemManifest::empty();
+
+        let slow = manifest.add_system::<&mut CompA>(Phase::Update, "Slow", |_, _| {});
+        let fast = manifest.add_system::<&CompB>(Phase::Update, "Fast", |_, _| {});
+        manifest.add_system_with_config::<&CompB>(
+            Phase::Update,
+            "After fast",
+            &SystemConfig {
+                dependencies: &[fast],
+                ..Default::default()
+            },
+            |_, _| {},
+        );
+        manifest.add_system_with_config::<&CompA>(
+            Phase::Update,
+            "After slow",
+            &SystemConfig {
+                dependencies: &[slow],
+                ..Default::default()
+            },
+            |_, _| {},
+        );
+        manifest.add_exclusive_system(Phase::Update, "Exclusive", |_| {});
+
+        let mut manager = SystemManager::new();
+        manager.build_schedule(manifest).unwrap();
+
+        // "After fast" is in the same stage as "After slow", but does not wait for "Slow"
+        assert_eq!(vec!["Fast"], predecessors(&manager, "After fast"));
+        assert_eq!(vec!["Slow"], predecessors(&manager, "After slow"));
+        assert_eq!(
+            vec!["After fast", "After slow", "Fast", "Slow"],
+            predecessors(&manager, "Exclusive")
+        );
+    }
+
+    #[test]
+    fn test_later_stages_wait_for_commands() {
+        let mut manifest = SystemManifest::empty();
+
+        manifest.add_system_with_commands::<&CompA>(
+            Phase::Update,
+            "Records commands",
+            &SystemConfig::default(),
+            |_, _, _| {},
+        );
+        manifest.add_system::<&CompA>(Phase::Update, "No commands", |_, _| {});
+        let first = manifest.add_system::<&CompB>(Phase::Update, "First", |_, _| {});
+        manifest.add_system_with_config::<&CompB>(
+            Phase::Update,
+            "Second",
+            &SystemConfig {
+                dependencies: &[first],
+                ..Default::default()
+            },
+            |_, _| {},
+        );
+
+        let mut manager = SystemManager::new();
+        manager.build_schedule(manifest).unwrap();
+
+        // "Second" does not borrow anything "Records commands" writes to, but must see its commands applied
+        assert_eq!(
+            vec!["First", "Records commands"],
+            predecessors(&manager, "Second")
+        );
+    }
+}
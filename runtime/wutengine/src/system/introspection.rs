@@ -0,0 +1,342 @@
+//! Structured views of the built system schedule, for debugging and visualizing system ordering
+
+use alloc::sync::Arc;
+use core::fmt::Display;
+use core::fmt::Write;
+use std::path::Path;
+use std::sync::LazyLock;
+use std::sync::RwLock;
+
+use super::BorrowSet;
+use super::Phase;
+use super::SystemId;
+use super::SystemLabel;
+
+/// The schedule that was last built by the runtime
+static CURRENT_SCHEDULE: LazyLock<RwLock<Arc<ScheduleInfo>>> =
+    LazyLock::new(|| RwLock::new(Arc::new(ScheduleInfo::default())));
+
+/// The config key of the path the schedule is exported to whenever it is rebuilt.
+/// Files ending in `.json` are exported with [`ScheduleInfo::to_json`], all others with [`ScheduleInfo::to_dot`]
+const EXPORT_PATH_KEY: &str = "wutengine.system.schedule_export_path";
+
+/// The full system schedule, ordered by phase
+#[derive(Debug, Clone, Default)]
+pub struct ScheduleInfo {
+    /// The phases with at least one system, in scheduling order
+    pub phases: Vec<PhaseInfo>,
+}
+
+/// The schedule of a single phase
+#[derive(Debug, Clone)]
+pub struct PhaseInfo {
+    /// The phase
+    pub phase: Phase,
+
+    /// The stages of the phase. Systems run in stage order only where they are ordered after
+    /// each other by a dependency or a borrow clash
+    pub stages: Vec<StageInfo>,
+}
+
+/// A single stage of a phase. All systems in a stage can run in parallel
+#[derive(Debug, Clone)]
+pub struct StageInfo {
+    /// The systems in the stage
+    pub systems: Vec<SystemInfo>,
+}
+
+impl StageInfo {
+    /// Returns whether this stage contains a single system with exclusive world access
+    #[inline]
+    pub fn is_exclusive(&self) -> bool {
+        self.systems.iter().any(|sys| sys.exclusive)
+    }
+}
+
+/// A scheduled system, and the reasons it was placed in its stage
+#[derive(Debug, Clone)]
+pub struct SystemInfo {
+    /// The ID of the system
+    pub id: SystemId,
+
+    /// The name of the system
+    pub name: &'static str,
+
+    /// Whether the system has exclusive world access
+    pub exclusive: bool,
+
+    /// Whether the system can record commands. Systems in later stages wait until its commands were applied
+    pub records_commands: bool,
+
+    /// The types the system borrows immutably
+    pub shared_borrows: BorrowSet,
+
+    /// The types the system borrows mutably
+    pub exclusive_borrows: BorrowSet,
+
+    /// The systems in the same phase that must run before this system, either through explicit
+    /// dependencies or through label ordering
+    pub dependencies: Vec<SystemId>,
+
+    /// The labels of the system
+    pub labels: Vec<SystemLabel>,
+
+    /// The first stage after all of [`Self::dependencies`]
+    pub earliest_stage: usize,
+
+    /// Why the system was not placed in each of the stages between [`Self::earliest_stage`] and its
+    /// actual stage
+    pub clashes: Vec<StageClash>,
+}
+
+/// Why a system could not be placed in a stage
+#[derive(Debug, Clone, Copy)]
+pub struct StageClash {
+    /// The stage the system could not be placed in
+    pub stage: usize,
+
+    /// The system in that stage that it clashed with
+    pub system: SystemId,
+
+    /// The name of the system it clashed with
+    pub system_name: &'static str,
+
+    /// The type name of the clashing borrow, or [`None`] if the other system has exclusive world access
+    pub borrow: Option<&'static str>,
+}
+
+impl Display for StageClash {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self.borrow {
+            Some(borrow) => write!(
+                f,
+                "stage {} borrows {borrow} in `{}`",
+                self.stage, self.system_name
+            ),
+            None => write!(
+                f,
+                "stage {} has exclusive system `{}`",
+                self.stage, self.system_name
+            ),
+        }
+    }
+}
+
+impl ScheduleInfo {
+    /// Returns the schedule as a Graphviz DOT graph. Each phase and stage is a cluster, dependencies
+    /// are solid edges and borrow clashes are dashed edges labelled with the clashing borrow
+    pub fn to_dot(&self) -> String {
+        let mut dot = String::from("digraph schedule {\n\tcompound=true;\n\tnode [shape=box];\n");
+        let mut edges = String::new();
+
+        for (p, phase) in self.phases.iter().enumerate() {
+            _ = writeln!(
+                dot,
+                "\tsubgraph cluster_{p} {{\n\t\tlabel=\"{}\";",
+                escape(phase.phase.str())
+            );
+
+            for (s, stage) in phase.stages.iter().enumerate() {
+                _ = writeln!(
+                    dot,
+                    "\t\tsubgraph cluster_{p}_{s} {{\n\t\t\tlabel=\"Stage {s}\";"
+                );
+
+                for system in &stage.systems {
+                    let mut label = escape(system.name);
+
+                    if system.exclusive {
+                        label.push_str("\\n(exclusive world access)");
+                    }
+
+                    for (kind, borrows) in [
+                        ("shared", &system.shared_borrows),
+                        ("exclusive", &system.exclusive_borrows),
+                    ] {
+                        if !borrows.is_empty() {
+                            _ = write!(label, "\\n{kind}: {}", escape(&borrows.names().join(", ")));
+                        }
+                    }
+
+                    _ = writeln!(dot, "\t\t\ts{} [label=\"{label}\"];", system.id.id());
+
+                    for dependency in &system.dependencies {
+                        _ = writeln!(edges, "\ts{} -> s{};", dependency.id(), system.id.id());
+                    }
+
+                    for clash in &system.clashes {
+                        _ = writeln!(
+                            edges,
+                            "\ts{} -> s{} [style=dashed, color=red, label=\"{}\"];",
+                            clash.system.id(),
+                            system.id.id(),
+                            escape(clash.borrow.unwrap_or("exclusive"))
+                        );
+                    }
+                }
+
+                dot.push_str("\t\t}\n");
+            }
+
+            dot.push_str("\t}\n");
+        }
+
+        dot.push_str(&edges);
+        dot.push_str("}\n");
+
+        dot
+    }
+
+    /// Returns the schedule as pretty-printed JSON
+    pub fn to_json(&self) -> String {
+        let phases: Vec<_> = self
+            .phases
+            .iter()
+            .map(|phase| {
+                let stages: Vec<_> = phase
+                    .stages
+                    .iter()
+                    .map(|stage| {
+                        let systems: Vec<_> = stage.systems.iter().map(system_to_json).collect();
+
+                        serde_json::json!({ "systems": systems })
+                    })
+                    .collect();
+
+                serde_json::json!({
+                    "phase": phase.phase.str(),
+                    "stages": stages,
+                })
+            })
+            .collect();
+
+        serde_json::to_string_pretty(&serde_json::json!({ "phases": phases }))
+            .expect("Schedule JSON is always serializable")
+    }
+
+    /// Writes the schedule to the file at `path`. Files with a `.json` extension are written with
+    /// [`Self::to_json`], all others with [`Self::to_dot`]
+    pub fn export(&self, path: impl AsRef<Path>) -> std::io::Result<()> {
+        let path = path.as_ref();
+
+        let contents = if path.extension().is_some_and(|ext| ext == "json") {
+            self.to_json()
+        } else {
+            self.to_dot()
+        };
+
+        std::fs::write(path, contents)
+    }
+}
+
+impl Display for ScheduleInfo {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        for phase in &self.phases {
+            writeln!(f, "Phase: {}", phase.phase)?;
+
+            for (s, stage) in phase.stages.iter().enumerate() {
+                let names: Vec<_> = stage.systems.iter().map(|sys| sys.name).collect();
+
+                writeln!(f, "\tStage {s}: {}", names.join(", "))?;
+
+                for system in &stage.systems {
+                    for clash in &system.clashes {
+                        writeln!(f, "\t\t`{}` skipped {clash}", system.name)?;
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Returns the system schedule that is currently used by the runtime. The returned schedule
+/// is not updated when systems are inserted or removed afterwards
+pub fn current_schedule() -> Arc<ScheduleInfo> {
+    CURRENT_SCHEDULE.read().unwrap().clone()
+}
+
+/// Makes `schedule` available through [`current_schedule`], and exports it to the file
+/// configured in the WutEngine config, if any
+pub(crate) fn publish_schedule(schedule: ScheduleInfo) {
+    if let Some(path) = crate::config::try_get::<String>(EXPORT_PATH_KEY) {
+        match schedule.export(&path) {
+            Ok(()) => log::debug!("Exported system schedule to {path}"),
+            Err(e) => log::error!("Failed to export system schedule to {path}: {e}"),
+        }
+    }
+
+    *CURRENT_SCHEDULE.write().unwrap() = Arc::new(schedule);
+}
+
+fn system_to_json(system: &SystemInfo) -> serde_json::Value {
+    let clashes: Vec<_> = system
+        .clashes
+        .iter()
+        .map(|clash| {
+            serde_json::json!({
+                "stage": clash.stage,
+                "system": clash.system.id(),
+                "system_name": clash.system_name,
+                "borrow": clash.borrow,
+            })
+        })
+        .collect();
+
+    serde_json::json!({
+        "id": system.id.id(),
+        "name": system.name,
+        "exclusive": system.exclusive,
+        "records_commands": system.records_commands,
+        "shared_borrows": system.shared_borrows.names(),
+        "exclusive_borrows": system.exclusive_borrows.names(),
+        "dependencies": system.dependencies.iter().map(|dep| dep.id()).collect::<Vec<_>>(),
+        "labels": system.labels.iter().map(|label| label.name()).collect::<Vec<_>>(),
+        "earliest_stage": system.earliest_stage,
+        "clashes": clashes,
+    })
+}
+
+/// Escapes a string for use inside a quoted DOT attribute
+fn escape(s: &str) -> String {
+    s.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+#[cfg(test)]
+mod test {
+    use crate::runtime::SystemManifest;
+    use crate::system::Phase;
+    use crate::system::SystemManager;
+
+    struct CompA;
+
+    #[test]
+    fn test_clash_reason() {
+        let mut manifest = SystemManifest::empty();
+
+        let read = manifest.add_system::<&CompA>(Phase::Update, "Read", |_, _| {});
+        manifest.add_system::<&mut CompA>(Phase::Update, "Write", |_, _| {});
+
+        let mut manager = SystemManager::new();
+        manager.build_schedule(manifest).unwrap();
+
+        let schedule = manager.schedule_info();
+        let stages = &schedule.phases[0].stages;
+
+        assert_eq!(2, stages.len());
+
+        let write = &stages[1].systems[0];
+
+        assert_eq!(0, write.earliest_stage);
+        assert_eq!(1, write.clashes.len());
+        assert_eq!(read, write.clashes[0].system);
+        assert_eq!(
+            Some(core::any::type_name::<CompA>()),
+            write.clashes[0].borrow
+        );
+
+        assert!(schedule.to_dot().contains("style=dashed"));
+        assert!(schedule.to_json().contains("\"system_name\": \"Read\""));
+    }
+}
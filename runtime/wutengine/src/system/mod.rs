@@ -1,24 +1,68 @@
 //! WutEngine ECS system registration and query helpers
 
 use alloc::sync::Arc;
-use core::any::TypeId;
 use core::fmt::Display;
 use core::sync::atomic::AtomicU32;
 use core::sync::atomic::Ordering;
-use rayon::prelude::*;
+use std::collections::HashMap;
 use std::collections::HashSet;
-
+use std::sync::Mutex;
+
+mod change;
+mod commands;
+mod condition;
+mod events;
+mod executor;
+mod introspection;
+mod phase;
 mod queryable;
+mod resource;
 mod scheduler;
-
+mod stats;
+
+pub use change::{Added, Changed, ComponentTicks, Mut};
+pub(crate) use change::{advance_tick, current_tick, enter_system_tick};
+pub use commands::*;
+pub use condition::*;
+pub use events::*;
+pub(crate) use introspection::publish_schedule;
+pub use introspection::{
+    PhaseInfo, ScheduleInfo, StageClash, StageInfo, SystemInfo, current_schedule,
+};
+pub use phase::*;
 pub use queryable::*;
+pub use resource::*;
+pub use scheduler::ScheduleErr;
+pub use stats::{
+    PhaseStats, STATS_WINDOW, ScheduleStats, SystemStats, current_system_stats,
+    set_count_matching_entities,
+};
+pub(crate) use stats::{count_matching_entities, publish_stats};
+
+use executor::SystemGraph;
+use stats::SystemTimings;
 
 use crate::runtime::SystemManifest;
 use crate::world::World;
-use wutengine_util::assert_main_thread;
 
-/// The generic type used for a non-typed system callback.
-pub(crate) type GenericSystem = dyn Fn(&World) + Send + Sync + 'static;
+/// The generic type used for a non-typed system callback. Returns the commands recorded by the system
+pub(crate) type GenericSystem = dyn Fn(&World) -> CommandBuffer + Send + Sync + 'static;
+
+/// Counts the entities matching the query of a system
+pub(crate) type EntityCountFn = dyn Fn(&World) -> usize + Send + Sync + 'static;
+
+/// The type used for a system callback with exclusive world access
+pub(crate) type ExclusiveSystem = dyn Fn(&mut World) + Send + Sync + 'static;
+
+/// The callback of a system, either running in parallel with other systems or exclusively
+#[derive(Clone)]
+pub(crate) enum SystemCallback {
+    /// Runs in parallel with other systems, with shared world access
+    Parallel(Arc<GenericSystem>),
+
+    /// Runs alone, in its own stage, with exclusive world access
+    Exclusive(Arc<ExclusiveSystem>),
+}
 
 /// The ID of a system
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
@@ -46,6 +90,39 @@ impl SystemId {
     }
 }
 
+impl Display for SystemId {
+    #[inline]
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "#{} ({})", self.0, self.1)
+    }
+}
+
+/// A named label for a set of systems. Systems can be ordered relative to all systems with a label,
+/// using [`crate::runtime::SystemConfig::before`] and [`crate::runtime::SystemConfig::after`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct SystemLabel(&'static str);
+
+impl SystemLabel {
+    /// Creates a new label with the given name. Labels with the same name are equal
+    #[inline]
+    pub const fn new(name: &'static str) -> Self {
+        Self(name)
+    }
+
+    /// Returns the name of the label
+    #[inline]
+    pub const fn name(self) -> &'static str {
+        self.0
+    }
+}
+
+impl Display for SystemLabel {
+    #[inline]
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
 impl PartialOrd for SystemId {
     #[inline]
     fn partial_cmp(&self, other: &Self) -> Option<core::cmp::Ordering> {
@@ -60,171 +137,366 @@ impl Ord for SystemId {
     }
 }
 
+/// A change to an already inserted system
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum SystemUpdate {
+    /// Remove the system from the schedule
+    Remove,
+
+    /// Stop running the system, but keep it in the schedule
+    Pause,
+
+    /// Resume running a paused system
+    Resume,
+}
+
 /// The system manager. Contains the full schedule of all systems, ordered by phase
 #[derive(Debug)]
 pub(crate) struct SystemManager {
-    pending_systems: Option<SystemManifest>,
+    /// The manifests queued for insertion, in insertion order
+    pending_systems: Vec<(SystemManifest, SystemInsertion)>,
+
     current_manifest: SystemManifest,
-    by_phase: Vec<(Phase, Vec<SystemSet>)>,
+    by_phase: Vec<(Phase, ScheduledPhase)>,
+
+    /// Systems that are scheduled, but should currently not run
+    paused: HashSet<SystemId>,
+
+    /// Whether systems were removed since the schedule was last successfully built
+    needs_rebuild: bool,
+
+    /// The execution times of the systems that ran at least once
+    timings: HashMap<SystemId, SystemTimings>,
 }
 
 impl SystemManager {
     /// Creates a new [`SystemManager`] without any systems
     pub(crate) fn new() -> Self {
         Self {
-            pending_systems: None,
+            pending_systems: Vec::new(),
             current_manifest: SystemManifest::empty(),
             by_phase: Vec::new(),
+            paused: HashSet::new(),
+            needs_rebuild: false,
+            timings: HashMap::new(),
         }
     }
 
-    /// Adds the systems in `manifest` to `self` and updates the schedule
-    pub(crate) fn queue_system(&mut self, manifest: SystemManifest) {
-        // TODO: Check if the systems are valid? Are they always valid?
-        match self.pending_systems.as_mut() {
-            Some(pending) => {
-                pending.merge(manifest);
+    /// Applies the given update to the system with the given ID. Removals take effect once the
+    /// schedule is updated with [`Self::update_schedule`]
+    pub(crate) fn update_system(&mut self, id: SystemId, update: SystemUpdate) {
+        match update {
+            SystemUpdate::Remove => self.remove_system(id),
+            SystemUpdate::Pause => {
+                log::debug!("Pausing system {}", id.id());
+                self.paused.insert(id);
             }
-            None => {
-                self.pending_systems = Some(manifest);
+            SystemUpdate::Resume => {
+                log::debug!("Resuming system {}", id.id());
+                self.paused.remove(&id);
             }
         }
     }
 
-    /// Updates the schedule if any systems were queued with [`Self::queue_system`]
-    pub(crate) fn update_schedule(&mut self) {
-        let Some(pending) = self.pending_systems.take() else {
-            return;
-        };
+    fn remove_system(&mut self, id: SystemId) {
+        let mut removed = false;
+
+        for manifest in self
+            .pending_systems
+            .iter_mut()
+            .map(|(manifest, _)| manifest)
+            .chain(core::iter::once(&mut self.current_manifest))
+        {
+            for removed_system in manifest.systems.extract_if(.., |sys| sys.system_id == id) {
+                log::debug!("Removing system {}", removed_system.name);
+                removed = true;
+            }
+
+            // Dependencies only order systems, so dependents of the removed system can still run
+            for system in &mut manifest.systems {
+                system.dependencies.retain(|dep| *dep != id);
+            }
+        }
 
-        if pending.systems.is_empty() {
+        if !removed {
+            log::warn!("Cannot remove system {} because it does not exist", id.id());
             return;
         }
 
+        self.paused.remove(&id);
+        self.timings.remove(&id);
+        self.needs_rebuild = true;
+    }
+
+    /// Queues the systems in `manifest` for insertion into the schedule with [`Self::update_schedule`].
+    /// The result is reported through `insertion`
+    pub(crate) fn queue_system(&mut self, manifest: SystemManifest, insertion: SystemInsertion) {
+        self.pending_systems.push((manifest, insertion));
+    }
+
+    /// Updates the schedule if any systems were queued with [`Self::queue_system`], or removed
+    /// with [`Self::update_system`]. Returns whether the schedule changed.
+    ///
+    /// Each queued manifest is checked on its own, so a manifest that can not be scheduled is
+    /// rejected without affecting the others
+    pub(crate) fn update_schedule(&mut self) -> bool {
+        if self.pending_systems.is_empty() && !self.needs_rebuild {
+            return false;
+        }
+
         profiling::function_scope!();
 
-        log::info!(
-            "Updating system schedule and inserting {} new systems",
-            pending.systems.len()
-        );
+        let mut new_manifest = self.current_manifest.clone();
+        let mut changed = self.needs_rebuild;
 
-        let mut new_manifest = core::mem::take(&mut self.current_manifest);
+        for (manifest, insertion) in core::mem::take(&mut self.pending_systems) {
+            if manifest.systems.is_empty() {
+                insertion.finish(Ok(()));
+                continue;
+            }
 
-        new_manifest.merge(pending);
+            let num_systems = manifest.systems.len();
 
-        self.build_schedule(new_manifest);
-    }
+            let mut candidate = new_manifest.clone();
+            candidate.merge(manifest);
 
-    /// Dumps the schedule of the system manager to a string
-    pub(crate) fn dump(&self) -> String {
-        let mut s = String::new();
+            match candidate.validate() {
+                Ok(()) => {
+                    log::info!("Inserting {num_systems} new systems into the system schedule");
 
-        for (phase, sets) in &self.by_phase {
-            s = format!("{s}Phase: {phase}\n");
+                    new_manifest = candidate;
+                    changed = true;
 
-            for (stage, set) in sets.iter().enumerate() {
-                let set_fmt = set.system_names.join(", ");
+                    insertion.finish(Ok(()));
+                }
+                Err(e) => {
+                    log::error!(
+                        "Rejecting {num_systems} new systems, because they can not be scheduled: {e}"
+                    );
 
-                s = format!("{s}\tStage {stage}: {set_fmt}\n");
+                    insertion.finish(Err(e));
+                }
             }
         }
 
-        s
-    }
+        if !changed {
+            return false;
+        }
 
-    /// Runs all systems for a given phase, using the provided world
-    pub(crate) fn run_systems_for_phase(&self, phase: Phase, world: &crate::world::World) {
-        profiling::function_scope!(phase.str());
+        if let Err(e) = self.build_schedule(new_manifest) {
+            log::error!(
+                "Failed to update system schedule, keeping the previous schedule and retrying next phase: {e}"
+            );
+            return false;
+        }
 
-        assert_main_thread!();
+        self.needs_rebuild = false;
 
-        log::trace!("Running systems for phase {phase}");
+        true
+    }
 
-        let Some(sets) = self.find_sets_for_phase(phase) else {
-            return;
-        };
+    /// Returns a structured view of the current schedule
+    pub(crate) fn schedule_info(&self) -> ScheduleInfo {
+        ScheduleInfo {
+            phases: self
+                .by_phase
+                .iter()
+                .map(|(phase, scheduled)| PhaseInfo {
+                    phase: *phase,
+                    stages: scheduled
+                        .sets
+                        .iter()
+                        .map(|set| StageInfo {
+                            systems: set.infos.clone(),
+                        })
+                        .collect(),
+                })
+                .collect(),
+        }
+    }
 
-        for set in sets {
-            set.systems.par_iter().for_each(|sys| {
-                sys(world);
-            });
+    /// Returns the execution statistics of all scheduled systems. If `count_entities` is set, the entities
+    /// matching their queries in `world` are counted too. Must not be called while systems are running
+    pub(crate) fn stats(&self, world: &World, count_entities: bool) -> ScheduleStats {
+        ScheduleStats {
+            phases: self
+                .by_phase
+                .iter()
+                .map(|(phase, scheduled)| PhaseStats {
+                    phase: *phase,
+                    systems: scheduled
+                        .sets
+                        .iter()
+                        .flat_map(|set| {
+                            set.infos
+                                .iter()
+                                .zip(&set.entity_counts)
+                                .map(|(info, entity_count)| {
+                                    (info, entity_count, set.exclusive_system.is_some())
+                                })
+                        })
+                        .map(|(info, entity_count, exclusive)| {
+                            let timings = self.timings.get(&info.id);
+
+                            SystemStats {
+                                id: info.id,
+                                name: info.name,
+                                invocations: timings.map_or(0, SystemTimings::invocations),
+                                average: timings.map(SystemTimings::average).unwrap_or_default(),
+                                max: timings.map(SystemTimings::max).unwrap_or_default(),
+                                exclusive,
+                                matching_entities: entity_count
+                                    .as_ref()
+                                    .filter(|_| count_entities)
+                                    .map(|count| count(world)),
+                            }
+                        })
+                        .collect(),
+                })
+                .collect(),
         }
     }
 
-    fn find_sets_for_phase(&self, phase: Phase) -> Option<&[SystemSet]> {
-        for (set_phase, set) in &self.by_phase {
-            if *set_phase == phase {
-                return Some(set.as_slice());
-            }
+    /// Returns whether the system at index `i` in `set` should run now
+    fn should_run(&self, set: &SystemSet, i: usize, world: &World) -> bool {
+        if self.paused.contains(&set.infos[i].id) {
+            return false;
         }
 
-        None
+        set.run_conditions[i]
+            .as_ref()
+            .is_none_or(|condition| condition.evaluate(world))
+    }
+
+    /// Returns the scheduled custom phases that run right before (`after == false`) or right after
+    /// (`after == true`) the builtin phase `anchor`, in order
+    pub(crate) fn custom_phases(&self, anchor: Phase, after: bool) -> Vec<Phase> {
+        let mut phases: Vec<_> = self
+            .by_phase
+            .iter()
+            .map(|(phase, _)| *phase)
+            .filter(|phase| phase.is_custom_at(anchor, after))
+            .collect();
+
+        phases.sort_unstable();
+        phases
     }
+
+    /// Returns the scheduled systems of `phase`, if it has any
+    fn find_phase(&self, phase: Phase) -> Option<&ScheduledPhase> {
+        self.by_phase
+            .iter()
+            .find(|(scheduled_phase, _)| *scheduled_phase == phase)
+            .map(|(_, scheduled)| scheduled)
+    }
+
+    /// Returns the stages of `phase`, if it has any systems
+    #[cfg(test)]
+    fn find_sets_for_phase(&self, phase: Phase) -> Option<&[SystemSet]> {
+        self.find_phase(phase)
+            .map(|scheduled| scheduled.sets.as_slice())
+    }
+}
+
+/// The systems of a single phase
+#[derive(Debug)]
+pub(crate) struct ScheduledPhase {
+    /// The systems, divided in stages of systems that could run in parallel
+    sets: Vec<SystemSet>,
+
+    /// The order in which the systems must run
+    graph: SystemGraph,
 }
 
 #[derive(derive_more::Debug)]
 struct SystemSet {
-    system_names: Vec<&'static str>,
-    system_ids: Vec<SystemId>,
-    shared_borrows: HashSet<TypeId>,
-    exclusive_borrows: HashSet<TypeId>,
+    infos: Vec<SystemInfo>,
+    run_conditions: Vec<Option<RunCondition>>,
+
+    /// Counts the entities matching the query of each system. [`None`] for systems without a query
+    #[debug(skip)]
+    entity_counts: Vec<Option<Arc<EntityCountFn>>>,
 
     #[debug("{} systems", systems.len())]
     systems: Vec<Arc<GenericSystem>>,
+
+    /// If set, this set contains only this single system, which has exclusive world access
+    #[debug(skip)]
+    exclusive_system: Option<Arc<ExclusiveSystem>>,
 }
 
 impl SystemSet {
     fn new() -> Self {
         Self {
-            system_names: Vec::new(),
-            system_ids: Vec::new(),
-            shared_borrows: HashSet::new(),
-            exclusive_borrows: HashSet::new(),
+            infos: Vec::new(),
+            run_conditions: Vec::new(),
+            entity_counts: Vec::new(),
             systems: Vec::new(),
+            exclusive_system: None,
         }
     }
+
+    /// Returns the names of the systems in this set
+    #[cfg(test)]
+    fn system_names(&self) -> Vec<&'static str> {
+        self.infos.iter().map(|info| info.name).collect()
+    }
 }
 
-/// Where, in the process of running a single tick, the system is called
-#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
-pub enum Phase {
-    /// Called once each fixed update. Depends on the configured fixed update time.
-    /// Might be any number (or zero) times per frame
-    FixedUpdate,
+/// The result of inserting systems with [`insert_systems`]
+#[derive(Debug, Clone, Default)]
+pub struct SystemInsertion(Arc<Mutex<Option<Result<(), ScheduleErr>>>>);
+
+impl SystemInsertion {
+    /// Returns whether the systems were inserted, or [`None`] if the schedule was not updated yet.
+    /// If the systems could not be scheduled, none of them were inserted
+    pub fn result(&self) -> Option<Result<(), ScheduleErr>> {
+        self.0.lock().unwrap().clone()
+    }
+
+    /// Reports the result of the insertion
+    fn finish(&self, result: Result<(), ScheduleErr>) {
+        *self.0.lock().unwrap() = Some(result);
+    }
+}
 
-    /// Called once each tick
-    Update,
+/// Adds the systems in `manifest` to the main schedule. Returns a handle to check whether they
+/// were inserted, which fails if they can not be scheduled together with the existing systems.
+///
+/// Note that the systems are not inserted immediately, but rather before the next frame phase
+#[inline]
+pub fn insert_systems(manifest: SystemManifest) -> SystemInsertion {
+    let insertion = SystemInsertion::default();
 
-    /// Called once each tick, after the main [`Self::Update`]
-    LateUpdate,
+    crate::runtime::send_to_main_thread(crate::runtime::MainThreadEvent::AddSystem(
+        manifest,
+        insertion.clone(),
+    ));
 
-    /// Called after all standard frame logic, right before rendering takes place
-    PreRender,
+    insertion
 }
 
-impl Phase {
-    /// Returns the phase name as a static [`str`]
-    pub(crate) const fn str(self) -> &'static str {
-        match self {
-            Self::FixedUpdate => "Fixed Update",
-            Self::Update => "Update",
-            Self::LateUpdate => "Late Update",
-            Self::PreRender => "Pre-render",
-        }
-    }
+/// Removes the system with the given ID from the main schedule. Systems that depend on it keep running.
+///
+/// Like insertion, the system is not removed immediately, but rather before the next frame phase
+#[inline]
+pub fn remove_system(id: SystemId) {
+    update_system(id, SystemUpdate::Remove);
 }
 
-impl Display for Phase {
-    #[inline]
-    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
-        self.str().fmt(f)
-    }
+/// Pauses the system with the given ID, until it is resumed with [`resume_system`]
+#[inline]
+pub fn pause_system(id: SystemId) {
+    update_system(id, SystemUpdate::Pause);
+}
+
+/// Resumes the system with the given ID, after it was paused with [`pause_system`]
+#[inline]
+pub fn resume_system(id: SystemId) {
+    update_system(id, SystemUpdate::Resume);
 }
 
-/// Adds the systems in `manifest` to the main schedule.
-///
-/// Note that the systems are not inserted immediately, but rather before the next frame phase
 #[inline]
-pub fn insert_systems(manifest: SystemManifest) {
-    crate::runtime::send_to_main_thread(crate::runtime::MainThreadEvent::AddSystem(manifest));
+fn update_system(id: SystemId, update: SystemUpdate) {
+    crate::runtime::send_to_main_thread(crate::runtime::MainThreadEvent::UpdateSystem(id, update));
 }
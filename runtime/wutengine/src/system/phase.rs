@@ -0,0 +1,189 @@
+//! The phases of a frame, in which systems run
+
+use core::fmt::Display;
+use core::sync::atomic::AtomicU16;
+use core::sync::atomic::Ordering;
+
+/// Where, in the process of running a single tick, the system is called
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Phase {
+    /// Called once each fixed update. Depends on the configured fixed update time.
+    /// Might be any number (or zero) times per frame
+    FixedUpdate,
+
+    /// Called once each tick
+    Update,
+
+    /// Called once each tick, after the main [`Self::Update`]
+    LateUpdate,
+
+    /// Called after all standard frame logic, right before rendering takes place
+    PreRender,
+
+    /// A user-defined phase, registered with [`register_phase`]
+    Custom(CustomPhase),
+}
+
+/// A user-defined phase, positioned relative to one of the builtin phases
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct CustomPhase {
+    name: &'static str,
+    anchor: BuiltinPhase,
+    after: bool,
+
+    /// Registration order, used to order custom phases with the same position
+    index: u16,
+}
+
+/// Where a custom phase runs, relative to a builtin phase. See [`register_phase`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PhasePosition {
+    /// Runs right before the given builtin phase
+    Before(Phase),
+
+    /// Runs right after the given builtin phase. Custom phases after [`Phase::FixedUpdate`] run
+    /// once per fixed update, after the physics step
+    After(Phase),
+}
+
+/// The builtin phases, in order of execution
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+enum BuiltinPhase {
+    FixedUpdate,
+    Update,
+    LateUpdate,
+    PreRender,
+}
+
+/// Registers a new custom phase with the given name and position. Custom phases with the same
+/// position run in order of registration.
+///
+/// Systems are added to custom phases just like the builtin phases, using the returned [`Phase`].
+///
+/// # Panics
+///
+/// Panics if `position` is relative to another custom phase
+pub fn register_phase(name: &'static str, position: PhasePosition) -> Phase {
+    static NEXT_INDEX: AtomicU16 = AtomicU16::new(0);
+
+    let (anchor, after) = match position {
+        PhasePosition::Before(anchor) => (anchor, false),
+        PhasePosition::After(anchor) => (anchor, true),
+    };
+
+    let Some(anchor) = anchor.builtin() else {
+        panic!("Custom phase {name} must be positioned relative to a builtin phase, not {anchor}");
+    };
+
+    log::debug!("Registering custom phase {name}");
+
+    Phase::Custom(CustomPhase {
+        name,
+        anchor,
+        after,
+        index: NEXT_INDEX.fetch_add(1, Ordering::Relaxed),
+    })
+}
+
+impl Phase {
+    /// Returns the phase name as a static [`str`]
+    pub(crate) const fn str(self) -> &'static str {
+        match self {
+            Self::FixedUpdate => "Fixed Update",
+            Self::Update => "Update",
+            Self::LateUpdate => "Late Update",
+            Self::PreRender => "Pre-render",
+            Self::Custom(custom) => custom.name,
+        }
+    }
+
+    /// Returns whether this phase is a custom phase that runs right before (`after == false`) or
+    /// right after (`after == true`) the builtin phase `anchor`
+    pub(crate) fn is_custom_at(self, anchor: Phase, after: bool) -> bool {
+        matches!(self, Self::Custom(custom) if Some(custom.anchor) == anchor.builtin() && custom.after == after)
+    }
+
+    const fn builtin(self) -> Option<BuiltinPhase> {
+        match self {
+            Self::FixedUpdate => Some(BuiltinPhase::FixedUpdate),
+            Self::Update => Some(BuiltinPhase::Update),
+            Self::LateUpdate => Some(BuiltinPhase::LateUpdate),
+            Self::PreRender => Some(BuiltinPhase::PreRender),
+            Self::Custom(_) => None,
+        }
+    }
+
+    /// Returns the key by which phases are ordered within a frame
+    fn order_key(self) -> (BuiltinPhase, u8, u16) {
+        match self {
+            Self::Custom(custom) => (
+                custom.anchor,
+                if custom.after { 2 } else { 0 },
+                custom.index,
+            ),
+            builtin => (builtin.builtin().expect("Not a custom phase"), 1, 0),
+        }
+    }
+}
+
+/// Phases are ordered by when they run within a frame
+impl PartialOrd for Phase {
+    #[inline]
+    fn partial_cmp(&self, other: &Self) -> Option<core::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Phase {
+    #[inline]
+    fn cmp(&self, other: &Self) -> core::cmp::Ordering {
+        self.order_key().cmp(&other.order_key())
+    }
+}
+
+impl Display for Phase {
+    #[inline]
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        self.str().fmt(f)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::Phase;
+    use super::PhasePosition;
+    use super::register_phase;
+
+    #[test]
+    fn test_custom_phase_order() {
+        let post_physics = register_phase("Post physics", PhasePosition::After(Phase::FixedUpdate));
+        let pre_update = register_phase("Pre update", PhasePosition::Before(Phase::Update));
+        let pre_update_2 = register_phase("Pre update 2", PhasePosition::Before(Phase::Update));
+
+        let mut phases = vec![
+            Phase::PreRender,
+            pre_update_2,
+            Phase::Update,
+            pre_update,
+            post_physics,
+            Phase::FixedUpdate,
+        ];
+        phases.sort();
+
+        assert_eq!(
+            vec![
+                Phase::FixedUpdate,
+                post_physics,
+                pre_update,
+                pre_update_2,
+                Phase::Update,
+                Phase::PreRender
+            ],
+            phases
+        );
+
+        assert!(pre_update.is_custom_at(Phase::Update, false));
+        assert!(!pre_update.is_custom_at(Phase::Update, true));
+        assert_eq!("Post physics", post_physics.to_string());
+    }
+}
@@ -1,5 +1,64 @@
 use core::any::TypeId;
-use std::collections::HashSet;
+use std::collections::HashMap;
+
+use crate::system::ComponentTicks;
+
+/// The set of component or resource types borrowed by a system, together with their type names
+#[derive(Debug, Clone, Default)]
+pub struct BorrowSet(HashMap<TypeId, &'static str>);
+
+impl BorrowSet {
+    /// Creates an empty set with room for at least `capacity` borrows
+    #[inline]
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self(HashMap::with_capacity(capacity))
+    }
+
+    /// Adds a borrow of `T`
+    #[inline]
+    pub fn insert<T: ?Sized + 'static>(&mut self) {
+        self.insert_named(TypeId::of::<T>(), core::any::type_name::<T>());
+    }
+
+    /// Adds a borrow with the given key and display name
+    #[inline]
+    pub(crate) fn insert_named(&mut self, type_id: TypeId, name: &'static str) {
+        self.0.insert(type_id, name);
+    }
+
+    /// Returns whether the type with the given [`TypeId`] is borrowed
+    #[inline]
+    pub fn contains(&self, type_id: &TypeId) -> bool {
+        self.0.contains_key(type_id)
+    }
+
+    /// Returns the name of the first borrow in this set that is also in `other`, if any
+    pub(crate) fn find_shared(&self, other: &Self) -> Option<&'static str> {
+        self.0
+            .iter()
+            .find(|(id, _)| other.contains(id))
+            .map(|(_, name)| *name)
+    }
+
+    /// Returns the amount of borrowed types
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    /// Returns whether no types are borrowed
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Returns the type names of all borrows, sorted alphabetically
+    pub fn names(&self) -> Vec<&'static str> {
+        let mut names: Vec<_> = self.0.values().copied().collect();
+        names.sort_unstable();
+        names
+    }
+}
 
 /// Helper trait that allows for better runtime scheduling of ECS systems
 /// Should not be implemented by hand. Is automatically implemented for all valid types.
@@ -11,7 +70,36 @@ pub trait Queryable {
     const NUM_EXCLUSIVE_BORROWS: usize;
 
     /// Adds the borrows of this query to their corresponding maps
-    fn register_borrows(shared: &mut HashSet<TypeId>, exclusive: &mut HashSet<TypeId>);
+    fn register_borrows(shared: &mut BorrowSet, exclusive: &mut BorrowSet);
+
+    /// The change ticks this query needs for [`Self::passes_change_filters`] and [`Self::mark_changed`].
+    /// Queried together with the query itself
+    type ChangeTicks: for<'a> hecs::Query<Item<'a>: Send>;
+
+    /// Returns whether an entity passes the [`crate::system::Added`] and [`crate::system::Changed`]
+    /// filters of this query, given the tick the system last ran at. Filters nested in [`Option`] or
+    /// [`hecs::Or`] are ignored
+    #[inline]
+    fn passes_change_filters(
+        _ticks: &<Self::ChangeTicks as hecs::Query>::Item<'_>,
+        _last_run: u64,
+    ) -> bool {
+        true
+    }
+
+    /// Marks the components this query borrows as `&mut T` as changed at `tick`
+    #[inline]
+    fn mark_changed(_ticks: &<Self::ChangeTicks as hecs::Query>::Item<'_>, _tick: u64) {}
+}
+
+impl Queryable for () {
+    const NUM_SHARED_BORROWS: usize = 0;
+    const NUM_EXCLUSIVE_BORROWS: usize = 0;
+
+    type ChangeTicks = ();
+
+    #[inline]
+    fn register_borrows(_shared: &mut BorrowSet, _exclusive: &mut BorrowSet) {}
 }
 
 impl<T> Queryable for &T
@@ -21,9 +109,11 @@ where
     const NUM_SHARED_BORROWS: usize = 1;
     const NUM_EXCLUSIVE_BORROWS: usize = 0;
 
+    type ChangeTicks = ();
+
     #[inline]
-    fn register_borrows(shared: &mut HashSet<TypeId>, _exclusive: &mut HashSet<TypeId>) {
-        shared.insert(TypeId::of::<T>());
+    fn register_borrows(shared: &mut BorrowSet, _exclusive: &mut BorrowSet) {
+        shared.insert::<T>();
     }
 }
 
@@ -34,9 +124,19 @@ where
     const NUM_SHARED_BORROWS: usize = 0;
     const NUM_EXCLUSIVE_BORROWS: usize = 1;
 
+    type ChangeTicks = Option<&'static ComponentTicks<T>>;
+
     #[inline]
-    fn register_borrows(_shared: &mut HashSet<TypeId>, exclusive: &mut HashSet<TypeId>) {
-        exclusive.insert(TypeId::of::<T>());
+    fn register_borrows(_shared: &mut BorrowSet, exclusive: &mut BorrowSet) {
+        exclusive.insert::<T>();
+    }
+
+    /// Every component handed out as `&mut T` counts as changed, whether it is written to or not
+    #[inline]
+    fn mark_changed(ticks: &<Self::ChangeTicks as hecs::Query>::Item<'_>, tick: u64) {
+        if let Some(ticks) = ticks {
+            ticks.mark_changed(tick);
+        }
     }
 }
 
@@ -47,10 +147,19 @@ where
     const NUM_SHARED_BORROWS: usize = T::NUM_SHARED_BORROWS;
     const NUM_EXCLUSIVE_BORROWS: usize = T::NUM_EXCLUSIVE_BORROWS;
 
+    type ChangeTicks = Option<T::ChangeTicks>;
+
     #[inline]
-    fn register_borrows(shared: &mut HashSet<TypeId>, exclusive: &mut HashSet<TypeId>) {
+    fn register_borrows(shared: &mut BorrowSet, exclusive: &mut BorrowSet) {
         T::register_borrows(shared, exclusive);
     }
+
+    #[inline]
+    fn mark_changed(ticks: &Option<<T::ChangeTicks as hecs::Query>::Item<'_>>, tick: u64) {
+        if let Some(ticks) = ticks {
+            T::mark_changed(ticks, tick);
+        }
+    }
 }
 
 impl<L, R> Queryable for hecs::Or<L, R>
@@ -74,11 +183,30 @@ where
         }
     };
 
+    type ChangeTicks = (Option<L::ChangeTicks>, Option<R::ChangeTicks>);
+
     #[inline]
-    fn register_borrows(shared: &mut HashSet<TypeId>, exclusive: &mut HashSet<TypeId>) {
+    fn register_borrows(shared: &mut BorrowSet, exclusive: &mut BorrowSet) {
         L::register_borrows(shared, exclusive);
         R::register_borrows(shared, exclusive);
     }
+
+    #[inline]
+    fn mark_changed(
+        ticks: &(
+            Option<<L::ChangeTicks as hecs::Query>::Item<'_>>,
+            Option<<R::ChangeTicks as hecs::Query>::Item<'_>>,
+        ),
+        tick: u64,
+    ) {
+        if let Some(left) = &ticks.0 {
+            L::mark_changed(left, tick);
+        }
+
+        if let Some(right) = &ticks.1 {
+            R::mark_changed(right, tick);
+        }
+    }
 }
 
 impl<T> Queryable for hecs::Satisfies<T>
@@ -88,8 +216,10 @@ where
     const NUM_SHARED_BORROWS: usize = 0;
     const NUM_EXCLUSIVE_BORROWS: usize = 0;
 
+    type ChangeTicks = ();
+
     #[inline]
-    fn register_borrows(_shared: &mut HashSet<TypeId>, _exclusive: &mut HashSet<TypeId>) {}
+    fn register_borrows(_shared: &mut BorrowSet, _exclusive: &mut BorrowSet) {}
 }
 
 impl<Q, R> Queryable for hecs::With<Q, R>
@@ -100,10 +230,25 @@ where
     const NUM_SHARED_BORROWS: usize = Q::NUM_SHARED_BORROWS;
     const NUM_EXCLUSIVE_BORROWS: usize = Q::NUM_EXCLUSIVE_BORROWS;
 
+    type ChangeTicks = Q::ChangeTicks;
+
     #[inline]
-    fn register_borrows(shared: &mut HashSet<TypeId>, exclusive: &mut HashSet<TypeId>) {
+    fn register_borrows(shared: &mut BorrowSet, exclusive: &mut BorrowSet) {
         Q::register_borrows(shared, exclusive);
     }
+
+    #[inline]
+    fn passes_change_filters(
+        ticks: &<Q::ChangeTicks as hecs::Query>::Item<'_>,
+        last_run: u64,
+    ) -> bool {
+        Q::passes_change_filters(ticks, last_run)
+    }
+
+    #[inline]
+    fn mark_changed(ticks: &<Q::ChangeTicks as hecs::Query>::Item<'_>, tick: u64) {
+        Q::mark_changed(ticks, tick);
+    }
 }
 
 impl<Q, R> Queryable for hecs::Without<Q, R>
@@ -114,10 +259,25 @@ where
     const NUM_SHARED_BORROWS: usize = Q::NUM_SHARED_BORROWS;
     const NUM_EXCLUSIVE_BORROWS: usize = Q::NUM_EXCLUSIVE_BORROWS;
 
+    type ChangeTicks = Q::ChangeTicks;
+
     #[inline]
-    fn register_borrows(shared: &mut HashSet<TypeId>, exclusive: &mut HashSet<TypeId>) {
+    fn register_borrows(shared: &mut BorrowSet, exclusive: &mut BorrowSet) {
         Q::register_borrows(shared, exclusive);
     }
+
+    #[inline]
+    fn passes_change_filters(
+        ticks: &<Q::ChangeTicks as hecs::Query>::Item<'_>,
+        last_run: u64,
+    ) -> bool {
+        Q::passes_change_filters(ticks, last_run)
+    }
+
+    #[inline]
+    fn mark_changed(ticks: &<Q::ChangeTicks as hecs::Query>::Item<'_>, tick: u64) {
+        Q::mark_changed(ticks, tick);
+    }
 }
 
 /// Generates tuple implementations for [`Queryable`]
@@ -130,10 +290,25 @@ macro_rules! queryable_tuples {
             const NUM_SHARED_BORROWS: usize = $t::NUM_SHARED_BORROWS;
             const NUM_EXCLUSIVE_BORROWS: usize = $t::NUM_EXCLUSIVE_BORROWS;
 
+            type ChangeTicks = ($t::ChangeTicks,);
+
             #[inline]
-            fn register_borrows(shared: &mut HashSet<TypeId>, exclusive: &mut HashSet<TypeId>) {
+            fn register_borrows(shared: &mut BorrowSet, exclusive: &mut BorrowSet) {
                 $t::register_borrows(shared, exclusive);
             }
+
+            #[inline]
+            fn passes_change_filters(
+                ticks: &(<$t::ChangeTicks as hecs::Query>::Item<'_>,),
+                last_run: u64,
+            ) -> bool {
+                $t::passes_change_filters(&ticks.0, last_run)
+            }
+
+            #[inline]
+            fn mark_changed(ticks: &(<$t::ChangeTicks as hecs::Query>::Item<'_>,), tick: u64) {
+                $t::mark_changed(&ticks.0, tick);
+            }
         }
     };
 
@@ -146,26 +321,58 @@ macro_rules! queryable_tuples {
             const NUM_SHARED_BORROWS: usize = $t::NUM_SHARED_BORROWS  $(+ $others::NUM_SHARED_BORROWS)*;
             const NUM_EXCLUSIVE_BORROWS: usize = $t::NUM_EXCLUSIVE_BORROWS  $(+ $others::NUM_EXCLUSIVE_BORROWS)*;
 
+            type ChangeTicks = ($t::ChangeTicks, $($others::ChangeTicks),*);
+
             #[inline]
-            fn register_borrows(shared: &mut HashSet<TypeId>, exclusive: &mut HashSet<TypeId>) {
+            fn register_borrows(shared: &mut BorrowSet, exclusive: &mut BorrowSet) {
                 $t::register_borrows(shared, exclusive);
                 $($others::register_borrows(shared, exclusive));*;
             }
+
+            #[inline]
+            #[allow(non_snake_case, reason = "The type parameters double as the names of the tuple fields")]
+            fn passes_change_filters(
+                ticks: &(
+                    <$t::ChangeTicks as hecs::Query>::Item<'_>,
+                    $(<$others::ChangeTicks as hecs::Query>::Item<'_>),*
+                ),
+                last_run: u64,
+            ) -> bool {
+                let ($t, $($others),*) = ticks;
+
+                $t::passes_change_filters($t, last_run)
+                    $(&& $others::passes_change_filters($others, last_run))*
+            }
+
+            #[inline]
+            #[allow(non_snake_case, reason = "The type parameters double as the names of the tuple fields")]
+            fn mark_changed(
+                ticks: &(
+                    <$t::ChangeTicks as hecs::Query>::Item<'_>,
+                    $(<$others::ChangeTicks as hecs::Query>::Item<'_>),*
+                ),
+                tick: u64,
+            ) {
+                let ($t, $($others),*) = ticks;
+
+                $t::mark_changed($t, tick);
+                $($others::mark_changed($others, tick));*;
+            }
         }
 
         queryable_tuples!($($others),*);
     };
 }
 
-queryable_tuples!(A, B, C, D, E, F, G, H, I, J, K, L, M, N, O, P);
+queryable_tuples!(A, B, C, D, E, F, G, H, I, J, K, L, M, N, O);
 
 #[cfg(test)]
 mod test {
     use core::any::TypeId;
-    use std::collections::HashSet;
 
     use hecs::{Or, Satisfies, With, Without};
 
+    use super::BorrowSet;
     use super::Queryable;
 
     struct CompA;
@@ -190,8 +397,8 @@ mod test {
 
     #[test]
     fn test_readonly() {
-        let mut shared = HashSet::new();
-        let mut exclusive = HashSet::new();
+        let mut shared = BorrowSet::default();
+        let mut exclusive = BorrowSet::default();
 
         <(&CompA, &CompB, &CompC, &CompD, &CompE, &CompF) as Queryable>::register_borrows(
             &mut shared,
@@ -213,8 +420,8 @@ mod test {
 
     #[test]
     fn test_writeonly() {
-        let mut shared = HashSet::new();
-        let mut exclusive = HashSet::new();
+        let mut shared = BorrowSet::default();
+        let mut exclusive = BorrowSet::default();
 
         <(
             &mut CompA,
@@ -239,8 +446,8 @@ mod test {
 
     #[test]
     fn test_mixed() {
-        let mut shared = HashSet::new();
-        let mut exclusive = HashSet::new();
+        let mut shared = BorrowSet::default();
+        let mut exclusive = BorrowSet::default();
 
         <(
             &mut CompA,
@@ -264,8 +471,8 @@ mod test {
 
     #[test]
     fn test_nested() {
-        let mut shared = HashSet::new();
-        let mut exclusive = HashSet::new();
+        let mut shared = BorrowSet::default();
+        let mut exclusive = BorrowSet::default();
 
         <(
             Option<&mut CompA>,
@@ -314,8 +521,8 @@ mod test {
 
     #[test]
     fn test_deep_nested() {
-        let mut shared = HashSet::new();
-        let mut exclusive = HashSet::new();
+        let mut shared = BorrowSet::default();
+        let mut exclusive = BorrowSet::default();
 
         <(
             With<Option<Or<(&CompA, &mut CompB), Option<(&CompC, &mut CompD)>>>, &Dummy>,
@@ -0,0 +1,184 @@
+//! Resource parameters for systems
+
+use core::any::TypeId;
+use core::marker::PhantomData;
+use std::sync::RwLockReadGuard;
+use std::sync::RwLockWriteGuard;
+
+use crate::system::BorrowSet;
+use crate::system::Queryable;
+use crate::world::World;
+
+/// The key used to register resource borrows with [`Queryable::register_borrows`], so that
+/// they never coincide with component borrows
+pub(super) struct ResourceBorrow<T>(PhantomData<fn() -> T>);
+
+/// System parameter for shared access to the resource of type `T`. See [`World::insert_resource`]
+#[derive(Debug)]
+pub struct Res<T>(PhantomData<fn() -> T>);
+
+/// System parameter for exclusive access to the resource of type `T`. See [`World::insert_resource`]
+#[derive(Debug)]
+pub struct ResMut<T>(PhantomData<fn() -> T>);
+
+/// A set of resources borrowed by a system. Implemented for [`Res`], [`ResMut`], [`crate::system::EventReader`],
+/// [`crate::system::EventWriter`] and tuples of those. Should not be implemented by hand
+pub trait Resources: Queryable {
+    /// The borrowed resources
+    type Item<'w>;
+
+    /// The state kept by each system between its runs, such as the cursors of event readers
+    type State: Default + Send + Sync + 'static;
+
+    /// Borrows the resources from the world. Returns the type name of the first missing resource
+    /// if not all of them exist
+    fn fetch<'w>(world: &'w World, state: &'w Self::State) -> Result<Self::Item<'w>, &'static str>;
+
+    /// Called once at the end of every run of the system
+    #[inline]
+    fn finish_run(_world: &World, _state: &Self::State) {}
+}
+
+/// Returns the name of a resource that `R` borrows more than once, if any. Borrowing a resource
+/// twice in a single system takes two locks on it on the same thread, which deadlocks
+pub(crate) fn find_duplicate_borrow<R: Resources>() -> Option<&'static str> {
+    let mut shared = BorrowSet::default();
+    let mut exclusive = BorrowSet::default();
+
+    R::register_borrows(&mut shared, &mut exclusive);
+
+    if let Some(duplicate) = shared.find_shared(&exclusive) {
+        return Some(duplicate);
+    }
+
+    // Each resource registers a single borrow, so a missing one was a duplicate
+    (shared.len() + exclusive.len() < R::NUM_SHARED_BORROWS + R::NUM_EXCLUSIVE_BORROWS)
+        .then(core::any::type_name::<R>)
+}
+
+/// Returns the name of a resource that `R` borrows exclusively, if any
+pub(crate) fn find_exclusive_borrow<R: Resources>() -> Option<&'static str> {
+    let mut shared = BorrowSet::default();
+    let mut exclusive = BorrowSet::default();
+
+    R::register_borrows(&mut shared, &mut exclusive);
+
+    exclusive.names().first().copied()
+}
+
+impl<T: Send + Sync + 'static> Queryable for Res<T> {
+    const NUM_SHARED_BORROWS: usize = 1;
+    const NUM_EXCLUSIVE_BORROWS: usize = 0;
+
+    type ChangeTicks = ();
+
+    #[inline]
+    fn register_borrows(shared: &mut BorrowSet, _exclusive: &mut BorrowSet) {
+        shared.insert_named(
+            TypeId::of::<ResourceBorrow<T>>(),
+            core::any::type_name::<Self>(),
+        );
+    }
+}
+
+impl<T: Send + Sync + 'static> Resources for Res<T> {
+    type Item<'w> = RwLockReadGuard<'w, T>;
+    type State = ();
+
+    #[inline]
+    fn fetch<'w>(
+        world: &'w World,
+        _state: &'w Self::State,
+    ) -> Result<Self::Item<'w>, &'static str> {
+        world.resource::<T>().ok_or(core::any::type_name::<T>())
+    }
+}
+
+impl<T: Send + Sync + 'static> Queryable for ResMut<T> {
+    const NUM_SHARED_BORROWS: usize = 0;
+    const NUM_EXCLUSIVE_BORROWS: usize = 1;
+
+    type ChangeTicks = ();
+
+    #[inline]
+    fn register_borrows(_shared: &mut BorrowSet, exclusive: &mut BorrowSet) {
+        exclusive.insert_named(
+            TypeId::of::<ResourceBorrow<T>>(),
+            core::any::type_name::<Self>(),
+        );
+    }
+}
+
+impl<T: Send + Sync + 'static> Resources for ResMut<T> {
+    type Item<'w> = RwLockWriteGuard<'w, T>;
+    type State = ();
+
+    #[inline]
+    fn fetch<'w>(
+        world: &'w World,
+        _state: &'w Self::State,
+    ) -> Result<Self::Item<'w>, &'static str> {
+        world.resource_mut::<T>().ok_or(core::any::type_name::<T>())
+    }
+}
+
+impl Resources for () {
+    type Item<'w> = ();
+    type State = ();
+
+    #[inline]
+    fn fetch<'w>(
+        _world: &'w World,
+        _state: &'w Self::State,
+    ) -> Result<Self::Item<'w>, &'static str> {
+        Ok(())
+    }
+}
+
+/// Generates tuple implementations for [`Resources`]
+macro_rules! resources_tuples {
+    ($t:ident) => {
+        impl<$t: Resources> Resources for ($t,) {
+            type Item<'w> = ($t::Item<'w>,);
+            type State = ($t::State,);
+
+            #[inline]
+            fn fetch<'w>(world: &'w World, state: &'w Self::State) -> Result<Self::Item<'w>, &'static str> {
+                Ok(($t::fetch(world, &state.0)?,))
+            }
+
+            #[inline]
+            fn finish_run(world: &World, state: &Self::State) {
+                $t::finish_run(world, &state.0);
+            }
+        }
+    };
+
+    ($t:ident, $($others:ident),*) => {
+        impl<$t: Resources, $($others: Resources),*> Resources for ($t, $($others),*) {
+            type Item<'w> = ($t::Item<'w>, $($others::Item<'w>),*);
+            type State = ($t::State, $($others::State),*);
+
+            #[inline]
+            #[expect(non_snake_case, reason = "The states are named after their resources")]
+            fn fetch<'w>(world: &'w World, state: &'w Self::State) -> Result<Self::Item<'w>, &'static str> {
+                let ($t, $($others),*) = state;
+
+                Ok(($t::fetch(world, $t)?, $($others::fetch(world, $others)?),*))
+            }
+
+            #[inline]
+            #[expect(non_snake_case, reason = "The states are named after their resources")]
+            fn finish_run(world: &World, state: &Self::State) {
+                let ($t, $($others),*) = state;
+
+                $t::finish_run(world, $t);
+                $($others::finish_run(world, $others);)*
+            }
+        }
+
+        resources_tuples!($($others),*);
+    };
+}
+
+resources_tuples!(A, B, C, D, E, F, G, H);
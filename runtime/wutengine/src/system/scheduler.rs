@@ -1,125 +1,843 @@
+use std::collections::BTreeSet;
+use std::collections::HashMap;
 use std::collections::HashSet;
 
 use crate::runtime::{PendingSystem, SystemManifest};
 
-use super::{Phase, SystemManager, SystemSet};
+use super::{
+    Phase, ScheduledPhase, StageClash, SystemCallback, SystemGraph, SystemId, SystemInfo,
+    SystemLabel, SystemManager, SystemSet,
+};
+
+/// An error while building a system schedule
+#[derive(Debug, Clone, derive_more::Error, derive_more::Display)]
+pub enum ScheduleErr {
+    /// A system depends on a system that is not in the schedule
+    #[display("System `{system}` depends on system {dependency}, which does not exist")]
+    DependencyNotFound {
+        /// The name of the depending system
+        system: &'static str,
+
+        /// The missing dependency
+        #[error(not(source))]
+        dependency: SystemId,
+    },
+
+    /// A system depends on a system in a phase that runs after its own phase
+    #[display(
+        "System `{system}` in phase {phase} depends on system `{dependency}` in later phase {dependency_phase}"
+    )]
+    LaterPhaseDependency {
+        /// The name of the depending system
+        system: &'static str,
+
+        /// The phase of the depending system
+        #[error(not(source))]
+        phase: Phase,
+
+        /// The name of the dependency
+        dependency: &'static str,
+
+        /// The phase of the dependency
+        #[error(not(source))]
+        dependency_phase: Phase,
+    },
+
+    /// A system borrows the same resource more than once, which would deadlock
+    #[display("System `{system}` borrows resource {resource} more than once")]
+    DuplicateResourceBorrow {
+        /// The name of the system
+        system: &'static str,
+
+        /// The name of the resource borrowed more than once
+        resource: &'static str,
+    },
+
+    /// A system with a parallel batch size borrows a resource exclusively, so its batches would
+    /// run one after another
+    #[display(
+        "System `{system}` has a parallel batch size, but borrows resource {resource} exclusively"
+    )]
+    BatchedExclusiveResourceBorrow {
+        /// The name of the system
+        system: &'static str,
+
+        /// The name of the resource borrowed exclusively
+        resource: &'static str,
+    },
+
+    /// The ordering constraints of the systems form a cycle
+    #[display("Systems form an ordering cycle: {}", _0.join(" -> "))]
+    Cycle(#[error(not(source))] Vec<&'static str>),
+}
+
+/// The scheduled systems of each phase
+pub(crate) type PhaseSchedule = Vec<(Phase, ScheduledPhase)>;
 
 /// Schedule building
 impl SystemManager {
-    /// Adds the systems in the provided manifest to the schedule of the system manager
-    pub(crate) fn build_schedule(&mut self, mut manifest: SystemManifest) {
+    /// Replaces the schedule of the system manager with a schedule built from the provided manifest.
+    ///
+    /// If the schedule can not be built, the current schedule is kept
+    pub(crate) fn build_schedule(&mut self, manifest: SystemManifest) -> Result<(), ScheduleErr> {
         log::trace!(
             "Building system schedule from manifest with {} systems",
             manifest.systems.len()
         );
 
-        self.current_manifest = manifest.clone();
-        self.by_phase.clear();
+        self.by_phase = build_phases(manifest.clone())?;
+        self.current_manifest = manifest;
 
-        while !manifest.systems.is_empty() {
-            let phase = manifest.systems[0].phase;
+        log::trace!("Done building schedule");
 
-            self.build_schedule_for_phase(&mut manifest, phase);
-        }
+        Ok(())
+    }
+}
+
+impl SystemManifest {
+    /// Checks whether a valid schedule can be built from the systems in this manifest.
+    ///
+    /// Note that the runtime schedules these systems together with all systems inserted before,
+    /// so a manifest that depends on systems in another manifest will fail this check
+    pub fn validate(&self) -> Result<(), ScheduleErr> {
+        build_phases(self.clone()).map(|_| ())
+    }
+}
 
-        assert!(manifest.systems.is_empty(), "Manifest not empty");
+/// Builds the schedule for all phases in the manifest
+fn build_phases(mut manifest: SystemManifest) -> Result<PhaseSchedule, ScheduleErr> {
+    if let Some(system) = manifest
+        .systems
+        .iter()
+        .find(|sys| sys.duplicate_resource.is_some())
+    {
+        return Err(ScheduleErr::DuplicateResourceBorrow {
+            system: system.name,
+            resource: system.duplicate_resource.expect("Checked above"),
+        });
+    }
 
-        log::trace!("Done building schedule");
+    if let Some(system) = manifest
+        .systems
+        .iter()
+        .find(|sys| sys.batched_exclusive_resource.is_some())
+    {
+        return Err(ScheduleErr::BatchedExclusiveResourceBorrow {
+            system: system.name,
+            resource: system.batched_exclusive_resource.expect("Checked above"),
+        });
     }
 
-    fn build_schedule_for_phase(&mut self, manifest: &mut SystemManifest, phase: Phase) {
-        let mut systems_in_phase = manifest
+    let all_systems: HashMap<SystemId, (&'static str, Phase)> = manifest
+        .systems
+        .iter()
+        .map(|sys| (sys.system_id, (sys.name, sys.phase)))
+        .collect();
+
+    let mut by_phase = PhaseSchedule::new();
+
+    while let Some(first) = manifest.systems.first() {
+        let phase = first.phase;
+
+        let systems_in_phase = manifest
             .systems
             .extract_if(.., |sys| sys.phase == phase)
             .collect::<Vec<_>>();
 
-        // Systems can only depend on earlier systems, so if we sort from low to high ID we know that all dependencies
-        // of the system have already been scheduled
-        systems_in_phase.sort_by_key(|pending_system| pending_system.system_id);
+        let sets = build_phase(systems_in_phase, &all_systems)?;
+        let graph = SystemGraph::build(&sets);
 
-        let system_sets =
-            if let Some(existing_sets) = self.by_phase.iter_mut().find(|(p, _)| *p == phase) {
-                &mut existing_sets.1
-            } else {
-                let new_set = Vec::new();
-                self.by_phase.push((phase, new_set));
+        by_phase.push((phase, ScheduledPhase { sets, graph }));
+    }
 
-                let new_set = self.by_phase.last_mut().unwrap();
+    by_phase.sort_unstable_by_key(|(phase, _)| *phase);
 
-                &mut new_set.1
-            };
+    Ok(by_phase)
+}
+
+/// Builds the sets for the systems of a single phase
+fn build_phase(
+    systems: Vec<PendingSystem>,
+    all_systems: &HashMap<SystemId, (&'static str, Phase)>,
+) -> Result<Vec<SystemSet>, ScheduleErr> {
+    let predecessors = find_predecessors(&systems, all_systems)?;
+    let order = sort_topologically(&systems, &predecessors)?;
+
+    let ids: Vec<SystemId> = systems.iter().map(|sys| sys.system_id).collect();
+    let mut systems: Vec<Option<PendingSystem>> = systems.into_iter().map(Some).collect();
+    let mut sets = Vec::new();
+
+    for i in order {
+        let system = systems[i].take().expect("Each system is sorted once");
+        let mut predecessors: Vec<SystemId> = predecessors[i].iter().map(|&p| ids[p]).collect();
+        predecessors.sort_unstable();
+
+        insert_system(&mut sets, system, predecessors);
+    }
+
+    Ok(sets)
+}
+
+/// Returns, for each system in `systems`, the indices of the systems that must run before it
+fn find_predecessors(
+    systems: &[PendingSystem],
+    all_systems: &HashMap<SystemId, (&'static str, Phase)>,
+) -> Result<Vec<BTreeSet<usize>>, ScheduleErr> {
+    let index_by_id: HashMap<SystemId, usize> = systems
+        .iter()
+        .enumerate()
+        .map(|(i, sys)| (sys.system_id, i))
+        .collect();
 
-        for system in systems_in_phase {
-            Self::insert_system(system_sets, system);
+    let mut by_label: HashMap<SystemLabel, Vec<usize>> = HashMap::new();
+
+    for (i, system) in systems.iter().enumerate() {
+        for label in &system.labels {
+            by_label.entry(*label).or_default().push(i);
         }
     }
 
-    fn insert_system(sets: &mut Vec<SystemSet>, system: PendingSystem) {
-        let mut unsatisfied_dependencies: HashSet<_> =
-            HashSet::from_iter(system.dependencies.as_slice());
+    let mut predecessors = vec![BTreeSet::new(); systems.len()];
 
-        let mut set_offset = 0;
+    for (i, system) in systems.iter().enumerate() {
+        for dependency in &system.dependencies {
+            if let Some(&dependency_index) = index_by_id.get(dependency) {
+                predecessors[i].insert(dependency_index);
+                continue;
+            }
 
-        // First we skip forward through the sets until all our dependencies have been satisfied
-        while !unsatisfied_dependencies.is_empty() {
-            assert!(
-                set_offset < sets.len(),
-                "Failed to build system schedule because a dependency could not be found"
-            );
+            let Some(&(dependency_name, dependency_phase)) = all_systems.get(dependency) else {
+                return Err(ScheduleErr::DependencyNotFound {
+                    system: system.name,
+                    dependency: *dependency,
+                });
+            };
 
-            for system_id in &sets[set_offset].system_ids {
-                unsatisfied_dependencies.remove(system_id);
+            // Dependencies in earlier phases are always satisfied
+            if dependency_phase > system.phase {
+                return Err(ScheduleErr::LaterPhaseDependency {
+                    system: system.name,
+                    phase: system.phase,
+                    dependency: dependency_name,
+                    dependency_phase,
+                });
             }
+        }
 
-            set_offset += 1;
+        for label in &system.after {
+            for &other in labelled(&by_label, *label, system.name) {
+                if other != i {
+                    predecessors[i].insert(other);
+                }
+            }
         }
 
-        // Now that we've satisfied our dependencies, we check for clashes in the borrows.
-        // If we find a clash, we skip to the next set, inserting a new layer if needed
-        loop {
-            if set_offset >= sets.len() {
-                sets.push(SystemSet::new());
+        for label in &system.before {
+            for &other in labelled(&by_label, *label, system.name) {
+                if other != i {
+                    predecessors[other].insert(i);
+                }
             }
+        }
+    }
 
-            let set = &mut sets[set_offset];
+    Ok(predecessors)
+}
 
-            if !has_clashing_borrows(set, &system) {
-                // If we don't have any clashing borrows, we can add the system here.
-                // Otherwise we must skip to the next set and try again
-                insert_system_into_set(set, system);
-                break;
-            }
+/// Returns the indices of all systems with the given label
+fn labelled<'a>(
+    by_label: &'a HashMap<SystemLabel, Vec<usize>>,
+    label: SystemLabel,
+    system: &'static str,
+) -> &'a [usize] {
+    let Some(labelled) = by_label.get(&label) else {
+        log::warn!(
+            "System `{system}` is ordered relative to label `{label}`, but no system in its phase has that label"
+        );
+        return &[];
+    };
+
+    labelled
+}
+
+/// Sorts the systems so that every system comes after its predecessors. Systems that are
+/// not ordered relative to each other are sorted by their ID, so insertion order is kept where possible
+fn sort_topologically(
+    systems: &[PendingSystem],
+    predecessors: &[BTreeSet<usize>],
+) -> Result<Vec<usize>, ScheduleErr> {
+    let mut successors = vec![Vec::new(); systems.len()];
+    let mut num_unsorted_predecessors = vec![0_usize; systems.len()];
+
+    for (i, preds) in predecessors.iter().enumerate() {
+        num_unsorted_predecessors[i] = preds.len();
+
+        for &pred in preds {
+            successors[pred].push(i);
+        }
+    }
 
-            set_offset += 1;
+    // Ordered by system ID, so the result is deterministic
+    let mut ready: BTreeSet<(SystemId, usize)> = (0..systems.len())
+        .filter(|&i| num_unsorted_predecessors[i] == 0)
+        .map(|i| (systems[i].system_id, i))
+        .collect();
+
+    let mut order = Vec::with_capacity(systems.len());
+
+    while let Some((_, i)) = ready.pop_first() {
+        order.push(i);
+
+        for &successor in &successors[i] {
+            num_unsorted_predecessors[successor] -= 1;
+
+            if num_unsorted_predecessors[successor] == 0 {
+                ready.insert((systems[successor].system_id, successor));
+            }
         }
     }
+
+    if order.len() < systems.len() {
+        return Err(ScheduleErr::Cycle(find_cycle(
+            systems,
+            predecessors,
+            &num_unsorted_predecessors,
+        )));
+    }
+
+    Ok(order)
 }
 
-fn insert_system_into_set(set: &mut SystemSet, new_system: PendingSystem) {
-    set.system_ids.push(new_system.system_id);
-    set.system_names.push(new_system.name);
-    set.systems.push(new_system.callback);
-    set.shared_borrows.extend(new_system.shared_borrows);
-    set.exclusive_borrows.extend(new_system.exclusive_borrows);
+/// Returns the names of the systems in a cycle, given the predecessor counts left over after a failed
+/// topological sort. Every system with remaining predecessors is in, or after, a cycle
+fn find_cycle(
+    systems: &[PendingSystem],
+    predecessors: &[BTreeSet<usize>],
+    num_unsorted_predecessors: &[usize],
+) -> Vec<&'static str> {
+    let mut current = (0..systems.len())
+        .find(|&i| num_unsorted_predecessors[i] > 0)
+        .expect("A cycle exists");
+
+    let mut path = Vec::new();
+
+    // Walk backwards through unsorted predecessors until we visit a system twice
+    loop {
+        if let Some(cycle_start) = path.iter().position(|&p| p == current) {
+            let mut cycle: Vec<_> = path[cycle_start..]
+                .iter()
+                .rev()
+                .map(|&i: &usize| systems[i].name)
+                .collect();
+
+            cycle.push(cycle[0]);
+
+            return cycle;
+        }
+
+        path.push(current);
+
+        current = *predecessors[current]
+            .iter()
+            .find(|&&p| num_unsorted_predecessors[p] > 0)
+            .expect("Unsorted systems always have an unsorted predecessor");
+    }
 }
 
-fn has_clashing_borrows(set: &SystemSet, new_system: &PendingSystem) -> bool {
-    // Check if any of the new shared borrows clash with the mutable borrows of the existing set
-    for new_shared_borrow in &new_system.shared_borrows {
-        if set.exclusive_borrows.contains(new_shared_borrow) {
-            return true;
+/// Inserts the system in the first set after all its predecessors where its borrows do not clash
+fn insert_system(sets: &mut Vec<SystemSet>, system: PendingSystem, predecessors: Vec<SystemId>) {
+    let mut unsatisfied_dependencies: HashSet<SystemId> = predecessors.iter().copied().collect();
+
+    let mut set_offset = 0;
+
+    // First we skip forward through the sets until all our predecessors have been satisfied.
+    // Predecessors are always inserted first, so they are always found
+    while !unsatisfied_dependencies.is_empty() {
+        for info in &sets[set_offset].infos {
+            unsatisfied_dependencies.remove(&info.id);
         }
+
+        set_offset += 1;
+    }
+
+    let exclusive_system = match &system.callback {
+        SystemCallback::Parallel(_) => None,
+        SystemCallback::Exclusive(exclusive_system) => Some(exclusive_system.clone()),
+    };
+
+    let mut info = SystemInfo {
+        id: system.system_id,
+        name: system.name,
+        exclusive: exclusive_system.is_some(),
+        records_commands: system.records_commands,
+        shared_borrows: system.shared_borrows,
+        exclusive_borrows: system.exclusive_borrows,
+        dependencies: predecessors,
+        labels: system.labels,
+        earliest_stage: set_offset,
+        clashes: Vec::new(),
+    };
+
+    // Exclusive systems always get a new stage of their own, after all previously scheduled systems
+    if let Some(exclusive_system) = exclusive_system {
+        let mut set = SystemSet::new();
+
+        set.infos.push(info);
+        set.run_conditions.push(system.run_condition);
+        set.entity_counts.push(None);
+        set.exclusive_system = Some(exclusive_system);
+
+        sets.push(set);
+        return;
     }
 
-    // Check if any of the new exlusive borrows clash with the mutable or immutable borrows of the existing set
-    for new_exclusive_borrow in &new_system.exclusive_borrows {
-        if set.shared_borrows.contains(new_exclusive_borrow)
-            || set.exclusive_borrows.contains(new_exclusive_borrow)
-        {
-            return true;
+    let SystemCallback::Parallel(callback) = system.callback else {
+        unreachable!("Exclusive systems were handled above");
+    };
+
+    // Now that we've satisfied our dependencies, we check for clashes in the borrows.
+    // If we find a clash, we skip to the next set, inserting a new layer if needed
+    loop {
+        if set_offset >= sets.len() {
+            sets.push(SystemSet::new());
+        }
+
+        let set = &mut sets[set_offset];
+
+        // If we don't have any clashing borrows, we can add the system here.
+        // Otherwise we must skip to the next set and try again
+        let Some((clashing_system, borrow)) = find_clashing_borrow(&set.infos, &info) else {
+            set.infos.push(info);
+            set.run_conditions.push(system.run_condition);
+            set.entity_counts.push(system.entity_count);
+            set.systems.push(callback);
+            break;
+        };
+
+        info.clashes.push(StageClash {
+            stage: set_offset,
+            system: clashing_system.id,
+            system_name: clashing_system.name,
+            borrow,
+        });
+
+        set_offset += 1;
+    }
+}
+
+/// Returns the first of `existing` systems that the new system clashes with, and the name of the borrow
+/// that clashed. The borrow is [`None`] if either system is exclusive
+pub(super) fn find_clashing_borrow<'s>(
+    existing: impl IntoIterator<Item = &'s SystemInfo>,
+    new_system: &SystemInfo,
+) -> Option<(&'s SystemInfo, Option<&'static str>)> {
+    for existing in existing {
+        // Nothing can run alongside an exclusive system
+        if existing.exclusive || new_system.exclusive {
+            return Some((existing, None));
+        }
+
+        // New shared borrows clash with existing exclusive borrows, and new exclusive borrows clash with any existing borrow
+        let clash = new_system
+            .shared_borrows
+            .find_shared(&existing.exclusive_borrows)
+            .or_else(|| {
+                new_system
+                    .exclusive_borrows
+                    .find_shared(&existing.shared_borrows)
+            })
+            .or_else(|| {
+                new_system
+                    .exclusive_borrows
+                    .find_shared(&existing.exclusive_borrows)
+            });
+
+        if clash.is_some() {
+            return Some((existing, clash));
         }
     }
 
     // No clashes!
-    false
+    None
+}
+
+#[cfg(test)]
+mod test {
+    use core::num::NonZero;
+
+    use crate::runtime::SystemConfig;
+    use crate::runtime::SystemManifest;
+    use crate::system::EventWriter;
+    use crate::system::Phase;
+    use crate::system::Res;
+    use crate::system::ResMut;
+    use crate::system::ScheduleErr;
+    use crate::system::SystemId;
+    use crate::system::SystemInsertion;
+    use crate::system::SystemLabel;
+    use crate::system::SystemManager;
+    use crate::system::SystemUpdate;
+    use crate::world::Events;
+
+    struct CompA;
+    struct CompB;
+
+    #[test]
+    fn test_exclusive_system_own_stage() {
+        let mut manifest = SystemManifest::empty();
+
+        manifest.add_system::<&CompA>(Phase::Update, "A", |_, _| {});
+        manifest.add_exclusive_system(Phase::Update, "Exclusive", |_| {});
+        manifest.add_system::<&mut CompA>(Phase::Update, "A mut", |_, _| {});
+        manifest.add_system::<&CompB>(Phase::Update, "B", |_, _| {});
+
+        let mut manager = SystemManager::new();
+        manager.build_schedule(manifest).unwrap();
+
+        let sets = manager.find_sets_for_phase(Phase::Update).unwrap();
+
+        assert_eq!(3, sets.len());
+        assert_eq!(vec!["A", "B"], sets[0].system_names());
+        assert_eq!(vec!["Exclusive"], sets[1].system_names());
+        assert!(sets[1].exclusive_system.is_some());
+        assert_eq!(vec!["A mut"], sets[2].system_names());
+    }
+
+    #[test]
+    fn test_stats_count_entities_on_request() {
+        let mut manifest = SystemManifest::empty();
+
+        manifest.add_system::<&CompA>(Phase::Update, "A", |_, _| {});
+        manifest.add_exclusive_system(Phase::Update, "Exclusive", |_| {});
+
+        let mut manager = SystemManager::new();
+        manager.build_schedule(manifest).unwrap();
+
+        let mut world = crate::world::World::new();
+        world.ecs.spawn((CompA,));
+        world.ecs.spawn((CompA,));
+
+        let matching = |count_entities: bool| {
+            manager.stats(&world, count_entities).phases[0]
+                .systems
+                .iter()
+                .map(|system| (system.name, system.exclusive, system.matching_entities))
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            vec![("A", false, None), ("Exclusive", true, None)],
+            matching(false)
+        );
+        assert_eq!(
+            vec![("A", false, Some(2)), ("Exclusive", true, None)],
+            matching(true)
+        );
+    }
+
+    #[test]
+    fn test_resource_borrows_clash() {
+        let mut manifest = SystemManifest::empty();
+
+        manifest.add_system_with_resources::<Res<u32>, &CompA>(
+            Phase::Update,
+            "Read",
+            &SystemConfig::default(),
+            |_, _, _, _| {},
+        );
+        manifest.add_system_with_resources::<ResMut<u32>, &CompB>(
+            Phase::Update,
+            "Write",
+            &SystemConfig::default(),
+            |_, _, _, _| {},
+        );
+        manifest.add_system_with_resources::<Res<u64>, &CompB>(
+            Phase::Update,
+            "Other",
+            &SystemConfig::default(),
+            |_, _, _, _| {},
+        );
+
+        let mut manager = SystemManager::new();
+        manager.build_schedule(manifest).unwrap();
+
+        let sets = manager.find_sets_for_phase(Phase::Update).unwrap();
+
+        assert_eq!(2, sets.len());
+        assert_eq!(vec!["Read", "Other"], sets[0].system_names());
+        assert_eq!(vec!["Write"], sets[1].system_names());
+    }
+
+    #[test]
+    fn test_reject_batched_exclusive_resource_borrows() {
+        let batched = SystemConfig {
+            parallel_batch_size: NonZero::new(16),
+            ..Default::default()
+        };
+
+        let mut manifest = SystemManifest::empty();
+
+        manifest.add_system_with_resources::<(Res<u32>, ResMut<u64>), &CompA>(
+            Phase::Update,
+            "Batched write",
+            &batched,
+            |_, _, _, _| {},
+        );
+
+        assert!(matches!(
+            manifest.validate(),
+            Err(ScheduleErr::BatchedExclusiveResourceBorrow {
+                system: "Batched write",
+                resource,
+            }) if resource == core::any::type_name::<ResMut<u64>>()
+        ));
+
+        // Shared resource borrows can be batched, and exclusive ones without batching
+        let mut manifest = SystemManifest::empty();
+
+        manifest.add_system_with_resources::<Res<u32>, &CompA>(
+            Phase::Update,
+            "Batched read",
+            &batched,
+            |_, _, _, _| {},
+        );
+        manifest.add_system_with_resources::<ResMut<u64>, &CompA>(
+            Phase::Update,
+            "Write",
+            &SystemConfig::default(),
+            |_, _, _, _| {},
+        );
+
+        assert!(manifest.validate().is_ok());
+    }
+
+    #[test]
+    fn test_reject_duplicate_resource_borrows() {
+        let mut manifest = SystemManifest::empty();
+
+        manifest.add_system_with_resources::<(Res<u32>, ResMut<u32>), &CompA>(
+            Phase::Update,
+            "Read and write",
+            &SystemConfig::default(),
+            |_, _, _, _| {},
+        );
+
+        assert!(matches!(
+            manifest.validate(),
+            Err(ScheduleErr::DuplicateResourceBorrow {
+                system: "Read and write",
+                ..
+            })
+        ));
+
+        let mut manifest = SystemManifest::empty();
+
+        manifest.add_system_with_resources::<(EventWriter<u32>, ResMut<Events<u32>>), &CompA>(
+            Phase::Update,
+            "Write twice",
+            &SystemConfig::default(),
+            |_, _, _, _| {},
+        );
+
+        assert!(matches!(
+            manifest.validate(),
+            Err(ScheduleErr::DuplicateResourceBorrow { .. })
+        ));
+
+        let mut manifest = SystemManifest::empty();
+
+        manifest.add_system_with_resources::<(Res<u32>, Res<u64>), &CompA>(
+            Phase::Update,
+            "Read both",
+            &SystemConfig::default(),
+            |_, _, _, _| {},
+        );
+
+        assert!(manifest.validate().is_ok());
+    }
+
+    #[test]
+    fn test_remove_system_with_dependents() {
+        let mut manifest = SystemManifest::empty();
+
+        let a = manifest.add_system::<&CompA>(Phase::Update, "A", |_, _| {});
+        manifest.add_system_with_config::<&CompB>(
+            Phase::Update,
+            "B",
+            &SystemConfig {
+                dependencies: &[a],
+                ..Default::default()
+            },
+            |_, _| {},
+        );
+
+        let mut manager = SystemManager::new();
+        manager.build_schedule(manifest).unwrap();
+
+        manager.update_system(a, SystemUpdate::Remove);
+        manager.update_schedule();
+
+        let sets = manager.find_sets_for_phase(Phase::Update).unwrap();
+
+        assert_eq!(1, sets.len());
+        assert_eq!(vec!["B"], sets[0].system_names());
+    }
+
+    #[test]
+    fn test_reject_only_invalid_queued_manifest() {
+        let mut manifest = SystemManifest::empty();
+        let removed = manifest.add_system::<&CompA>(Phase::Update, "Removed", |_, _| {});
+
+        let mut manager = SystemManager::new();
+        manager.build_schedule(manifest).unwrap();
+
+        let mut invalid = SystemManifest::empty();
+        invalid.add_system_with_config::<&CompA>(
+            Phase::Update,
+            "Invalid",
+            &SystemConfig {
+                dependencies: &[SystemId::next(Phase::Update)],
+                ..Default::default()
+            },
+            |_, _| {},
+        );
+
+        let mut valid = SystemManifest::empty();
+        valid.add_system::<&CompB>(Phase::Update, "Valid", |_, _| {});
+
+        let invalid_insertion = SystemInsertion::default();
+        let valid_insertion = SystemInsertion::default();
+
+        manager.update_system(removed, SystemUpdate::Remove);
+        manager.queue_system(invalid, invalid_insertion.clone());
+        manager.queue_system(valid, valid_insertion.clone());
+
+        assert!(invalid_insertion.result().is_none());
+        assert!(manager.update_schedule());
+
+        assert!(matches!(
+            invalid_insertion.result(),
+            Some(Err(ScheduleErr::DependencyNotFound { .. }))
+        ));
+        assert!(matches!(valid_insertion.result(), Some(Ok(()))));
+
+        let sets = manager.find_sets_for_phase(Phase::Update).unwrap();
+
+        assert_eq!(1, sets.len());
+        assert_eq!(vec!["Valid"], sets[0].system_names());
+        assert!(!manager.update_schedule());
+    }
+
+    #[test]
+    fn test_label_ordering() {
+        const PHYSICS: SystemLabel = SystemLabel::new("physics");
+
+        let mut manifest = SystemManifest::empty();
+
+        manifest.add_system_with_config::<&CompA>(
+            Phase::Update,
+            "After physics",
+            &SystemConfig {
+                after: &[PHYSICS],
+                ..Default::default()
+            },
+            |_, _| {},
+        );
+        manifest.add_system_with_config::<&CompB>(
+            Phase::Update,
+            "Physics",
+            &SystemConfig {
+                labels: &[PHYSICS],
+                ..Default::default()
+            },
+            |_, _| {},
+        );
+        manifest.add_system_with_config::<&CompB>(
+            Phase::Update,
+            "Before physics",
+            &SystemConfig {
+                before: &[PHYSICS],
+                ..Default::default()
+            },
+            |_, _| {},
+        );
+
+        let mut manager = SystemManager::new();
+        manager.build_schedule(manifest).unwrap();
+
+        let sets = manager.find_sets_for_phase(Phase::Update).unwrap();
+
+        assert_eq!(3, sets.len());
+        assert_eq!(vec!["Before physics"], sets[0].system_names());
+        assert_eq!(vec!["Physics"], sets[1].system_names());
+        assert_eq!(vec!["After physics"], sets[2].system_names());
+    }
+
+    #[test]
+    fn test_cycle_detection() {
+        const FIRST: SystemLabel = SystemLabel::new("first");
+        const SECOND: SystemLabel = SystemLabel::new("second");
+
+        let mut manifest = SystemManifest::empty();
+
+        manifest.add_system_with_config::<&CompA>(
+            Phase::Update,
+            "First",
+            &SystemConfig {
+                labels: &[FIRST],
+                after: &[SECOND],
+                ..Default::default()
+            },
+            |_, _| {},
+        );
+        manifest.add_system_with_config::<&CompB>(
+            Phase::Update,
+            "Second",
+            &SystemConfig {
+                labels: &[SECOND],
+                after: &[FIRST],
+                ..Default::default()
+            },
+            |_, _| {},
+        );
+
+        let Err(ScheduleErr::Cycle(cycle)) = manifest.validate() else {
+            panic!("Expected cycle");
+        };
+
+        assert_eq!(3, cycle.len());
+        assert!(cycle.contains(&"First"));
+        assert!(cycle.contains(&"Second"));
+    }
+
+    #[test]
+    fn test_later_phase_dependency() {
+        let mut manifest = SystemManifest::empty();
+
+        let update = manifest.add_system::<&CompA>(Phase::Update, "Update", |_, _| {});
+        manifest.add_system_with_config::<&CompA>(
+            Phase::LateUpdate,
+            "Late update",
+            &SystemConfig {
+                dependencies: &[update],
+                ..Default::default()
+            },
+            |_, _| {},
+        );
+        manifest.add_system_with_config::<&CompA>(
+            Phase::FixedUpdate,
+            "Fixed update",
+            &SystemConfig {
+                dependencies: &[update],
+                ..Default::default()
+            },
+            |_, _| {},
+        );
+
+        let Err(ScheduleErr::LaterPhaseDependency { system, .. }) = manifest.validate() else {
+            panic!("Expected later phase dependency error");
+        };
+
+        assert_eq!("Fixed update", system);
+    }
 }
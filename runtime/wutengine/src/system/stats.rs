@@ -0,0 +1,169 @@
+//! Rolling execution statistics of the scheduled systems, for finding expensive systems without a profiler
+
+use alloc::sync::Arc;
+use core::sync::atomic::AtomicBool;
+use core::sync::atomic::Ordering;
+use core::time::Duration;
+use std::collections::VecDeque;
+use std::sync::LazyLock;
+use std::sync::RwLock;
+
+use super::Phase;
+use super::SystemId;
+
+/// The number of most recent invocations the averages and maxima are calculated over
+pub const STATS_WINDOW: usize = 120;
+
+/// The statistics that were last published by the runtime
+static CURRENT_STATS: LazyLock<RwLock<Arc<ScheduleStats>>> =
+    LazyLock::new(|| RwLock::new(Arc::new(ScheduleStats::default())));
+
+/// Whether the published statistics include the number of entities matching each system
+static COUNT_MATCHING_ENTITIES: AtomicBool = AtomicBool::new(false);
+
+/// The execution statistics of all scheduled systems, ordered by phase
+#[derive(Debug, Clone, Default)]
+pub struct ScheduleStats {
+    /// The phases with at least one system, in scheduling order
+    pub phases: Vec<PhaseStats>,
+}
+
+/// The execution statistics of the systems of a single phase
+#[derive(Debug, Clone)]
+pub struct PhaseStats {
+    /// The phase
+    pub phase: Phase,
+
+    /// The systems in the phase, in schedule order
+    pub systems: Vec<SystemStats>,
+}
+
+/// The execution statistics of a single system
+#[derive(Debug, Clone)]
+pub struct SystemStats {
+    /// The system ID
+    pub id: SystemId,
+
+    /// The system name
+    pub name: &'static str,
+
+    /// The number of times the system ran since it was added. Runs skipped by a run condition or
+    /// because the system was paused are not counted
+    pub invocations: u64,
+
+    /// The average execution time over the last [`STATS_WINDOW`] invocations
+    pub average: Duration,
+
+    /// The maximum execution time over the last [`STATS_WINDOW`] invocations
+    pub max: Duration,
+
+    /// Whether the system has exclusive world access
+    pub exclusive: bool,
+
+    /// The number of entities matching the query of the system when the statistics were published.
+    /// [`None`] for systems without a query, like exclusive systems, and if counting was not enabled with [`set_count_matching_entities`]
+    pub matching_entities: Option<usize>,
+}
+
+impl PhaseStats {
+    /// Returns the summed average execution time of all systems in the phase. Because systems run in
+    /// parallel, this is an upper bound of the time spent running the phase
+    pub fn total_average(&self) -> Duration {
+        self.systems.iter().map(|system| system.average).sum()
+    }
+}
+
+/// The execution times of a single system, as kept by the system manager
+#[derive(Debug, Default)]
+pub(crate) struct SystemTimings {
+    /// The execution times of the last [`STATS_WINDOW`] invocations, oldest first
+    recent: VecDeque<Duration>,
+
+    /// The total number of invocations
+    invocations: u64,
+}
+
+impl SystemTimings {
+    /// Records a single invocation that took `duration`
+    pub(crate) fn record(&mut self, duration: Duration) {
+        if self.recent.len() == STATS_WINDOW {
+            self.recent.pop_front();
+        }
+
+        self.recent.push_back(duration);
+        self.invocations += 1;
+    }
+
+    /// Returns the total number of invocations
+    #[inline]
+    pub(crate) const fn invocations(&self) -> u64 {
+        self.invocations
+    }
+
+    /// Returns the average execution time of the recent invocations
+    #[expect(
+        clippy::cast_possible_truncation,
+        reason = "The window is much smaller than u32::MAX"
+    )]
+    pub(crate) fn average(&self) -> Duration {
+        if self.recent.is_empty() {
+            return Duration::ZERO;
+        }
+
+        self.recent.iter().sum::<Duration>() / self.recent.len() as u32
+    }
+
+    /// Returns the maximum execution time of the recent invocations
+    pub(crate) fn max(&self) -> Duration {
+        self.recent.iter().copied().max().unwrap_or_default()
+    }
+}
+
+/// Returns the statistics that were last published by the runtime. They are updated once per frame
+pub fn current_system_stats() -> Arc<ScheduleStats> {
+    CURRENT_STATS.read().unwrap().clone()
+}
+
+/// Enables or disables counting the entities matching each system in the published statistics. Counting
+/// runs the query of every system once per frame, so it is disabled by default
+pub fn set_count_matching_entities(enabled: bool) {
+    COUNT_MATCHING_ENTITIES.store(enabled, Ordering::Relaxed);
+}
+
+/// Returns whether the entities matching each system should be counted, see [`set_count_matching_entities`]
+#[inline]
+pub(crate) fn count_matching_entities() -> bool {
+    COUNT_MATCHING_ENTITIES.load(Ordering::Relaxed)
+}
+
+/// Makes `stats` available through [`current_system_stats`]
+pub(crate) fn publish_stats(stats: ScheduleStats) {
+    *CURRENT_STATS.write().unwrap() = Arc::new(stats);
+}
+
+#[cfg(test)]
+mod test {
+    use core::time::Duration;
+
+    use super::STATS_WINDOW;
+    use super::SystemTimings;
+
+    #[test]
+    fn test_rolling_timings() {
+        let mut timings = SystemTimings::default();
+
+        assert_eq!(Duration::ZERO, timings.average());
+        assert_eq!(Duration::ZERO, timings.max());
+
+        timings.record(Duration::from_millis(10));
+
+        for _ in 0..STATS_WINDOW {
+            timings.record(Duration::from_millis(2));
+        }
+
+        // The slow invocation dropped out of the window, but is still counted
+        assert_eq!(STATS_WINDOW as u64 + 1, timings.invocations());
+        assert_eq!(Duration::from_millis(2), timings.average());
+        assert_eq!(Duration::from_millis(2), timings.max());
+    }
+}
@@ -0,0 +1,15 @@
+//! Helpers shared by the unit tests
+
+use std::sync::Once;
+
+/// Initializes the global managers used by the unit tests. They can only be initialized once,
+/// and from the main thread, so the first test calling this claims its thread as the main thread
+pub(crate) fn init_globals() {
+    static INIT: Once = Once::new();
+
+    INIT.call_once(|| {
+        wutengine_util::set_cur_thread_as_main_thread();
+        crate::time::init();
+        wutengine_physics::init();
+    });
+}
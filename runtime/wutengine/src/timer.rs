@@ -0,0 +1,278 @@
+//! Scheduled callbacks, driven by game time.
+//!
+//! For timers stored on entities, see [`crate::builtins::components::Timer`]
+
+use alloc::sync::Arc;
+use core::sync::atomic::AtomicU64;
+use core::sync::atomic::Ordering;
+use core::time::Duration;
+use std::collections::HashMap;
+use std::sync::LazyLock;
+use std::sync::Mutex;
+
+/// The next timer ID
+static NEXT_TIMER_ID: AtomicU64 = AtomicU64::new(0);
+
+/// All scheduled timers that have not finished or been cancelled, by their ID
+static TIMERS: LazyLock<Mutex<HashMap<u64, ScheduledTimer>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+/// A scheduled callback. The callback is called outside of the [`TIMERS`] lock, so
+/// that it can schedule and cancel timers itself
+type TimerCallback = Arc<Mutex<dyn FnMut() + Send + 'static>>;
+
+/// A timer scheduled with [`after`] or [`every`]
+struct ScheduledTimer {
+    /// Game time left until the callback is called, in nanoseconds
+    remaining: u64,
+
+    /// The repeat interval in nanoseconds, or [`None`] for one-shot timers
+    interval: Option<u64>,
+
+    /// Whether the timer is paused
+    paused: bool,
+
+    /// The callback to call when the timer finishes
+    callback: TimerCallback,
+}
+
+/// Handle to a scheduled timer. Handles are cheap to copy, and stay valid after the timer
+/// finished or was cancelled
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct TimerHandle(u64);
+
+impl TimerHandle {
+    /// Pauses the timer. A paused timer does not count down until resumed
+    pub fn pause(self) {
+        self.set_paused(true);
+    }
+
+    /// Resumes the timer if it was paused
+    pub fn resume(self) {
+        self.set_paused(false);
+    }
+
+    /// Cancels the timer. Its callback will not be called anymore
+    pub fn cancel(self) {
+        TIMERS.lock().unwrap().remove(&self.0);
+    }
+
+    /// Returns `true` if the timer has not finished and has not been cancelled.
+    /// Repeating timers stay active until cancelled
+    pub fn is_active(self) -> bool {
+        TIMERS.lock().unwrap().contains_key(&self.0)
+    }
+
+    /// Returns `true` if the timer is active and paused
+    pub fn is_paused(self) -> bool {
+        TIMERS
+            .lock()
+            .unwrap()
+            .get(&self.0)
+            .is_some_and(|timer| timer.paused)
+    }
+
+    /// Returns the game time left until the callback is next called, or [`None`] if the
+    /// timer is no longer active
+    pub fn remaining(self) -> Option<Duration> {
+        TIMERS
+            .lock()
+            .unwrap()
+            .get(&self.0)
+            .map(|timer| Duration::from_nanos(timer.remaining))
+    }
+
+    fn set_paused(self, paused: bool) {
+        if let Some(timer) = TIMERS.lock().unwrap().get_mut(&self.0) {
+            timer.paused = paused;
+        }
+    }
+}
+
+/// Calls `callback` once on the main thread, after `delay` of game time has passed.
+///
+/// Game time is affected by the time scale, see [`crate::time::set_time_scale`]
+pub fn after(delay: Duration, callback: impl FnOnce() + Send + 'static) -> TimerHandle {
+    let mut callback = Some(callback);
+
+    schedule(delay, None, move || {
+        if let Some(callback) = callback.take() {
+            callback();
+        }
+    })
+}
+
+/// Calls `callback` on the main thread every `interval` of game time, until cancelled. If
+/// multiple intervals pass in a single frame, the callback is called once for each interval.
+/// A zero interval calls the callback once every frame.
+///
+/// Game time is affected by the time scale, see [`crate::time::set_time_scale`]
+pub fn every(interval: Duration, callback: impl FnMut() + Send + 'static) -> TimerHandle {
+    schedule(interval, Some(to_nanos(interval)), callback)
+}
+
+fn schedule(
+    delay: Duration,
+    interval: Option<u64>,
+    callback: impl FnMut() + Send + 'static,
+) -> TimerHandle {
+    let id = NEXT_TIMER_ID.fetch_add(1, Ordering::Relaxed);
+
+    TIMERS.lock().unwrap().insert(
+        id,
+        ScheduledTimer {
+            remaining: to_nanos(delay),
+            interval,
+            paused: false,
+            callback: Arc::new(Mutex::new(callback)),
+        },
+    );
+
+    TimerHandle(id)
+}
+
+/// Counts all unpaused timers down by `delta_nanos` of game time, and calls the callbacks of the
+/// timers that finished, in the order they were scheduled. Called by the runtime once per frame,
+/// before [`crate::system::Phase::Update`]
+pub(crate) fn run_timers(delta_nanos: u64) {
+    profiling::function_scope!();
+
+    let mut to_call: Vec<(u64, u64, TimerCallback)> = Vec::new();
+
+    {
+        let mut timers = TIMERS.lock().unwrap();
+
+        timers.retain(|id, timer| {
+            if timer.paused {
+                return true;
+            }
+
+            if timer.remaining > delta_nanos {
+                timer.remaining -= delta_nanos;
+                return true;
+            }
+
+            let overshoot = delta_nanos - timer.remaining;
+
+            let Some(interval) = timer.interval else {
+                to_call.push((*id, 1, timer.callback.clone()));
+                return false;
+            };
+
+            let times = match interval {
+                0 => 1,
+                interval => 1 + overshoot / interval,
+            };
+
+            timer.remaining = interval - overshoot.checked_rem(interval).unwrap_or(0);
+            to_call.push((*id, times, timer.callback.clone()));
+
+            true
+        });
+    }
+
+    to_call.sort_unstable_by_key(|(id, _, _)| *id);
+
+    for (id, times, callback) in to_call {
+        let mut callback = callback.lock().unwrap();
+
+        for i in 0..times {
+            // The callback may have cancelled its own timer, so it is not called again for the remaining intervals
+            if i > 0 && !TIMERS.lock().unwrap().contains_key(&id) {
+                break;
+            }
+
+            callback();
+        }
+    }
+}
+
+#[expect(clippy::cast_possible_truncation, reason = "Centuries of nanoseconds")]
+fn to_nanos(duration: Duration) -> u64 {
+    duration.as_nanos() as u64
+}
+
+#[cfg(test)]
+mod test {
+    use alloc::sync::Arc;
+    use core::sync::atomic::AtomicU32;
+    use core::sync::atomic::Ordering;
+    use core::time::Duration;
+    use std::sync::Mutex;
+    use std::sync::OnceLock;
+
+    use super::TimerHandle;
+
+    /// The timers are global, so the tests run them one at a time
+    static SERIAL: Mutex<()> = Mutex::new(());
+
+    #[test]
+    fn test_scheduled_timers() {
+        let _serial = SERIAL.lock().unwrap();
+
+        let once = Arc::new(AtomicU32::new(0));
+        let repeating = Arc::new(AtomicU32::new(0));
+
+        let once_handle = {
+            let once = once.clone();
+            super::after(Duration::from_nanos(10), move || {
+                once.fetch_add(1, Ordering::Relaxed);
+            })
+        };
+
+        let repeating_handle = {
+            let repeating = repeating.clone();
+            super::every(Duration::from_nanos(4), move || {
+                repeating.fetch_add(1, Ordering::Relaxed);
+            })
+        };
+
+        super::run_timers(9);
+
+        assert_eq!(0, once.load(Ordering::Relaxed));
+        assert_eq!(2, repeating.load(Ordering::Relaxed));
+        assert_eq!(Some(Duration::from_nanos(1)), once_handle.remaining());
+        assert_eq!(Some(Duration::from_nanos(3)), repeating_handle.remaining());
+
+        repeating_handle.pause();
+        super::run_timers(5);
+
+        assert_eq!(1, once.load(Ordering::Relaxed));
+        assert!(!once_handle.is_active());
+        assert_eq!(2, repeating.load(Ordering::Relaxed));
+
+        repeating_handle.resume();
+        super::run_timers(3);
+        repeating_handle.cancel();
+        super::run_timers(100);
+
+        assert_eq!(3, repeating.load(Ordering::Relaxed));
+        assert_eq!(None, repeating_handle.remaining());
+    }
+
+    #[test]
+    fn test_cancel_from_callback() {
+        let _serial = SERIAL.lock().unwrap();
+
+        let calls = Arc::new(AtomicU32::new(0));
+        let own_handle = Arc::new(OnceLock::<TimerHandle>::new());
+
+        let handle = {
+            let calls = calls.clone();
+            let own_handle = own_handle.clone();
+
+            super::every(Duration::from_nanos(2), move || {
+                calls.fetch_add(1, Ordering::Relaxed);
+                own_handle.get().unwrap().cancel();
+            })
+        };
+
+        own_handle.set(handle).unwrap();
+
+        // Five intervals passed, but the timer is cancelled during the first call
+        super::run_timers(10);
+
+        assert_eq!(1, calls.load(Ordering::Relaxed));
+        assert!(!handle.is_active());
+    }
+}
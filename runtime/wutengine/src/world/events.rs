@@ -0,0 +1,166 @@
+//! Double-buffered typed event queues, read and written by systems through
+//! [`crate::system::EventReader`] and [`crate::system::EventWriter`]
+
+use core::any::TypeId;
+
+use super::World;
+
+/// Updates the event queue of a single event type at the start of each frame
+type EventUpdater = fn(&World);
+
+/// The typed event queues registered with [`World::add_event`]
+#[derive(Debug, Default)]
+pub(crate) struct EventRegistry {
+    /// The updaters of the registered event types
+    updaters: Vec<(TypeId, EventUpdater)>,
+}
+
+/// The queue of events of type `T`, stored as a resource in the [`World`].
+///
+/// Events live for two frames: the frame they were sent in, and the frame after. Every reader keeps
+/// its own cursor, so that each reader sees each event once, as long as it runs at least once every frame
+#[derive(Debug)]
+pub struct Events<T> {
+    /// The events sent during the previous frame, oldest first
+    previous: Vec<T>,
+
+    /// The events sent during the current frame, oldest first
+    current: Vec<T>,
+
+    /// The sequence number of the first event in [`Self::previous`]
+    previous_start: u64,
+}
+
+impl<T> Default for Events<T> {
+    fn default() -> Self {
+        Self {
+            previous: Vec::new(),
+            current: Vec::new(),
+            previous_start: 0,
+        }
+    }
+}
+
+impl<T> Events<T> {
+    /// Sends an event
+    #[inline]
+    pub fn send(&mut self, event: T) {
+        self.current.push(event);
+    }
+
+    /// Returns the number of events that are still alive
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.previous.len() + self.current.len()
+    }
+
+    /// Returns `true` if no events are alive
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.previous.is_empty() && self.current.is_empty()
+    }
+
+    /// Returns all alive events, oldest first
+    pub fn iter(&self) -> impl Iterator<Item = &T> {
+        self.previous.iter().chain(&self.current)
+    }
+
+    /// Returns the sequence number the next sent event will get
+    #[inline]
+    pub(crate) fn next_sequence(&self) -> u64 {
+        self.previous_start + self.len() as u64
+    }
+
+    /// Returns all alive events with a sequence number of at least `from`, oldest first
+    pub(crate) fn iter_from(&self, from: u64) -> impl Iterator<Item = &T> {
+        let skip = from.saturating_sub(self.previous_start);
+
+        self.iter()
+            .skip(usize::try_from(skip).unwrap_or(usize::MAX))
+    }
+
+    /// Drops the events of the previous frame, and starts a new frame
+    fn update(&mut self) {
+        self.previous_start += self.previous.len() as u64;
+        self.previous.clear();
+
+        core::mem::swap(&mut self.previous, &mut self.current);
+    }
+}
+
+impl World {
+    /// Registers the event type `T`, so that it can be sent and read by systems. Does nothing if
+    /// the event type was already registered
+    pub fn add_event<T: Send + Sync + 'static>(&mut self) {
+        let type_id = TypeId::of::<Events<T>>();
+
+        if self.events.updaters.iter().any(|(id, _)| *id == type_id) {
+            return;
+        }
+
+        log::debug!("Adding event {}", core::any::type_name::<T>());
+
+        self.insert_resource(Events::<T>::default());
+        self.events.updaters.push((type_id, update_events::<T>));
+    }
+
+    /// Sends an event of type `T`. Returns `false` if the event type was not registered with
+    /// [`Self::add_event`]
+    pub fn send_event<T: Send + Sync + 'static>(&self, event: T) -> bool {
+        let Some(mut events) = self.resource_mut::<Events<T>>() else {
+            log::error!(
+                "Cannot send event {} because it was not added to the world",
+                core::any::type_name::<T>()
+            );
+            return false;
+        };
+
+        events.send(event);
+
+        true
+    }
+
+    /// Drops all events sent before the previous frame. Called by the runtime at the start of each frame
+    pub(crate) fn update_events(&self) {
+        for (_, updater) in &self.events.updaters {
+            updater(self);
+        }
+    }
+}
+
+/// Starts a new frame for the events of type `T`
+fn update_events<T: Send + Sync + 'static>(world: &World) {
+    if let Some(mut events) = world.resource_mut::<Events<T>>() {
+        events.update();
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::Events;
+    use super::World;
+
+    #[test]
+    fn test_event_lifetime() {
+        let mut world = World::new();
+        world.add_event::<u32>();
+
+        assert!(world.send_event(1_u32));
+        assert!(!world.send_event(1_u64));
+
+        world.update_events();
+        world.send_event(2_u32);
+
+        let events = world.resource::<Events<u32>>().unwrap();
+        assert_eq!(vec![1, 2], events.iter().copied().collect::<Vec<_>>());
+        assert_eq!(vec![2], events.iter_from(1).copied().collect::<Vec<_>>());
+        drop(events);
+
+        // The first event lived for two frames
+        world.update_events();
+
+        let events = world.resource::<Events<u32>>().unwrap();
+        assert_eq!(vec![2], events.iter_from(0).copied().collect::<Vec<_>>());
+        assert_eq!(2, events.next_sequence());
+    }
+}
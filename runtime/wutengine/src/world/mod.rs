@@ -0,0 +1,289 @@
+//! World management for the WutEngine runtime
+
+use core::any::Any;
+use core::any::TypeId;
+use core::cell::Cell;
+use core::ops::{Deref, DerefMut};
+use std::sync::RwLock;
+use std::sync::RwLockWriteGuard;
+
+use wutengine_util::InitOnce;
+
+use crate::builtins::components::Name;
+use crate::builtins::components::Transform;
+use crate::component::Component;
+use crate::entity::Entity;
+
+mod events;
+mod names;
+mod resource;
+mod save;
+mod spawn;
+
+pub use events::Events;
+pub use save::*;
+pub use spawn::*;
+
+static WORLD: InitOnce<RwLock<World>> = InitOnce::new_checked();
+
+thread_local! {
+    /// Whether this thread holds the world mutably, like during exclusive systems and state
+    /// callbacks. Locking the world again on the same thread would deadlock
+    static HOLDS_WORLD_MUT: Cell<bool> = const { Cell::new(false) };
+}
+
+/// Panics if this thread holds the world mutably, instead of deadlocking on the world lock
+#[track_caller]
+fn assert_not_held_mut() {
+    assert!(
+        !HOLDS_WORLD_MUT.get(),
+        "The world is already borrowed mutably on this thread, like in an exclusive system or state callback. \
+        Use the `World` passed to it instead of the `Entity` APIs, for example `World::spawn` instead of `Entity::spawn`"
+    );
+}
+
+/// Initializes the global "world", which contains the ECS data
+pub(crate) fn init() {
+    log::trace!("Initializing world");
+
+    InitOnce::init(&WORLD, RwLock::new(World::new()));
+}
+
+/// Inserts a resource into the world. Like components, the resource is not inserted immediately,
+/// but on the main thread before the next frame
+pub fn insert_resource<T: Send + Sync + 'static>(resource: T) {
+    _ = crate::runtime::run_on_main_thread(move || {
+        get_world_mut().insert_resource(resource);
+    });
+}
+
+/// Registers the event type `T`, so that it can be sent and read by systems. Like resources, the
+/// event type is not registered immediately, but on the main thread before the next frame
+pub fn add_event<T: Send + Sync + 'static>() {
+    _ = crate::runtime::run_on_main_thread(|| {
+        get_world_mut().add_event::<T>();
+    });
+}
+
+/// Returns a type that dereferences to a [`World`].
+/// Might be a lock guard, so drop as soon as possible.
+///
+/// Panics if this thread already holds the world mutably
+#[inline]
+#[track_caller]
+pub(crate) fn get_world() -> impl Deref<Target = World> {
+    assert_not_held_mut();
+
+    WORLD.read().unwrap()
+}
+
+/// Returns a type that mutably dereferences to a [`World`].
+/// Might be a lock guard, so drop as soon as possible.
+///
+/// Panics if this thread already holds the world mutably
+#[inline]
+#[track_caller]
+pub(crate) fn get_world_mut() -> impl DerefMut<Target = World> {
+    assert_not_held_mut();
+
+    let guard = WORLD.write().unwrap();
+
+    HOLDS_WORLD_MUT.set(true);
+
+    WorldWriteGuard(guard)
+}
+
+/// Lock guard of the mutably borrowed [`World`], that keeps track of which thread holds it
+struct WorldWriteGuard(RwLockWriteGuard<'static, World>);
+
+impl Deref for WorldWriteGuard {
+    type Target = World;
+
+    #[inline]
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl DerefMut for WorldWriteGuard {
+    #[inline]
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+
+impl Drop for WorldWriteGuard {
+    #[inline]
+    fn drop(&mut self) {
+        HOLDS_WORLD_MUT.set(false);
+    }
+}
+
+/// Manager of all entities and components currently living in the game engine.
+///
+/// Only directly accessible from exclusive systems and state callbacks. Unlike the [`Entity`] APIs, all
+/// changes made through the world are applied immediately. The [`Entity`] APIs lock the world themselves,
+/// so they panic when used while a mutable world is held, like [`Entity::spawn`]. Use [`World::spawn`]
+/// and the other world methods there instead
+#[derive(derive_more::Debug)]
+pub struct World {
+    /// The raw [`hecs`] world
+    #[debug(skip)]
+    pub(crate) ecs: hecs::World,
+
+    /// The typed resources
+    resources: resource::ResourceStorage,
+
+    /// The entities by their name
+    names: names::NameIndex,
+
+    /// The registered event types
+    events: events::EventRegistry,
+}
+
+impl World {
+    /// Creates a new, empty, [`World`]
+    pub(crate) fn new() -> Self {
+        Self {
+            ecs: hecs::World::new(),
+            resources: resource::ResourceStorage::default(),
+            names: names::NameIndex::default(),
+            events: events::EventRegistry::default(),
+        }
+    }
+
+    /// Spawns a new entity with the given name and a default [`Transform`]
+    pub fn spawn(&mut self, name: impl Into<String>) -> Entity {
+        let entity = Entity(self.ecs.spawn(()));
+
+        self.add_component(entity, Name::new(name.into()));
+        self.add_component(entity, Transform::new());
+
+        entity
+    }
+
+    /// Adds a component to the given entity, replacing any existing component of the same type.
+    /// Returns `false` if the entity does not exist.
+    ///
+    /// Calls [`Component::on_remove`] on the replaced component, and [`Component::on_add`] on the new one.
+    /// A replaced [`Transform`] keeps its parent and children
+    pub fn add_component<C: Component>(&mut self, entity: Entity, component: C) -> bool {
+        crate::component::queue_default_component_systems::<C>();
+        crate::component::register_hooks::<C>();
+
+        if !self.ecs.contains(entity.0) {
+            log::error!(
+                "Failed to insert component on entity {entity} because it does not exist in the world"
+            );
+            return false;
+        }
+
+        let ticks = crate::system::ComponentTicks::<C>::new(crate::system::current_tick());
+
+        if let Ok(existing) = self.ecs.query_one_mut::<&mut C>(entity.0) {
+            // Replaced in place, so that a replaced transform keeps its parent and children
+            let mut replaced = core::mem::replace(existing, component);
+
+            if let (Some(replaced), Some(replacement)) = (
+                (&mut replaced as &mut dyn Any).downcast_mut::<Transform>(),
+                (existing as &mut dyn Any).downcast_mut::<Transform>(),
+            ) {
+                crate::builtins::components::transform::keep_hierarchy(replaced, replacement);
+            }
+
+            replaced.on_remove(entity);
+
+            self.ecs
+                .insert_one(entity.0, ticks)
+                .expect("Entity checked above");
+        } else {
+            self.ecs
+                .insert(entity.0, (component, ticks))
+                .expect("Entity checked above");
+        }
+
+        if TypeId::of::<C>() == TypeId::of::<Name>() {
+            self.index_name(entity);
+        }
+
+        if let Ok(added) = self.ecs.query_one_mut::<&mut C>(entity.0) {
+            added.on_add(entity);
+        }
+
+        true
+    }
+
+    /// Removes the component of type `C` from the given entity and returns it, if it had one.
+    /// Calls [`Component::on_remove`] on the removed component.
+    ///
+    /// Removing a [`Transform`] detaches the entity from its parent and children
+    pub fn remove_component<C: Component>(&mut self, entity: Entity) -> Option<C> {
+        let mut removed = self.take_component::<C>(entity)?;
+
+        removed.on_remove(entity);
+
+        Some(removed)
+    }
+
+    /// Removes the component of type `C` from the given entity without calling any hooks
+    fn take_component<C: Component>(&mut self, entity: Entity) -> Option<C> {
+        if !self.ecs.satisfies::<&C>(entity.0) {
+            return None;
+        }
+
+        if TypeId::of::<C>() == TypeId::of::<Name>() {
+            self.unindex_name(entity);
+        }
+
+        if TypeId::of::<C>() == TypeId::of::<Transform>() {
+            crate::builtins::components::transform::detach(self, entity);
+        }
+
+        _ = self
+            .ecs
+            .remove_one::<crate::system::ComponentTicks<C>>(entity.0);
+
+        self.ecs.remove_one::<C>(entity.0).ok()
+    }
+
+    /// Returns the component of type `C` of the given entity, if it has one
+    #[inline]
+    pub fn component<C: Component>(&self, entity: Entity) -> Option<hecs::Ref<'_, C>> {
+        self.ecs.get::<&C>(entity.0).ok()
+    }
+
+    /// Returns whether the given entity has a component of type `C`
+    #[inline]
+    pub fn has_component<C: Component>(&self, entity: Entity) -> bool {
+        self.ecs.satisfies::<&C>(entity.0)
+    }
+
+    /// Makes `parent` the parent of `child` in the transform hierarchy. See [`Entity::set_parent`]
+    pub fn set_parent(&mut self, child: Entity, parent: Option<Entity>) {
+        crate::builtins::components::transform::set_parent(self, child, parent);
+    }
+
+    /// Destroys an entity and all its children
+    pub fn destroy(&mut self, entity: Entity) {
+        crate::entity::destroy_entities(self, vec![entity]);
+    }
+
+    /// Returns whether the given entity exists in the world
+    #[inline]
+    pub fn contains(&self, entity: Entity) -> bool {
+        self.ecs.contains(entity.0)
+    }
+
+    /// Queries the world for all entities matching `Q`. Components are borrowed dynamically,
+    /// so multiple queries can be active at the same time as long as their borrows do not clash
+    #[inline]
+    pub fn query<Q: hecs::Query>(&self) -> hecs::QueryBorrow<'_, Q> {
+        self.ecs.query::<Q>()
+    }
+
+    /// Queries the world for all entities matching `Q`, without any dynamic borrow checking
+    #[inline]
+    pub fn query_mut<Q: hecs::Query>(&mut self) -> hecs::QueryMut<'_, Q> {
+        self.ecs.query_mut::<Q>()
+    }
+}
@@ -0,0 +1,163 @@
+//! Index of entities by their [`Name`] component
+
+use std::collections::HashMap;
+
+use super::World;
+use crate::builtins::components::Name;
+use crate::entity::Entity;
+use crate::system::ComponentTicks;
+
+/// Lookup table from entity names to the entities with that name, in order of naming
+#[derive(Debug, Default)]
+pub(crate) struct NameIndex {
+    by_name: HashMap<String, Vec<Entity>>,
+
+    /// The name each entity is indexed under, which can differ from its current [`Name`] if it was
+    /// changed in place since the last [`World::refresh_names`]
+    by_entity: HashMap<Entity, String>,
+
+    /// The change tick of the last [`World::refresh_names`]
+    last_refresh: u64,
+}
+
+impl World {
+    /// Returns the first entity that was given the name `name`, if any. A [`Name`] changed in place
+    /// is found under its new name after the next batch of entity changes is processed, while one
+    /// replaced with [`World::add_component`] is found right away
+    pub fn find_by_name(&self, name: &str) -> Option<Entity> {
+        self.names
+            .by_name
+            .get(name)
+            .and_then(|entities| entities.first().copied())
+    }
+
+    /// Returns all entities with the name `name`, in the order they were given that name
+    pub fn find_all_by_name(&self, name: &str) -> Vec<Entity> {
+        self.names.by_name.get(name).cloned().unwrap_or_default()
+    }
+
+    /// Adds the current [`Name`] of `entity` to the index, if it has one
+    pub(crate) fn index_name(&mut self, entity: Entity) {
+        self.unindex_name(entity);
+
+        let Ok(name) = self.ecs.get::<&Name>(entity.0) else {
+            return;
+        };
+
+        self.names
+            .by_name
+            .entry(name.0.clone())
+            .or_default()
+            .push(entity);
+
+        self.names.by_entity.insert(entity, name.0.clone());
+    }
+
+    /// Removes `entity` from the index, under the name it was indexed with
+    pub(crate) fn unindex_name(&mut self, entity: Entity) {
+        let Some(name) = self.names.by_entity.remove(&entity) else {
+            return;
+        };
+
+        if let Some(entities) = self.names.by_name.get_mut(name.as_str()) {
+            entities.retain(|e| *e != entity);
+
+            if entities.is_empty() {
+                self.names.by_name.remove(name.as_str());
+            }
+        }
+    }
+
+    /// Re-indexes the entities whose [`Name`] was changed in place since the last refresh. Like all
+    /// change detection, this only sees names changed through a system query or [`crate::system::Mut`]
+    pub(crate) fn refresh_names(&mut self) {
+        let last_refresh =
+            core::mem::replace(&mut self.names.last_refresh, crate::system::current_tick());
+
+        let renamed: Vec<Entity> = self
+            .ecs
+            .query::<(hecs::Entity, &Name, &ComponentTicks<Name>)>()
+            .iter()
+            .filter(|(entity, name, ticks)| {
+                ticks.is_changed_after(last_refresh)
+                    && self
+                        .names
+                        .by_entity
+                        .get(&Entity(*entity))
+                        .is_none_or(|indexed| *indexed != name.0)
+            })
+            .map(|(entity, _, _)| Entity(entity))
+            .collect();
+
+        for entity in renamed {
+            self.index_name(entity);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::builtins::components::Name;
+    use crate::entity::Entity;
+    use crate::system::ComponentTicks;
+    use crate::system::Mut;
+    use crate::world::World;
+
+    #[test]
+    fn test_name_index() {
+        let mut world = World::new();
+
+        let mut spawn_named = |name: &str| {
+            let entity = Entity(world.ecs.spawn((Name::new(name.to_string()),)));
+            world.index_name(entity);
+            entity
+        };
+
+        let first = spawn_named("Player");
+        let second = spawn_named("Player");
+        let other = spawn_named("Enemy");
+
+        assert_eq!(Some(first), world.find_by_name("Player"));
+        assert_eq!(vec![first, second], world.find_all_by_name("Player"));
+
+        world.destroy(other);
+
+        assert_eq!(None, world.find_by_name("Enemy"));
+
+        assert!(world.remove_component::<Name>(first).is_some());
+        assert_eq!(vec![second], world.find_all_by_name("Player"));
+    }
+
+    #[test]
+    fn test_rename_in_place() {
+        let mut world = World::new();
+
+        let mut spawn_named = |name: &str| {
+            let ticks = ComponentTicks::<Name>::new(crate::system::current_tick());
+            let entity = Entity(world.ecs.spawn((Name::new(name.to_string()), ticks)));
+            world.index_name(entity);
+            entity
+        };
+
+        let renamed = spawn_named("Player");
+        let kept = spawn_named("Player");
+
+        world.refresh_names();
+        crate::system::advance_tick();
+
+        for (entity, mut name) in world.query_mut::<(hecs::Entity, Mut<Name>)>() {
+            if entity == renamed.0 {
+                *name = Name::new("Enemy".to_string());
+            }
+        }
+
+        world.refresh_names();
+
+        assert_eq!(vec![kept], world.find_all_by_name("Player"));
+        assert_eq!(Some(renamed), world.find_by_name("Enemy"));
+
+        world.destroy(renamed);
+
+        assert_eq!(None, world.find_by_name("Enemy"));
+    }
+}
@@ -0,0 +1,95 @@
+//! Typed resources: global singleton values stored in the [`World`]
+
+use core::any::Any;
+use core::any::TypeId;
+use std::collections::HashMap;
+use std::sync::RwLock;
+use std::sync::RwLockReadGuard;
+use std::sync::RwLockWriteGuard;
+
+use super::World;
+
+/// Storage for all resources in a [`World`], by their type.
+///
+/// Each resource has its own lock, so that systems with non-clashing resource borrows can access
+/// them in parallel through a shared world reference
+#[derive(Debug, Default)]
+pub(crate) struct ResourceStorage {
+    /// The resources. Each value is a [`RwLock<T>`] for the resource type `T` with the key's [`TypeId`]
+    values: HashMap<TypeId, Box<dyn Any + Send + Sync>>,
+}
+
+impl World {
+    /// Inserts a resource into the world, returning the previous resource of the same type, if any
+    pub fn insert_resource<T: Send + Sync + 'static>(&mut self, resource: T) -> Option<T> {
+        log::debug!("Inserting resource {}", core::any::type_name::<T>());
+
+        self.resources
+            .values
+            .insert(TypeId::of::<T>(), Box::new(RwLock::new(resource)))
+            .map(|previous| unwrap_resource(previous))
+    }
+
+    /// Removes a resource from the world, returning it if it existed
+    pub fn remove_resource<T: Send + Sync + 'static>(&mut self) -> Option<T> {
+        log::debug!("Removing resource {}", core::any::type_name::<T>());
+
+        self.resources
+            .values
+            .remove(&TypeId::of::<T>())
+            .map(|previous| unwrap_resource(previous))
+    }
+
+    /// Returns whether a resource of type `T` exists in the world
+    #[inline]
+    pub fn contains_resource<T: Send + Sync + 'static>(&self) -> bool {
+        self.resources.values.contains_key(&TypeId::of::<T>())
+    }
+
+    /// Borrows the resource of type `T`, if it exists
+    pub fn resource<T: Send + Sync + 'static>(&self) -> Option<RwLockReadGuard<'_, T>> {
+        self.resource_lock::<T>().map(|lock| lock.read().unwrap())
+    }
+
+    /// Mutably borrows the resource of type `T`, if it exists
+    pub fn resource_mut<T: Send + Sync + 'static>(&self) -> Option<RwLockWriteGuard<'_, T>> {
+        self.resource_lock::<T>().map(|lock| lock.write().unwrap())
+    }
+
+    fn resource_lock<T: Send + Sync + 'static>(&self) -> Option<&RwLock<T>> {
+        self.resources.values.get(&TypeId::of::<T>()).map(|value| {
+            value
+                .downcast_ref()
+                .expect("Resource stored with wrong type")
+        })
+    }
+}
+
+/// Extracts the resource from its type-erased lock
+fn unwrap_resource<T: Send + Sync + 'static>(value: Box<dyn Any + Send + Sync>) -> T {
+    value
+        .downcast::<RwLock<T>>()
+        .expect("Resource stored with wrong type")
+        .into_inner()
+        .unwrap()
+}
+
+#[cfg(test)]
+mod test {
+    use super::World;
+
+    #[test]
+    fn test_resources() {
+        let mut world = World::new();
+
+        assert!(world.resource::<u32>().is_none());
+        assert!(world.insert_resource(5_u32).is_none());
+
+        *world.resource_mut::<u32>().unwrap() += 1;
+
+        assert_eq!(6, *world.resource::<u32>().unwrap());
+        assert_eq!(Some(6), world.insert_resource(10_u32));
+        assert_eq!(Some(10), world.remove_resource::<u32>());
+        assert!(!world.contains_resource::<u32>());
+    }
+}
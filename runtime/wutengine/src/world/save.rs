@@ -0,0 +1,500 @@
+//! Save-game snapshots of the world, stored as versioned binary blobs
+
+use std::collections::HashMap;
+use std::collections::HashSet;
+use std::path::Path;
+use std::path::PathBuf;
+
+use wutengine_assets::assets::component::SerializedComponent;
+use wutengine_task::TaskHandle;
+
+use super::World;
+use crate::builtins::components::Name;
+use crate::builtins::components::Transform;
+use crate::component::Component;
+use crate::component::ComponentSerializationErr;
+use crate::component::SerializableComponent;
+use crate::entity::Entity;
+
+/// The magic bytes every save blob starts with
+const SAVE_MAGIC: [u8; 4] = *b"WESV";
+
+/// The version of the save blob format written by this version of WutEngine
+pub const SAVE_FORMAT_VERSION: u32 = 1;
+
+/// The config key of the directory [`default_save_dir`] returns, overriding the platform default
+const SAVE_DIR_KEY: &str = "wutengine.save.directory";
+
+/// An error while saving or restoring a [`SaveGame`]
+#[derive(Debug, derive_more::Error, derive_more::Display, derive_more::From)]
+pub enum SaveGameErr {
+    /// A component could not be serialized
+    #[display("Failed to serialize component: {_0}")]
+    Component(ComponentSerializationErr),
+
+    /// The save blob could not be encoded or decoded
+    #[display("Failed to encode or decode save blob: {_0}")]
+    Encoding(postcard::Error),
+
+    /// The save file could not be read or written
+    #[display("Failed to read or write save file: {_0}")]
+    Io(std::io::Error),
+
+    /// The data is not a WutEngine save blob
+    #[display("Data is not a WutEngine save blob")]
+    InvalidHeader,
+
+    /// The save blob was written with an unsupported format version
+    #[display("Unsupported save format version {_0}. Expected at most {SAVE_FORMAT_VERSION}")]
+    #[from(skip)]
+    UnsupportedVersion(#[error(not(source))] u32),
+}
+
+/// Marks an entity for saving with [`SaveScope::Persistent`]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Persistent;
+
+impl Component for Persistent {
+    const ID: uuid::NonNilUuid =
+        uuid::NonNilUuid::new(uuid::uuid!("7a1d4e9c-3b62-4f08-9e57-c2b8a6f1d403")).unwrap();
+}
+
+impl SerializableComponent for Persistent {
+    type Serialized = ();
+
+    fn to_serialized(&self) -> Self::Serialized {}
+
+    fn from_serialized((): Self::Serialized) -> Self {
+        Self
+    }
+}
+
+/// Which entities are part of a [`SaveGame`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SaveScope {
+    /// All entities in the world with at least one registered serializable component besides their
+    /// [`Name`]. Other entities, like the render passes of the engine and plugins, could not be restored
+    /// beyond their name, so they are skipped
+    #[default]
+    All,
+
+    /// Only the entities with a [`Persistent`] component
+    Persistent,
+}
+
+/// A snapshot of (a subset of) the entities in the world, and their registered serializable components.
+///
+/// Components that were not registered with [`crate::component::register_serializable_component`] are not saved
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct SaveGame {
+    /// The saved entities. Parents always come before their children
+    pub entities: Vec<SavedEntity>,
+}
+
+/// A single entity in a [`SaveGame`]
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct SavedEntity {
+    /// The ID of the entity at the time it was saved
+    pub id: Entity,
+
+    /// The name of the entity, if it had one
+    pub name: Option<String>,
+
+    /// The parent of the entity at the time it was saved, if the parent is part of the same save
+    pub parent: Option<Entity>,
+
+    /// The serialized components of the entity, excluding its name
+    pub components: Vec<SerializedComponent>,
+}
+
+/// Maps the IDs entities had when they were saved to the IDs of the entities they were restored as
+#[derive(Debug, Clone, Default)]
+pub struct EntityMap(HashMap<Entity, Entity>);
+
+impl EntityMap {
+    /// Returns the restored entity of the saved entity `saved`, if it was part of the save
+    #[inline]
+    pub fn get(&self, saved: Entity) -> Option<Entity> {
+        self.0.get(&saved).copied()
+    }
+
+    /// Returns the restored entity of the saved entity `saved`, or `saved` itself if it was not part of the save
+    #[inline]
+    pub fn map(&self, saved: Entity) -> Entity {
+        self.get(saved).unwrap_or(saved)
+    }
+
+    /// Returns all restored entities
+    pub fn restored(&self) -> impl Iterator<Item = Entity> + '_ {
+        self.0.values().copied()
+    }
+}
+
+impl SaveGame {
+    /// Encodes the save as a versioned binary blob
+    pub fn to_bytes(&self) -> Result<Vec<u8>, SaveGameErr> {
+        let mut bytes = Vec::from(SAVE_MAGIC);
+        bytes.extend_from_slice(&SAVE_FORMAT_VERSION.to_le_bytes());
+
+        Ok(postcard::to_extend(self, bytes)?)
+    }
+
+    /// Decodes a save from a binary blob written by [`Self::to_bytes`]
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, SaveGameErr> {
+        let (magic, rest) = bytes
+            .split_first_chunk::<4>()
+            .ok_or(SaveGameErr::InvalidHeader)?;
+
+        if *magic != SAVE_MAGIC {
+            return Err(SaveGameErr::InvalidHeader);
+        }
+
+        let (version, data) = rest
+            .split_first_chunk::<4>()
+            .ok_or(SaveGameErr::InvalidHeader)?;
+
+        let version = u32::from_le_bytes(*version);
+
+        if version > SAVE_FORMAT_VERSION {
+            return Err(SaveGameErr::UnsupportedVersion(version));
+        }
+
+        Ok(postcard::from_bytes(data)?)
+    }
+}
+
+impl World {
+    /// Takes a snapshot of the entities in `scope`
+    pub fn snapshot(&self, scope: SaveScope) -> Result<SaveGame, SaveGameErr> {
+        profiling::function_scope!();
+
+        let included: HashSet<Entity> = match scope {
+            SaveScope::All => self
+                .ecs
+                .iter()
+                .map(|e| Entity(e.entity()))
+                .filter(|entity| crate::component::has_serializable_components(self, *entity))
+                .collect(),
+            SaveScope::Persistent => self
+                .ecs
+                .query::<hecs::With<hecs::Entity, &Persistent>>()
+                .iter()
+                .map(Entity)
+                .collect(),
+        };
+
+        let parent_of = |entity: Entity| {
+            self.component::<Transform>(entity)
+                .and_then(|transform| transform.parent())
+                .filter(|parent| included.contains(parent))
+        };
+
+        // Walked depth-first from the roots, so that parents are saved before their children, and children
+        // keep their order
+        let mut to_save: Vec<Entity> = included
+            .iter()
+            .copied()
+            .filter(|entity| parent_of(*entity).is_none())
+            .collect();
+
+        to_save.sort_unstable_by(|a, b| b.cmp(a));
+
+        let mut entities = Vec::with_capacity(included.len());
+
+        while let Some(entity) = to_save.pop() {
+            if let Some(transform) = self.component::<Transform>(entity) {
+                to_save.extend(
+                    transform
+                        .children()
+                        .iter()
+                        .rev()
+                        .filter(|child| included.contains(child)),
+                );
+            }
+
+            entities.push(SavedEntity {
+                id: entity,
+                name: self.component::<Name>(entity).map(|name| name.to_string()),
+                parent: parent_of(entity),
+                components: crate::component::serialize_components_in(self, entity)?,
+            });
+        }
+
+        Ok(SaveGame { entities })
+    }
+
+    /// Spawns all entities of `save` into this world, next to the existing entities. References to saved
+    /// entities are remapped to the restored entities with [`SerializableComponent::map_entities`].
+    ///
+    /// The saved entities are always spawned anew, so restoring into the world the save was taken from
+    /// duplicates any entities that still exist. Destroy them first, for example by making them
+    /// [`crate::state::StateScoped`], to replace them instead. Components of unknown types are skipped
+    pub fn restore(&mut self, save: &SaveGame) -> EntityMap {
+        profiling::function_scope!();
+
+        let mut map = EntityMap::default();
+
+        for saved in &save.entities {
+            map.0.insert(saved.id, Entity(self.ecs.spawn(())));
+        }
+
+        for saved in &save.entities {
+            let entity = map.map(saved.id);
+
+            if let Some(name) = &saved.name {
+                self.add_component(entity, Name::new(name.clone()));
+            }
+
+            for component in &saved.components {
+                if let Err(e) =
+                    crate::component::insert_serialized_component(self, entity, component)
+                {
+                    log::error!(
+                        "Skipping component {} on restored entity {entity}: {e}",
+                        component.component_type
+                    );
+                }
+            }
+
+            if let Some(parent) = saved.parent {
+                self.set_parent(entity, Some(map.map(parent)));
+            }
+        }
+
+        for entity in map.restored() {
+            crate::component::map_component_entities(self, entity, &map);
+        }
+
+        log::info!("Restored {} entities from save", save.entities.len());
+
+        map
+    }
+}
+
+/// Takes a snapshot of the entities in `scope`, and writes it to the file at `path` in the background.
+/// Parent directories are created if needed.
+///
+/// The snapshot itself is taken immediately, so it must not be called from a system that mutably borrows
+/// any registered serializable component
+pub fn save_to_file(
+    scope: SaveScope,
+    path: impl Into<PathBuf>,
+) -> TaskHandle<Result<(), SaveGameErr>> {
+    let path = path.into();
+
+    let save = match super::get_world().snapshot(scope) {
+        Ok(save) => save,
+        Err(e) => return TaskHandle::from_value(Err(e)),
+    };
+
+    wutengine_task::spawn_async(async move {
+        let bytes = save.to_bytes()?;
+
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+
+        std::fs::write(&path, bytes)?;
+
+        log::info!(
+            "Saved {} entities to {}",
+            save.entities.len(),
+            path.display()
+        );
+
+        Ok(())
+    })
+}
+
+/// Reads the save at `path` in the background, and then restores it into the world on the main thread
+/// before the next frame. See [`World::restore`]
+pub fn load_from_file(path: impl Into<PathBuf>) -> TaskHandle<Result<EntityMap, SaveGameErr>> {
+    let path = path.into();
+
+    wutengine_task::spawn_async(async move {
+        let save = SaveGame::from_bytes(&std::fs::read(&path)?)?;
+
+        log::info!("Loaded save {}", path.display());
+
+        let restored =
+            crate::runtime::run_on_main_thread(move || super::get_world_mut().restore(&save));
+
+        Ok(restored.get_async().await)
+    })
+}
+
+/// Returns the directory saves of the application `app_name` should be stored in, if it could be
+/// determined. Can be overridden with the `wutengine.save.directory` config key.
+///
+/// Defaults to `saves` in the platform's user data directory: `%APPDATA%\{app_name}` on Windows,
+/// `~/Library/Application Support/{app_name}` on macOS and `$XDG_DATA_HOME/{app_name}` on Linux
+pub fn default_save_dir(app_name: &str) -> Option<PathBuf> {
+    if let Some(configured) = crate::config::try_get::<String>(SAVE_DIR_KEY) {
+        return Some(PathBuf::from(configured));
+    }
+
+    dirs::data_dir().map(|dir| dir.join(app_name).join("saves"))
+}
+
+/// Returns the path of the save named `name` in [`default_save_dir`], if it could be determined
+pub fn default_save_path(app_name: &str, name: impl AsRef<Path>) -> Option<PathBuf> {
+    default_save_dir(app_name).map(|dir| dir.join(name).with_extension("wesave"))
+}
+
+#[cfg(test)]
+mod test {
+    use wutengine_math::Quat;
+    use wutengine_math::Vec3;
+
+    use super::EntityMap;
+    use super::Persistent;
+    use super::SaveGame;
+    use super::SaveGameErr;
+    use super::SaveScope;
+    use crate::builtins::components::Name;
+    use crate::builtins::components::Transform;
+    use crate::component::Component;
+    use crate::component::SerializableComponent;
+    use crate::entity::Entity;
+    use crate::world::World;
+
+    /// References another entity, to check that references are remapped on restore
+    struct Target(Entity);
+
+    impl Component for Target {
+        const ID: uuid::NonNilUuid =
+            uuid::NonNilUuid::new(uuid::uuid!("b4e2c7a1-59d3-4f86-8a0e-6d1f3c92e875")).unwrap();
+    }
+
+    impl SerializableComponent for Target {
+        type Serialized = Entity;
+
+        fn to_serialized(&self) -> Self::Serialized {
+            self.0
+        }
+
+        fn from_serialized(serialized: Self::Serialized) -> Self {
+            Self(serialized)
+        }
+
+        fn map_entities(&mut self, map: &EntityMap) {
+            self.0 = map.map(self.0);
+        }
+    }
+
+    #[test]
+    fn test_snapshot_roundtrip() {
+        let mut world = World::new();
+
+        let spawn = |world: &mut World, name: &str, persistent: bool| {
+            let entity = Entity(world.ecs.spawn((
+                Name::new(name.to_string()),
+                Transform::new_at_local(Vec3::X, Quat::IDENTITY, Vec3::ONE),
+            )));
+
+            if persistent {
+                world.ecs.insert_one(entity.0, Persistent).unwrap();
+            }
+
+            entity
+        };
+
+        let parent = spawn(&mut world, "Parent", true);
+        let child = spawn(&mut world, "Child", true);
+        let _temporary = spawn(&mut world, "Temporary", false);
+
+        world.set_parent(child, Some(parent));
+
+        let save = world.snapshot(SaveScope::Persistent).unwrap();
+
+        assert_eq!(2, save.entities.len());
+        assert_eq!(parent, save.entities[0].id);
+        assert_eq!(Some(parent), save.entities[1].parent);
+
+        let decoded = SaveGame::from_bytes(&save.to_bytes().unwrap()).unwrap();
+
+        assert_eq!(Some("Child"), decoded.entities[1].name.as_deref());
+        assert_eq!(Some(parent), decoded.entities[1].parent);
+        assert_eq!(
+            save.entities[1].components.len(),
+            decoded.entities[1].components.len()
+        );
+
+        assert!(matches!(
+            SaveGame::from_bytes(b"nope"),
+            Err(SaveGameErr::InvalidHeader)
+        ));
+        assert_eq!(3, world.snapshot(SaveScope::All).unwrap().entities.len());
+    }
+
+    #[test]
+    fn test_restore_remaps_entities() {
+        crate::component::register_serializable_component::<Target>();
+
+        // There is no runtime to queue the default systems of the restored components on
+        _ = crate::component::should_insert_default_component_systems::<Name>();
+        _ = crate::component::should_insert_default_component_systems::<Transform>();
+        _ = crate::component::should_insert_default_component_systems::<Target>();
+
+        let mut world = World::new();
+
+        let parent = Entity(
+            world
+                .ecs
+                .spawn((Name::new("Parent".to_string()), Transform::new())),
+        );
+        let child = Entity(world.ecs.spawn((
+            Name::new("Child".to_string()),
+            Transform::new_at_local(Vec3::X, Quat::IDENTITY, Vec3::ONE),
+            Target(parent),
+        )));
+        let _unserializable = world.ecs.spawn((Name::new("Render pass".to_string()),));
+
+        world.set_parent(child, Some(parent));
+
+        let save = world.snapshot(SaveScope::All).unwrap();
+
+        assert_eq!(2, save.entities.len(), "Unserializable entity was saved");
+
+        // Offset the entity IDs, so that unmapped references would point at the wrong entities
+        let mut restored_world = World::new();
+
+        for _ in 0..3 {
+            restored_world.ecs.spawn(());
+        }
+
+        let map = restored_world.restore(&save);
+
+        let restored_parent = map.get(parent).unwrap();
+        let restored_child = map.get(child).unwrap();
+
+        assert_ne!(parent, restored_parent);
+        assert_ne!(child, restored_child);
+
+        assert_eq!(
+            Some(restored_parent),
+            restored_world
+                .component::<Transform>(restored_child)
+                .unwrap()
+                .parent()
+        );
+        assert_eq!(
+            &[restored_child],
+            restored_world
+                .component::<Transform>(restored_parent)
+                .unwrap()
+                .children()
+        );
+        assert_eq!(
+            restored_parent,
+            restored_world
+                .component::<Target>(restored_child)
+                .unwrap()
+                .0
+        );
+
+        assert_eq!(Some(restored_parent), restored_world.find_by_name("Parent"));
+        assert_eq!(Some(restored_child), restored_world.find_by_name("Child"));
+        assert_eq!(None, restored_world.find_by_name("Render pass"));
+    }
+}
@@ -0,0 +1,270 @@
+//! Instantiation of serialized levels and bundles into the running world
+
+use alloc::sync::Arc;
+use core::convert::Infallible;
+use std::collections::HashMap;
+
+use wutengine_asset_server::GetAssetErr;
+use wutengine_assets::AssetRef;
+use wutengine_assets::assets::bundle::BundleEntry;
+use wutengine_assets::assets::bundle::SerializedBundle;
+use wutengine_assets::assets::entity::EntityEntry;
+use wutengine_assets::assets::entity::SerializedEntity;
+use wutengine_assets::assets::level::LevelEntry;
+use wutengine_assets::assets::level::SerializedLevel;
+use wutengine_task::TaskHandle;
+
+use crate::entity::Entity;
+
+/// An error while spawning a [`SerializedLevel`] or [`SerializedBundle`] into the world
+#[derive(Debug, derive_more::Error, derive_more::Display)]
+pub enum SpawnAssetErr {
+    /// The level asset could not be loaded
+    #[display("Failed to load level: {_0}")]
+    Level(GetAssetErr<Infallible>),
+
+    /// A (nested) bundle asset could not be loaded
+    #[display("Failed to load bundle: {_0}")]
+    Bundle(GetAssetErr<Infallible>),
+
+    /// A bundle (indirectly) contains itself
+    #[display("Bundle {_0} contains itself")]
+    RecursiveBundle(#[error(not(source))] uuid::NonNilUuid),
+}
+
+/// Loads the given level and spawns all its entities and bundles into the world.
+///
+/// Loading happens in the background. The returned handle yields the root entities of the level
+/// once all of them have been queued for spawning. Like any other spawned entity, their components
+/// are inserted right before the next frame-phase callback.
+pub fn spawn_level(
+    level: AssetRef<SerializedLevel>,
+) -> TaskHandle<Result<Vec<Entity>, SpawnAssetErr>> {
+    wutengine_task::spawn_async(async move {
+        let server = wutengine_asset_server::global_asset_server();
+
+        let level = server
+            .get_ref::<SerializedLevel>(&level)
+            .get_async()
+            .await
+            .map_err(SpawnAssetErr::Level)?;
+
+        log::info!("Spawning level {}", level.name);
+
+        let entries: Vec<Entry<'_>> = level.entries.iter().map(Entry::from).collect();
+
+        let bundles = load_nested_bundles(&entries).await?;
+
+        Ok(spawn_entries(&entries, None, &bundles))
+    })
+}
+
+/// Loads the given bundle and spawns all its entities and nested bundles into the world.
+///
+/// Loading happens in the background. The returned handle yields the root entities of the bundle
+/// once all of them have been queued for spawning. If `parent` is given, the root entities are
+/// attached to it in the transform hierarchy
+pub fn spawn_bundle(
+    bundle: AssetRef<SerializedBundle>,
+    parent: Option<Entity>,
+) -> TaskHandle<Result<Vec<Entity>, SpawnAssetErr>> {
+    wutengine_task::spawn_async(async move {
+        let entries = [Entry::Bundle(&bundle)];
+
+        let bundles = load_nested_bundles(&entries).await?;
+
+        Ok(spawn_entries(&entries, parent, &bundles))
+    })
+}
+
+/// A borrowed level, bundle or entity entry. These all share the same structure, so they
+/// are handled the same way during spawning
+#[derive(Debug, Clone, Copy)]
+enum Entry<'a> {
+    /// An inline entity
+    Entity(&'a SerializedEntity),
+
+    /// A reference to a bundle
+    Bundle(&'a AssetRef<SerializedBundle>),
+}
+
+impl<'a> From<&'a LevelEntry> for Entry<'a> {
+    fn from(value: &'a LevelEntry) -> Self {
+        match value {
+            LevelEntry::Entity(entity) => Self::Entity(entity),
+            LevelEntry::Bundle(bundle) => Self::Bundle(bundle),
+        }
+    }
+}
+
+impl<'a> From<&'a BundleEntry> for Entry<'a> {
+    fn from(value: &'a BundleEntry) -> Self {
+        match value {
+            BundleEntry::Entity(entity) => Self::Entity(entity),
+            BundleEntry::Bundle(bundle) => Self::Bundle(bundle),
+        }
+    }
+}
+
+impl<'a> From<&'a EntityEntry> for Entry<'a> {
+    fn from(value: &'a EntityEntry) -> Self {
+        match value {
+            EntityEntry::Entity(entity) => Self::Entity(entity),
+            EntityEntry::Bundle(bundle) => Self::Bundle(bundle),
+        }
+    }
+}
+
+/// Map of all bundles referenced by a set of entries, by their asset ID
+type LoadedBundles = HashMap<uuid::NonNilUuid, Arc<SerializedBundle>>;
+
+/// Loads all bundles referenced, directly or through other bundles and entities, by `entries`,
+/// and checks that none of them contain themselves.
+///
+/// Everything is loaded and checked before anything is spawned, so an invalid bundle does not leave a
+/// half-spawned level behind
+async fn load_nested_bundles(entries: &[Entry<'_>]) -> Result<LoadedBundles, SpawnAssetErr> {
+    let server = wutengine_asset_server::global_asset_server();
+
+    let mut loaded = LoadedBundles::new();
+    let mut to_load = Vec::new();
+
+    collect_bundle_refs(entries.iter().copied(), &mut to_load);
+
+    while let Some(bundle_ref) = to_load.pop() {
+        if bundle_ref
+            .get_id()
+            .is_some_and(|id| loaded.contains_key(&id))
+        {
+            continue;
+        }
+
+        let bundle = server
+            .get_ref::<SerializedBundle>(&bundle_ref)
+            .get_async()
+            .await
+            .map_err(SpawnAssetErr::Bundle)?;
+
+        collect_bundle_refs(bundle.entries.iter().map(Entry::from), &mut to_load);
+
+        loaded.insert(bundle_ref.get_id().expect("Loaded, so has ID"), bundle);
+    }
+
+    check_recursion(entries.iter().copied(), &loaded, &mut Vec::new())?;
+
+    Ok(loaded)
+}
+
+/// Adds all bundle references in `entries` and their child entities to `refs`
+fn collect_bundle_refs<'a>(
+    entries: impl Iterator<Item = Entry<'a>>,
+    refs: &mut Vec<AssetRef<SerializedBundle>>,
+) {
+    for entry in entries {
+        match entry {
+            Entry::Entity(entity) => {
+                collect_bundle_refs(entity.children.iter().map(Entry::from), refs);
+            }
+            Entry::Bundle(bundle) => refs.push(bundle.clone()),
+        }
+    }
+}
+
+/// Checks that none of the bundles referenced by `entries` contain themselves.
+///
+/// `bundle_stack` contains the bundles currently being checked
+fn check_recursion<'a>(
+    entries: impl Iterator<Item = Entry<'a>>,
+    bundles: &'a LoadedBundles,
+    bundle_stack: &mut Vec<uuid::NonNilUuid>,
+) -> Result<(), SpawnAssetErr> {
+    for entry in entries {
+        match entry {
+            Entry::Entity(entity) => {
+                check_recursion(
+                    entity.children.iter().map(Entry::from),
+                    bundles,
+                    bundle_stack,
+                )?;
+            }
+            Entry::Bundle(bundle_ref) => {
+                let id = bundle_ref.get_id().expect("Loaded, so has ID");
+
+                if bundle_stack.contains(&id) {
+                    return Err(SpawnAssetErr::RecursiveBundle(id));
+                }
+
+                bundle_stack.push(id);
+                check_recursion(
+                    bundles[&id].entries.iter().map(Entry::from),
+                    bundles,
+                    bundle_stack,
+                )?;
+                bundle_stack.pop();
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Spawns all entries, attaching their root entities to `parent`. Returns the root entities.
+///
+/// All referenced bundles must have been loaded and checked with [`load_nested_bundles`]
+fn spawn_entries(
+    entries: &[Entry<'_>],
+    parent: Option<Entity>,
+    bundles: &LoadedBundles,
+) -> Vec<Entity> {
+    let mut roots = Vec::with_capacity(entries.len());
+
+    for entry in entries {
+        match entry {
+            Entry::Entity(serialized) => {
+                roots.push(spawn_entity(serialized, parent, bundles));
+            }
+            Entry::Bundle(bundle_ref) => {
+                let id = bundle_ref.get_id().expect("Loaded, so has ID");
+                let bundle = &bundles[&id];
+
+                log::debug!("Spawning bundle {} ({id})", bundle.name);
+
+                let bundle_entries: Vec<Entry<'_>> =
+                    bundle.entries.iter().map(Entry::from).collect();
+
+                roots.extend(spawn_entries(&bundle_entries, parent, bundles));
+            }
+        }
+    }
+
+    roots
+}
+
+/// Spawns a single serialized entity and its children
+fn spawn_entity(
+    serialized: &SerializedEntity,
+    parent: Option<Entity>,
+    bundles: &LoadedBundles,
+) -> Entity {
+    let mut entity = Entity::spawn(serialized.name.as_str());
+
+    for component in &serialized.components {
+        if let Err(e) = crate::component::add_serialized_component(entity, component) {
+            log::error!(
+                "Skipping component {} on entity {}: {e}",
+                component.component_type,
+                serialized.name
+            );
+        }
+    }
+
+    if parent.is_some() {
+        entity = entity.set_parent(parent);
+    }
+
+    let children: Vec<Entry<'_>> = serialized.children.iter().map(Entry::from).collect();
+
+    spawn_entries(&children, Some(entity), bundles);
+
+    entity
+}
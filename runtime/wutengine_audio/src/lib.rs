@@ -63,6 +63,13 @@ pub fn init() {
     InitOnce::init(&AUDIO_MANAGER, AudioManager::default());
 }
 
+/// Initializes the audio manager without opening an audio device, for runtimes without audio
+/// output. No audio will be played
+#[doc(hidden)]
+pub fn init_without_output() {
+    InitOnce::init(&AUDIO_MANAGER, AudioManager { sink: None });
+}
+
 /// Returns a new audio player
 #[inline]
 pub fn new_player() -> Option<rodio::Player> {
@@ -271,13 +271,23 @@ pub fn set_state(active: bool) {
     DEV_OVERLAY.active.store(active, Ordering::Release);
 }
 
-/// Returns whether the development overlay is currently enabled
+/// Returns whether the development overlay is currently enabled. Always `false` if the overlay was
+/// not initialized, such as in a headless runtime
 pub fn is_enabled() -> bool {
-    DEV_OVERLAY.active.load(Ordering::Acquire)
+    InitOnce::is_initialized(&DEV_OVERLAY) && DEV_OVERLAY.active.load(Ordering::Acquire)
 }
 
-/// Add a new [`DevelopmentOverlayWindow`] to the engine
+/// Add a new [`DevelopmentOverlayWindow`] to the engine. Ignored if the overlay was not
+/// initialized, such as in a headless runtime
 pub fn add_development_overlay_window<T: DevelopmentOverlayWindow>(window: T) {
+    if !InitOnce::is_initialized(&DEV_OVERLAY) {
+        log::debug!(
+            "Ignoring development overlay window {} because the overlay is not initialized",
+            core::any::type_name::<T>()
+        );
+        return;
+    }
+
     DEV_OVERLAY.windows.lock().unwrap().push(DevOverlayWindow {
         id: DevOverlayWindowId::new(),
         open: false,
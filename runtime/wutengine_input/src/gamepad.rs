@@ -12,12 +12,30 @@ use wutengine_util_macro::VariantIndex;
 
 use crate::INPUT_MANAGER;
 
-use super::DeviceSet;
+use crate::recording::DeviceHandle;
+use crate::recording::InputEvent;
 
 /// A gamepad input device
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, derive_more::From, derive_more::Display)]
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    Hash,
+    derive_more::Display,
+    serde::Serialize,
+    serde::Deserialize,
+)]
 #[repr(transparent)]
-pub struct GamepadId(pub(super) gilrs::GamepadId);
+pub struct GamepadId(pub(crate) DeviceHandle<gilrs::GamepadId>);
+
+impl From<gilrs::GamepadId> for GamepadId {
+    #[inline]
+    fn from(value: gilrs::GamepadId) -> Self {
+        Self(DeviceHandle::Live(value))
+    }
+}
 
 /// The data for a gamepad
 #[derive(Debug)]
@@ -69,103 +87,116 @@ pub fn poll_for_events() {
         return;
     };
 
-    let mut gamepads = INPUT_MANAGER.gamepads.write().unwrap();
-    let mut gamepad_manager = gamepad_manager.lock().unwrap();
-
-    while let Some(event) = gamepad_manager.next_event() {
-        if event.is_dropped() {
-            continue;
-        }
-
-        let gamepad_id = GamepadId(event.id);
-        let gilrs_gamepad = gamepad_manager.gamepad(event.id);
-
-        log::trace!("Event for gamepad {}: {:#?}", event.id, event.event);
-
-        match event.event {
-            gilrs::EventType::ButtonPressed(button, code) => {
-                set_axis_or_button_value(
-                    &mut gamepads,
-                    gamepad_id,
-                    AxisOrButton::Button(Button::from_gilrs(button, code)),
-                    1.0,
-                );
-            }
-            gilrs::EventType::ButtonReleased(button, code) => {
-                set_axis_or_button_value(
-                    &mut gamepads,
-                    gamepad_id,
-                    AxisOrButton::Button(Button::from_gilrs(button, code)),
-                    0.0,
-                );
-            }
-            gilrs::EventType::ButtonChanged(button, value, code) => {
-                set_axis_or_button_value(
-                    &mut gamepads,
-                    gamepad_id,
-                    AxisOrButton::Button(Button::from_gilrs(button, code)),
-                    value,
-                );
-            }
-            gilrs::EventType::AxisChanged(axis, value, code) => {
-                let Some((axis, subaxis)) = Axis::from_gilrs(axis) else {
-                    log::warn!(
-                        "Unrecognized controller axis: {axis:#?} (native code: {})",
-                        code.into_u32()
-                    );
-                    continue;
-                };
-
-                set_axis_or_button_value(
-                    &mut gamepads,
-                    gamepad_id,
-                    AxisOrButton::Axis(axis, subaxis),
-                    value,
-                );
-            }
-            gilrs::EventType::Connected => {
-                let name = gilrs_gamepad.name();
-
-                log::info!("Gamepad \"{name}\" with ID {} connected", event.id);
+    let mut events = Vec::new();
 
-                INPUT_MANAGER.set_most_recent_gamepad(gamepad_id);
+    {
+        let mut gamepad_manager = gamepad_manager.lock().unwrap();
 
-                gamepads.update_device(Some(&gamepad_id), |_| {});
+        while let Some(event) = gamepad_manager.next_event() {
+            if event.is_dropped() {
+                continue;
             }
-            gilrs::EventType::Disconnected => {
-                let name = gilrs_gamepad.name();
-
-                log::info!("Gamepad \"{name}\" with ID {} disconnected", event.id);
 
-                gamepads.remove_device(&gamepad_id);
+            let gamepad_id = GamepadId::from(event.id);
+            let gilrs_gamepad = gamepad_manager.gamepad(event.id);
+
+            log::trace!("Event for gamepad {}: {:#?}", event.id, event.event);
+
+            match event.event {
+                gilrs::EventType::ButtonPressed(button, code) => {
+                    events.push(InputEvent::GamepadValue {
+                        gamepad: gamepad_id,
+                        input: AxisOrButton::Button(Button::from_gilrs(button, code)),
+                        value: 1.0,
+                    });
+                }
+                gilrs::EventType::ButtonReleased(button, code) => {
+                    events.push(InputEvent::GamepadValue {
+                        gamepad: gamepad_id,
+                        input: AxisOrButton::Button(Button::from_gilrs(button, code)),
+                        value: 0.0,
+                    });
+                }
+                gilrs::EventType::ButtonChanged(button, value, code) => {
+                    events.push(InputEvent::GamepadValue {
+                        gamepad: gamepad_id,
+                        input: AxisOrButton::Button(Button::from_gilrs(button, code)),
+                        value,
+                    });
+                }
+                gilrs::EventType::AxisChanged(axis, value, code) => {
+                    let Some((axis, subaxis)) = Axis::from_gilrs(axis) else {
+                        log::warn!(
+                            "Unrecognized controller axis: {axis:#?} (native code: {})",
+                            code.into_u32()
+                        );
+                        continue;
+                    };
+
+                    events.push(InputEvent::GamepadValue {
+                        gamepad: gamepad_id,
+                        input: AxisOrButton::Axis(axis, subaxis),
+                        value,
+                    });
+                }
+                gilrs::EventType::Connected => {
+                    let name = gilrs_gamepad.name();
+
+                    log::info!("Gamepad \"{name}\" with ID {} connected", event.id);
+
+                    events.push(InputEvent::GamepadConnected(gamepad_id));
+                }
+                gilrs::EventType::Disconnected => {
+                    let name = gilrs_gamepad.name();
+
+                    log::info!("Gamepad \"{name}\" with ID {} disconnected", event.id);
+
+                    events.push(InputEvent::GamepadDisconnected(gamepad_id));
+                }
+                _ => {}
             }
-            _ => {}
         }
+
+        gamepad_manager.inc();
     }
 
-    gamepad_manager.inc();
+    // Applied after releasing the gamepad manager, because live input is recorded or ignored
+    // while recording or replaying
+    for event in &events {
+        INPUT_MANAGER.live_input(event);
+    }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
-enum SubAxis {
+/// One of the two axes of a gamepad stick
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+pub(crate) enum SubAxis {
+    /// Horizontal
     X,
+
+    /// Vertical
     Y,
 }
 
-#[derive(Debug, Clone, Copy, derive_more::From)]
-enum AxisOrButton {
+/// A single gamepad input value
+#[derive(Debug, Clone, Copy, derive_more::From, serde::Serialize, serde::Deserialize)]
+pub(crate) enum AxisOrButton {
+    /// An axis of a stick
     Axis(Axis, SubAxis),
+
+    /// A button
     Button(Button),
 }
 
-fn set_axis_or_button_value(
-    gamepads: &mut DeviceSet<super::GamepadId, Gamepad>,
+/// Sets the value of an axis or button of an identified gamepad
+pub(crate) fn set_axis_or_button_value(
     gamepad: super::GamepadId,
     axis_or_button: AxisOrButton,
     value: f32,
 ) {
     INPUT_MANAGER.set_most_recent_gamepad(gamepad);
 
+    let mut gamepads = INPUT_MANAGER.gamepads.write().unwrap();
+
     let Some(gamepad) = gamepads.get_identified_device_mut(&gamepad) else {
         log::warn!("Unknown gamepad: {gamepad}");
         return;
@@ -187,7 +218,7 @@ fn set_axis_or_button_value(
 /// Gamepad buttons.
 ///
 /// Based on [`gilrs 0.11.2`](https://docs.rs/gilrs/0.11.2/gilrs/)
-#[derive(Clone, Copy, Debug, Eq, PartialEq, VariantIndex)]
+#[derive(Clone, Copy, Debug, Eq, PartialEq, VariantIndex, serde::Serialize, serde::Deserialize)]
 #[index_repr(u32)]
 pub enum Button {
     /// Right button pad, south button
@@ -306,7 +337,7 @@ impl nohash_hasher::IsEnabled for Button {}
 /// Gamepad axes
 ///
 /// Based on [`gilrs 0.11.2`](https://docs.rs/gilrs/0.11.2/gilrs/)
-#[derive(Debug, Clone, Copy, Eq, PartialEq, VariantIndex)]
+#[derive(Debug, Clone, Copy, Eq, PartialEq, VariantIndex, serde::Serialize, serde::Deserialize)]
 #[index_repr(u8)]
 pub enum Axis {
     /// Left stick
@@ -7,7 +7,18 @@ use super::winit_native_keycode_to_u32;
 ///
 /// Taken from [`winit 0.30.13`](https://github.com/rust-windowing/winit/tree/v0.30.13),
 /// and modified to suit WutEngine APIs.
-#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, VariantIndex)]
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    VariantIndex,
+    serde::Serialize,
+    serde::Deserialize,
+)]
 #[index_repr(u32)]
 #[expect(clippy::doc_markdown, reason = "Too many false positives")]
 pub enum Key {
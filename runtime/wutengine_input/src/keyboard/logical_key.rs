@@ -3,7 +3,7 @@ use super::winit_nativekey_to_unknown_logical;
 /// A logical keyboard input.
 ///
 /// Used by non-location based input mappings, like UI and text input.
-#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
 pub enum LogicalKey {
     /// A character
     Character(char),
@@ -19,7 +19,7 @@ pub enum LogicalKey {
 }
 
 /// An unknown logical key with its identifier
-#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
 pub enum UnknownLogicalKey {
     /// Keycode
     Code(u32),
@@ -60,7 +60,7 @@ impl LogicalKey {
 ///
 /// Taken from [`winit 0.30.13`](https://github.com/rust-windowing/winit/tree/v0.30.13),
 /// and modified to suit WutEngine APIs.
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
 #[expect(clippy::doc_markdown, reason = "Too many false positives")]
 pub enum LogicalNamed {
     /// The `Alt` (Alternative) key.
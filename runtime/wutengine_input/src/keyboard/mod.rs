@@ -10,11 +10,14 @@ pub use logical_key::*;
 use nohash_hasher::IntSet;
 
 use super::INPUT_MANAGER;
+use crate::recording::DeviceHandle;
 
 /// A keyboard input device
-#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, serde::Serialize, serde::Deserialize,
+)]
 #[repr(transparent)]
-pub struct KeyboardId(winit::event::DeviceId);
+pub struct KeyboardId(pub(crate) DeviceHandle<winit::event::DeviceId>);
 
 impl KeyboardId {
     /// Maps a winit device to a [`KeyboardId`], if the winit device is valid
@@ -23,7 +26,7 @@ impl KeyboardId {
         if device == winit::event::DeviceId::dummy() {
             None
         } else {
-            Some(Self(device))
+            Some(Self(DeviceHandle::Live(device)))
         }
     }
 }
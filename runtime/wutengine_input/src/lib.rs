@@ -12,9 +12,10 @@ use keyboard::Keyboard;
 use keyboard::KeyboardId;
 use mouse::Mouse;
 use mouse::MouseId;
+use recording::InputEvent;
+use recording::InputSource;
 use winit::event::ButtonId;
 use winit::event::DeviceId;
-use winit::event::ElementState;
 use wutengine_math::Vec2;
 
 use wutengine_util::InitOnce;
@@ -22,11 +23,14 @@ use wutengine_util::InitOnce;
 pub mod gamepad;
 pub mod keyboard;
 pub mod mouse;
+pub mod recording;
 
 /// Opaque identifier for a window that can receive input.
 /// Users of the library should convert to-and-from this type
 /// from their own actual window handles.
-#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, serde::Serialize, serde::Deserialize,
+)]
 #[repr(transparent)]
 pub struct WindowIdentifier(u64);
 
@@ -179,6 +183,9 @@ pub(crate) struct InputManager {
 
     /// All known gamepads
     gamepads: RwLock<DeviceSet<GamepadId, Gamepad>>,
+
+    /// Whether live input is applied, recorded or ignored
+    source: Mutex<InputSource>,
 }
 
 impl Default for InputManager {
@@ -198,7 +205,7 @@ impl Default for InputManager {
                         gamepad.id()
                     );
 
-                    gamepads.update_device(Some(&GamepadId(id)), |_| {});
+                    gamepads.update_device(Some(&GamepadId::from(id)), |_| {});
                 }
 
                 Some(Mutex::new(grs))
@@ -217,6 +224,7 @@ impl Default for InputManager {
             mice: RwLock::default(),
             keyboards: RwLock::default(),
             gamepads: RwLock::new(gamepads),
+            source: Mutex::default(),
         }
     }
 }
@@ -228,20 +236,88 @@ impl InputManager {
         Self::default()
     }
 
-    /// Removes a winit device from the manager
-    fn remove_winit_device(&self, device: DeviceId) {
-        {
-            if let Some(mouse_id) = MouseId::from_winit(device) {
-                self.mice.write().unwrap().remove_device(&mouse_id);
-            }
+    /// Returns a new [`InputManager`] that does not read gamepad input
+    fn without_gamepads() -> Self {
+        Self {
+            gamepad_manager: None,
+            most_recent_mouse: RwLock::default(),
+            most_recent_keyboard: RwLock::default(),
+            most_recent_gamepad: RwLock::default(),
+            mice: RwLock::default(),
+            keyboards: RwLock::default(),
+            gamepads: RwLock::default(),
+            source: Mutex::default(),
         }
-        {
-            if let Some(keyboard_id) = KeyboardId::from_winit(device) {
-                self.keyboards.write().unwrap().remove_device(&keyboard_id);
+    }
+
+    /// Applies live input, unless recorded input is being replayed. Captures the input if it is being recorded
+    fn live_input(&self, event: &InputEvent) {
+        match &mut *self.source.lock().unwrap() {
+            InputSource::Live => {}
+            InputSource::Recording(recorder) => recorder.record(event),
+            InputSource::Replaying => return,
+        }
+
+        self.apply(event);
+    }
+
+    /// Applies live or recorded input
+    fn apply(&self, event: &InputEvent) {
+        match event {
+            InputEvent::MouseRemoved(mouse) => self.mice.write().unwrap().remove_device(mouse),
+            InputEvent::KeyboardRemoved(keyboard) => {
+                self.keyboards.write().unwrap().remove_device(keyboard);
+            }
+            InputEvent::MouseMotion { mouse, delta } => self.mouse_motion(*mouse, *delta),
+            InputEvent::MouseScroll { mouse, delta } => self.mouse_scroll(*mouse, *delta),
+            InputEvent::MouseButton {
+                mouse,
+                button,
+                pressed,
+            } => self.mouse_button(*mouse, *button, *pressed),
+            InputEvent::MouseWindowPosition { mouse, position } => {
+                self.mouse_window_position(*mouse, *position);
             }
+            InputEvent::KeyboardKey {
+                keyboard,
+                key,
+                pressed,
+            } => self.keyboard_key(*keyboard, *key, *pressed),
+            InputEvent::KeyboardLogicalKey {
+                keyboard,
+                key,
+                pressed,
+            } => self.keyboard_logical_key(*keyboard, key, *pressed),
+            InputEvent::KeyboardText { keyboard, text } => self.keyboard_text(*keyboard, text),
+            InputEvent::GamepadConnected(gamepad) => {
+                self.set_most_recent_gamepad(*gamepad);
+
+                self.gamepads
+                    .write()
+                    .unwrap()
+                    .update_device(Some(gamepad), |_| {});
+            }
+            InputEvent::GamepadDisconnected(gamepad) => {
+                self.gamepads.write().unwrap().remove_device(gamepad);
+            }
+            InputEvent::GamepadValue {
+                gamepad,
+                input,
+                value,
+            } => gamepad::set_axis_or_button_value(*gamepad, *input, *value),
         }
     }
 
+    /// Forgets all devices and their input state
+    fn reset_devices(&self) {
+        *self.most_recent_mouse.write().unwrap() = None;
+        *self.most_recent_keyboard.write().unwrap() = None;
+        *self.most_recent_gamepad.write().unwrap() = None;
+        *self.mice.write().unwrap() = DeviceSet::default();
+        *self.keyboards.write().unwrap() = DeviceSet::default();
+        *self.gamepads.write().unwrap() = DeviceSet::default();
+    }
+
     /// Advances the input manager and all devices to the next frame
     fn end_frame(&self) {
         self.mice.write().unwrap().for_each(Mouse::end_frame);
@@ -306,16 +382,19 @@ impl InputManager {
     }
 
     /// Sets the physical mouse button state for a given mouse
-    fn mouse_button(&self, mouse: Option<MouseId>, button: ButtonId, state: ElementState) {
+    fn mouse_button(&self, mouse: Option<MouseId>, button: ButtonId, pressed: bool) {
         if let Some(identified_mouse) = mouse {
             self.set_most_recent_mouse(identified_mouse);
         }
 
         let mut mice = self.mice.write().unwrap();
 
-        mice.update_device(mouse.as_ref(), |mouse| match state {
-            ElementState::Pressed => mouse.set_button_pressed(button),
-            ElementState::Released => mouse.set_button_released(button),
+        mice.update_device(mouse.as_ref(), |mouse| {
+            if pressed {
+                mouse.set_button_pressed(button);
+            } else {
+                mouse.set_button_released(button);
+            }
         });
     }
 
@@ -337,16 +416,19 @@ impl InputManager {
     }
 
     /// Adds a physical key state to the given keyboard
-    fn keyboard_key(&self, keyboard: Option<KeyboardId>, key: keyboard::Key, state: ElementState) {
+    fn keyboard_key(&self, keyboard: Option<KeyboardId>, key: keyboard::Key, pressed: bool) {
         if let Some(identified_keyboard) = keyboard {
             self.set_most_recent_keyboard(identified_keyboard);
         }
 
         let mut keyboards = self.keyboards.write().unwrap();
 
-        keyboards.update_device(keyboard.as_ref(), |kbd| match state {
-            ElementState::Pressed => kbd.set_key_pressed(key),
-            ElementState::Released => kbd.set_key_released(key),
+        keyboards.update_device(keyboard.as_ref(), |kbd| {
+            if pressed {
+                kbd.set_key_pressed(key);
+            } else {
+                kbd.set_key_released(key);
+            }
         });
     }
 
@@ -355,7 +437,7 @@ impl InputManager {
         &self,
         keyboard: Option<KeyboardId>,
         logical_key: &keyboard::LogicalKey,
-        state: ElementState,
+        pressed: bool,
     ) {
         if let Some(identified_keyboard) = keyboard {
             self.set_most_recent_keyboard(identified_keyboard);
@@ -363,11 +445,10 @@ impl InputManager {
 
         let mut keyboards = self.keyboards.write().unwrap();
 
-        keyboards.update_device(keyboard.as_ref(), |kbd| match state {
-            ElementState::Pressed => {
+        keyboards.update_device(keyboard.as_ref(), |kbd| {
+            if pressed {
                 kbd.add_logical_input(keyboard::LogicalInput::Pressed(logical_key.clone()));
-            }
-            ElementState::Released => {
+            } else {
                 kbd.add_logical_input(keyboard::LogicalInput::Released(logical_key.clone()));
             }
         });
@@ -398,6 +479,13 @@ pub fn init() {
     InitOnce::init(&INPUT_MANAGER, InputManager::new());
 }
 
+/// Initializes the global [`InputManager`] without gamepad support, for runtimes without any
+/// input devices
+#[doc(hidden)]
+pub fn init_without_gamepads() {
+    InitOnce::init(&INPUT_MANAGER, InputManager::without_gamepads());
+}
+
 /// Inserts a new raw [winit device event](winit::event::DeviceEvent) for the given [`device`](winit::event::DeviceId)
 /// into the input manager for the current frame.
 #[expect(
@@ -423,33 +511,29 @@ pub fn insert_raw_device_event(device: DeviceId, event: winit::event::DeviceEven
 
             log::info!("Raw input device added: {device:?}");
 
-            INPUT_MANAGER.remove_winit_device(device);
+            if let Some(mouse) = MouseId::from_winit(device) {
+                INPUT_MANAGER.live_input(&InputEvent::MouseRemoved(mouse));
+            }
+
+            if let Some(keyboard) = KeyboardId::from_winit(device) {
+                INPUT_MANAGER.live_input(&InputEvent::KeyboardRemoved(keyboard));
+            }
         }
         winit::event::DeviceEvent::MouseMotion { delta } => {
             profiling::scope!("Mouse motion");
 
-            INPUT_MANAGER.mouse_motion(
-                MouseId::from_winit(device),
-                Vec2::new(delta.0 as f32, -delta.1 as f32),
-            );
+            INPUT_MANAGER.live_input(&InputEvent::MouseMotion {
+                mouse: MouseId::from_winit(device),
+                delta: Vec2::new(delta.0 as f32, -delta.1 as f32),
+            });
         }
         winit::event::DeviceEvent::MouseWheel { delta } => {
             profiling::scope!("Mouse wheel");
 
-            match delta {
-                winit::event::MouseScrollDelta::LineDelta(hor, ver) => {
-                    INPUT_MANAGER.mouse_scroll(MouseId::from_winit(device), Vec2::new(hor, ver));
-                }
-                winit::event::MouseScrollDelta::PixelDelta(phys_pos) => {
-                    const PIXELS_PER_LINE: f32 = 50.0;
-                    const LINES_PER_PIXEL: f32 = 1.0 / PIXELS_PER_LINE;
-
-                    let hor = (phys_pos.x as f32) * LINES_PER_PIXEL;
-                    let ver = (phys_pos.y as f32) * LINES_PER_PIXEL;
-
-                    INPUT_MANAGER.mouse_scroll(MouseId::from_winit(device), Vec2::new(hor, ver));
-                }
-            }
+            INPUT_MANAGER.live_input(&InputEvent::MouseScroll {
+                mouse: MouseId::from_winit(device),
+                delta: scroll_delta_to_lines(delta),
+            });
         }
         winit::event::DeviceEvent::Motion { .. } => {
             log::trace!("Ignoring unsupported device motion event");
@@ -457,17 +541,21 @@ pub fn insert_raw_device_event(device: DeviceId, event: winit::event::DeviceEven
         winit::event::DeviceEvent::Button { button, state } => {
             profiling::scope!("Button");
 
-            INPUT_MANAGER.mouse_button(MouseId::from_winit(device), button, state);
+            INPUT_MANAGER.live_input(&InputEvent::MouseButton {
+                mouse: MouseId::from_winit(device),
+                button,
+                pressed: state.is_pressed(),
+            });
         }
         winit::event::DeviceEvent::Key(raw_key_event) => {
             profiling::scope!("Key");
 
             if let Ok(as_key) = keyboard::Key::try_from(raw_key_event.physical_key) {
-                INPUT_MANAGER.keyboard_key(
-                    KeyboardId::from_winit(device),
-                    as_key,
-                    raw_key_event.state,
-                );
+                INPUT_MANAGER.live_input(&InputEvent::KeyboardKey {
+                    keyboard: KeyboardId::from_winit(device),
+                    key: as_key,
+                    pressed: raw_key_event.state.is_pressed(),
+                });
             }
         }
     }
@@ -503,11 +591,19 @@ pub fn insert_raw_window_event(
             let keyboard_id = KeyboardId::from_winit(*device_id);
 
             if let Ok(as_key) = keyboard::Key::try_from(event.physical_key) {
-                INPUT_MANAGER.keyboard_key(keyboard_id, as_key, event.state);
+                INPUT_MANAGER.live_input(&InputEvent::KeyboardKey {
+                    keyboard: keyboard_id,
+                    key: as_key,
+                    pressed: event.state.is_pressed(),
+                });
             }
 
             if let Some(logical_key) = keyboard::LogicalKey::try_from_winit(&event.logical_key) {
-                INPUT_MANAGER.keyboard_logical_key(keyboard_id, &logical_key, event.state);
+                INPUT_MANAGER.live_input(&InputEvent::KeyboardLogicalKey {
+                    keyboard: keyboard_id,
+                    key: logical_key,
+                    pressed: event.state.is_pressed(),
+                });
             }
 
             if event.state.is_pressed()
@@ -517,7 +613,10 @@ pub fn insert_raw_window_event(
                     .or_else(|| event.logical_key.to_text())
                 && !text.is_empty()
             {
-                INPUT_MANAGER.keyboard_text(keyboard_id, text);
+                INPUT_MANAGER.live_input(&InputEvent::KeyboardText {
+                    keyboard: keyboard_id,
+                    text: text.to_string(),
+                });
             }
         }
         winit::event::WindowEvent::ModifiersChanged(_) => {}
@@ -528,10 +627,10 @@ pub fn insert_raw_window_event(
         } => {
             profiling::scope!("Cursor moved");
 
-            INPUT_MANAGER.mouse_window_position(
-                MouseId::from_winit(*device_id),
-                Some((window, Vec2::new(position.x as f32, position.y as f32))),
-            );
+            INPUT_MANAGER.live_input(&InputEvent::MouseWindowPosition {
+                mouse: MouseId::from_winit(*device_id),
+                position: Some((window, Vec2::new(position.x as f32, position.y as f32))),
+            });
         }
         winit::event::WindowEvent::CursorEntered { .. } => {
             // Winit also sends a cursor-moved event, so we don't have to explicitely handle this
@@ -539,29 +638,20 @@ pub fn insert_raw_window_event(
         winit::event::WindowEvent::CursorLeft { device_id } => {
             profiling::scope!("Cursor left");
 
-            INPUT_MANAGER.mouse_window_position(MouseId::from_winit(*device_id), None);
+            INPUT_MANAGER.live_input(&InputEvent::MouseWindowPosition {
+                mouse: MouseId::from_winit(*device_id),
+                position: None,
+            });
         }
         winit::event::WindowEvent::MouseWheel {
             device_id, delta, ..
         } => {
             profiling::scope!("MouseWheel");
 
-            match *delta {
-                winit::event::MouseScrollDelta::LineDelta(hor, ver) => {
-                    INPUT_MANAGER
-                        .mouse_scroll(MouseId::from_winit(*device_id), Vec2::new(hor, ver));
-                }
-                winit::event::MouseScrollDelta::PixelDelta(phys_pos) => {
-                    const PIXELS_PER_LINE: f32 = 50.0;
-                    const LINES_PER_PIXEL: f32 = 1.0 / PIXELS_PER_LINE;
-
-                    let hor = (phys_pos.x as f32) * LINES_PER_PIXEL;
-                    let ver = (phys_pos.y as f32) * LINES_PER_PIXEL;
-
-                    INPUT_MANAGER
-                        .mouse_scroll(MouseId::from_winit(*device_id), Vec2::new(hor, ver));
-                }
-            }
+            INPUT_MANAGER.live_input(&InputEvent::MouseScroll {
+                mouse: MouseId::from_winit(*device_id),
+                delta: scroll_delta_to_lines(*delta),
+            });
         }
         winit::event::WindowEvent::MouseInput {
             device_id,
@@ -570,11 +660,11 @@ pub fn insert_raw_window_event(
         } => {
             profiling::scope!("MouseInput");
 
-            INPUT_MANAGER.mouse_button(
-                MouseId::from_winit(*device_id),
-                logical_mouse_to_button_id(*button),
-                *state,
-            );
+            INPUT_MANAGER.live_input(&InputEvent::MouseButton {
+                mouse: MouseId::from_winit(*device_id),
+                button: logical_mouse_to_button_id(*button),
+                pressed: state.is_pressed(),
+            });
         }
         winit::event::WindowEvent::PinchGesture { .. } => {}
         winit::event::WindowEvent::PanGesture { .. } => {}
@@ -594,6 +684,24 @@ pub fn insert_raw_window_event(
     true
 }
 
+/// Maps a winit scroll delta to scrolled lines
+#[expect(
+    clippy::cast_possible_truncation,
+    reason = "WutEngine uses less precision"
+)]
+fn scroll_delta_to_lines(delta: winit::event::MouseScrollDelta) -> Vec2 {
+    const PIXELS_PER_LINE: f32 = 50.0;
+    const LINES_PER_PIXEL: f32 = 1.0 / PIXELS_PER_LINE;
+
+    match delta {
+        winit::event::MouseScrollDelta::LineDelta(hor, ver) => Vec2::new(hor, ver),
+        winit::event::MouseScrollDelta::PixelDelta(phys_pos) => Vec2::new(
+            (phys_pos.x as f32) * LINES_PER_PIXEL,
+            (phys_pos.y as f32) * LINES_PER_PIXEL,
+        ),
+    }
+}
+
 /// Maps a logical winit mouse button to a raw id
 const fn logical_mouse_to_button_id(logical: winit::event::MouseButton) -> winit::event::ButtonId {
     match logical {
@@ -7,6 +7,7 @@ use winit::event::ButtonId;
 use wutengine_math::Vec2;
 
 use crate::WindowIdentifier;
+use crate::recording::DeviceHandle;
 
 use super::INPUT_MANAGER;
 
@@ -26,9 +27,11 @@ pub const BUTTON_BACK: u32 = 3;
 pub const BUTTON_FORWARD: u32 = 4;
 
 /// A mouse input device
-#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, serde::Serialize, serde::Deserialize,
+)]
 #[repr(transparent)]
-pub struct MouseId(winit::event::DeviceId);
+pub struct MouseId(pub(crate) DeviceHandle<winit::event::DeviceId>);
 
 impl MouseId {
     /// Maps a winit device to a [`MouseId`], if the winit device is valid
@@ -37,7 +40,7 @@ impl MouseId {
         if device == winit::event::DeviceId::dummy() {
             None
         } else {
-            Some(Self(device))
+            Some(Self(DeviceHandle::Live(device)))
         }
     }
 }
@@ -0,0 +1,349 @@
+//! Recording and replaying of raw input, for reproducing bugs.
+//!
+//! While recording, all raw input that reaches the input manager is captured per frame, with
+//! the connected devices replaced by stable indices. While replaying, live input is ignored, and
+//! only the recorded input is applied. Both should be started before the first frame, so that no
+//! input state from before the recording is missed
+
+use core::fmt::Display;
+use std::collections::HashMap;
+
+use wutengine_math::Vec2;
+
+use crate::INPUT_MANAGER;
+use crate::WindowIdentifier;
+use crate::gamepad::AxisOrButton;
+use crate::gamepad::GamepadId;
+use crate::keyboard::Key;
+use crate::keyboard::KeyboardId;
+use crate::keyboard::LogicalKey;
+use crate::mouse::MouseId;
+
+/// Identifies an input device that is either connected, or was connected while a recording was made
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub(crate) enum DeviceHandle<T> {
+    /// A connected device
+    Live(T),
+
+    /// A device from a recording, by the order in which it was first used in the recording
+    Replayed(u32),
+}
+
+impl<T: Display> Display for DeviceHandle<T> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Self::Live(device) => device.fmt(f),
+            Self::Replayed(index) => write!(f, "replayed#{index}"),
+        }
+    }
+}
+
+impl<T> serde::Serialize for DeviceHandle<T> {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            Self::Live(_) => Err(serde::ser::Error::custom(
+                "Live input devices must be remapped before they can be serialized",
+            )),
+            Self::Replayed(index) => serializer.serialize_u32(*index),
+        }
+    }
+}
+
+impl<'de, T> serde::Deserialize<'de> for DeviceHandle<T> {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Ok(Self::Replayed(u32::deserialize(deserializer)?))
+    }
+}
+
+/// A single raw input, as applied to the input manager
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub(crate) enum InputEvent {
+    /// A mouse was removed
+    MouseRemoved(MouseId),
+
+    /// A keyboard was removed
+    KeyboardRemoved(KeyboardId),
+
+    /// Physical non-mapped mouse motion
+    MouseMotion {
+        /// The mouse, or [`None`] if unidentified
+        mouse: Option<MouseId>,
+
+        /// The motion, in opaque units
+        delta: Vec2,
+    },
+
+    /// Mouse scrolling, in lines
+    MouseScroll {
+        /// The mouse, or [`None`] if unidentified
+        mouse: Option<MouseId>,
+
+        /// The scrolled lines
+        delta: Vec2,
+    },
+
+    /// A mouse button was pressed or released
+    MouseButton {
+        /// The mouse, or [`None`] if unidentified
+        mouse: Option<MouseId>,
+
+        /// The raw button ID
+        button: u32,
+
+        /// Whether the button was pressed or released
+        pressed: bool,
+    },
+
+    /// The mouse moved within, into or out of a window
+    MouseWindowPosition {
+        /// The mouse, or [`None`] if unidentified
+        mouse: Option<MouseId>,
+
+        /// The window and the position within it, or [`None`] if the mouse left the window
+        position: Option<(WindowIdentifier, Vec2)>,
+    },
+
+    /// A physical key was pressed or released
+    KeyboardKey {
+        /// The keyboard, or [`None`] if unidentified
+        keyboard: Option<KeyboardId>,
+
+        /// The physical key
+        key: Key,
+
+        /// Whether the key was pressed or released
+        pressed: bool,
+    },
+
+    /// A logical key was pressed or released
+    KeyboardLogicalKey {
+        /// The keyboard, or [`None`] if unidentified
+        keyboard: Option<KeyboardId>,
+
+        /// The logical key
+        key: LogicalKey,
+
+        /// Whether the key was pressed or released
+        pressed: bool,
+    },
+
+    /// Text was entered
+    KeyboardText {
+        /// The keyboard, or [`None`] if unidentified
+        keyboard: Option<KeyboardId>,
+
+        /// The entered text. Never empty
+        text: String,
+    },
+
+    /// A gamepad was connected
+    GamepadConnected(GamepadId),
+
+    /// A gamepad was disconnected
+    GamepadDisconnected(GamepadId),
+
+    /// The value of a gamepad axis or button changed
+    GamepadValue {
+        /// The gamepad
+        gamepad: GamepadId,
+
+        /// The axis or button that changed
+        input: AxisOrButton,
+
+        /// The new value
+        value: f32,
+    },
+}
+
+/// Where the input manager takes its input from
+#[derive(Debug, Default)]
+pub(crate) enum InputSource {
+    /// Live input is applied as-is
+    #[default]
+    Live,
+
+    /// Live input is applied, and captured
+    Recording(Recorder),
+
+    /// Live input is ignored
+    Replaying,
+}
+
+/// Captures live input, remapping live devices to replayed devices
+#[derive(Debug, Default)]
+pub(crate) struct Recorder {
+    /// The input captured since the last [`take_recorded_frame`]
+    events: Vec<InputEvent>,
+
+    /// The index of each recorded mouse
+    mice: HashMap<MouseId, u32>,
+
+    /// The index of each recorded keyboard
+    keyboards: HashMap<KeyboardId, u32>,
+
+    /// The index of each recorded gamepad
+    gamepads: HashMap<GamepadId, u32>,
+}
+
+impl Recorder {
+    /// Captures `event`, with its devices remapped
+    pub(crate) fn record(&mut self, event: &InputEvent) {
+        let mut event = event.clone();
+
+        match &mut event {
+            InputEvent::MouseRemoved(mouse) => self.remap_mouse(mouse),
+            InputEvent::KeyboardRemoved(keyboard) => self.remap_keyboard(keyboard),
+            InputEvent::MouseMotion { mouse, .. }
+            | InputEvent::MouseScroll { mouse, .. }
+            | InputEvent::MouseButton { mouse, .. }
+            | InputEvent::MouseWindowPosition { mouse, .. } => {
+                if let Some(mouse) = mouse {
+                    self.remap_mouse(mouse);
+                }
+            }
+            InputEvent::KeyboardKey { keyboard, .. }
+            | InputEvent::KeyboardLogicalKey { keyboard, .. }
+            | InputEvent::KeyboardText { keyboard, .. } => {
+                if let Some(keyboard) = keyboard {
+                    self.remap_keyboard(keyboard);
+                }
+            }
+            InputEvent::GamepadConnected(gamepad)
+            | InputEvent::GamepadDisconnected(gamepad)
+            | InputEvent::GamepadValue { gamepad, .. } => self.remap_gamepad(gamepad),
+        }
+
+        self.events.push(event);
+    }
+
+    /// Replaces a live mouse with its recorded index
+    fn remap_mouse(&mut self, mouse: &mut MouseId) {
+        mouse.0 = DeviceHandle::Replayed(remap(&mut self.mice, *mouse));
+    }
+
+    /// Replaces a live keyboard with its recorded index
+    fn remap_keyboard(&mut self, keyboard: &mut KeyboardId) {
+        keyboard.0 = DeviceHandle::Replayed(remap(&mut self.keyboards, *keyboard));
+    }
+
+    /// Replaces a live gamepad with its recorded index
+    fn remap_gamepad(&mut self, gamepad: &mut GamepadId) {
+        gamepad.0 = DeviceHandle::Replayed(remap(&mut self.gamepads, *gamepad));
+    }
+}
+
+/// Returns the index of `device` in `indices`, assigning it the next index if it has none yet
+#[expect(
+    clippy::cast_possible_truncation,
+    reason = "Not billions of devices in one recording"
+)]
+fn remap<T: Eq + core::hash::Hash>(indices: &mut HashMap<T, u32>, device: T) -> u32 {
+    let next = indices.len() as u32;
+
+    *indices.entry(device).or_insert(next)
+}
+
+/// The raw input applied during a single frame, with the devices replaced by stable indices
+#[derive(Debug, Clone, Default, serde::Serialize, serde::Deserialize)]
+pub struct RecordedInput(Vec<InputEvent>);
+
+impl RecordedInput {
+    /// Returns the number of raw inputs
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    /// Returns `true` if no input was applied during the frame
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+/// Starts capturing all raw input. The gamepads that are already connected are recorded as
+/// connected at the start of the recording
+pub fn start_recording() {
+    let mut recorder = Recorder::default();
+
+    if let crate::DeviceSet::Identified(gamepads) = &*INPUT_MANAGER.gamepads.read().unwrap() {
+        for gamepad in gamepads.keys() {
+            recorder.record(&InputEvent::GamepadConnected(*gamepad));
+        }
+    }
+
+    log::info!("Started recording input");
+
+    *INPUT_MANAGER.source.lock().unwrap() = InputSource::Recording(recorder);
+}
+
+/// Stops capturing raw input. Input captured since the last [`take_recorded_frame`] is discarded
+pub fn stop_recording() {
+    let mut source = INPUT_MANAGER.source.lock().unwrap();
+
+    if matches!(*source, InputSource::Recording(_)) {
+        log::info!("Stopped recording input");
+
+        *source = InputSource::Live;
+    }
+}
+
+/// Returns `true` if raw input is being recorded
+pub fn is_recording() -> bool {
+    matches!(
+        *INPUT_MANAGER.source.lock().unwrap(),
+        InputSource::Recording(_)
+    )
+}
+
+/// Returns the raw input captured since the last call. Should be called by the engine runtime
+/// once per frame, right before the frame's simulation
+pub fn take_recorded_frame() -> RecordedInput {
+    match &mut *INPUT_MANAGER.source.lock().unwrap() {
+        InputSource::Recording(recorder) => RecordedInput(core::mem::take(&mut recorder.events)),
+        _ => RecordedInput::default(),
+    }
+}
+
+/// Starts replaying recorded input. All input state is reset, and live input is ignored until
+/// the replay is stopped
+pub fn start_replay() {
+    log::info!("Started replaying input");
+
+    *INPUT_MANAGER.source.lock().unwrap() = InputSource::Replaying;
+
+    INPUT_MANAGER.reset_devices();
+}
+
+/// Stops replaying recorded input. All input state is reset, and live input is applied again
+pub fn stop_replay() {
+    let mut source = INPUT_MANAGER.source.lock().unwrap();
+
+    if matches!(*source, InputSource::Replaying) {
+        log::info!("Stopped replaying input");
+
+        *source = InputSource::Live;
+
+        drop(source);
+
+        INPUT_MANAGER.reset_devices();
+    }
+}
+
+/// Returns `true` if recorded input is being replayed
+pub fn is_replaying() -> bool {
+    matches!(
+        *INPUT_MANAGER.source.lock().unwrap(),
+        InputSource::Replaying
+    )
+}
+
+/// Applies the recorded input of a single frame. Should be called by the engine runtime once per
+/// replayed frame, right before the frame's simulation
+pub fn replay_frame(input: &RecordedInput) {
+    profiling::function_scope!();
+
+    for event in &input.0 {
+        INPUT_MANAGER.apply(event);
+    }
+}
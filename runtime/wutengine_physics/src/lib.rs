@@ -78,6 +78,8 @@ pub fn update_physics_world(
                     manager: &mut manager_lock,
                 };
 
+                updater.delete_deferred_colliders();
+
                 cb_2d(&mut updater);
             }
         },
@@ -91,6 +93,8 @@ pub fn update_physics_world(
                     manager: &mut manager_lock,
                 };
 
+                updater.delete_deferred_colliders();
+
                 cb_3d(&mut updater);
             }
         },
@@ -103,6 +107,5 @@ trait RapierConversion<T> {
     fn to_rapier(self) -> T;
 
     /// Convert from rapier
-    #[expect(unused, reason = "Will be used once basic physics have been added")]
     fn from_rapier(val: T) -> Self;
 }
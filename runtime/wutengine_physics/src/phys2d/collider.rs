@@ -15,7 +15,7 @@ use super::ColliderPose;
 use crate::RapierConversion;
 
 use rapier2d::prelude::*;
-use wutengine_math::*;
+use wutengine_math::Vec2;
 
 /// Data to a 2D collider
 #[derive(Debug, Clone)]
@@ -23,7 +23,7 @@ pub struct ColliderData2D {
     /// Offset of the collider in local space, with regards to its containing entity
     pub offset: Vec2,
 
-    /// Rotation (in degrees) of the collider in local space in the X axis, with regards to its containing entity
+    /// Rotation (in degrees) of the collider in local space around the Z axis, with regards to its containing entity
     pub rotation: f32,
 
     /// Whether this collider is a trigger
@@ -36,7 +36,38 @@ pub struct ColliderData2D {
 /// Collider-type specific data
 #[derive(Debug, Clone)]
 pub enum ColliderType2D {
-    /// Cube collider
+    /// Box collider
+    Box {
+        /// Width
+        x: f32,
+
+        /// Height
+        y: f32,
+    },
+
+    /// Circle collider
+    Circle {
+        /// Radius
+        radius: f32,
+    },
+
+    /// Capsule collider, along the local Y axis
+    Capsule {
+        /// Height of the rectangular part, excluding the rounded caps
+        height: f32,
+
+        /// Radius
+        radius: f32,
+    },
+
+    /// Convex hull of a set of points, in local space
+    Convex {
+        /// The points. Must contain at least three points that are not on a single line
+        points: Vec<Vec2>,
+    },
+
+    /// Box collider
+    #[deprecated = "renamed to `ColliderType2D::Box`"]
     Cube {
         /// Width
         x: f32,
@@ -48,7 +79,7 @@ pub enum ColliderType2D {
 
 impl Default for ColliderType2D {
     fn default() -> Self {
-        Self::Cube { x: 1.0, y: 1.0 }
+        Self::Box { x: 1.0, y: 1.0 }
     }
 }
 
@@ -64,20 +95,29 @@ impl Default for ColliderData2D {
 }
 
 impl ColliderData2D {
-    /// Create a [`ColliderBuilder`] from this data
-    pub fn create(&self, local_to_world_offset: Vec2, local_to_world_rot: f32) -> ColliderBuilder {
-        let mut builder = match self.type_data {
+    /// Create a [`ColliderBuilder`] from this data, positioned relative to the rigidbody it is attached to.
+    /// Returns [`None`] if the shape is invalid, such as a convex hull of too few points
+    pub fn create(&self) -> Option<ColliderBuilder> {
+        let builder = match &self.type_data {
+            ColliderType2D::Box { x, y } => ColliderBuilder::cuboid(x * 0.5, y * 0.5),
+            #[expect(deprecated, reason = "Still supported until removed")]
             ColliderType2D::Cube { x, y } => ColliderBuilder::cuboid(x * 0.5, y * 0.5),
+            ColliderType2D::Circle { radius } => ColliderBuilder::ball(*radius),
+            ColliderType2D::Capsule { height, radius } => {
+                ColliderBuilder::capsule_y(height * 0.5, *radius)
+            }
+            ColliderType2D::Convex { points } => {
+                let points: Vec<_> = points.iter().map(|point| point.to_rapier()).collect();
+
+                ColliderBuilder::convex_hull(&points)?
+            }
         };
 
-        builder = builder
-            .position(Pose2::new(
-                (self.offset + local_to_world_offset).to_rapier(),
-                (self.rotation + local_to_world_rot).to_radians(),
-            ))
-            .sensor(self.trigger);
-
-        builder
+        Some(
+            builder
+                .position(make_pose((self.offset, self.rotation)))
+                .sensor(self.trigger),
+        )
     }
 }
 
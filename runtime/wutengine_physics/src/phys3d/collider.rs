@@ -3,6 +3,8 @@
 use super::ColliderPose;
 use crate::RapierConversion;
 use rapier3d::prelude::*;
+use wutengine_math::Quat;
+use wutengine_math::Vec3;
 use wutengine_util_macro::unique_id_type64;
 
 unique_id_type64! {
@@ -14,8 +16,149 @@ unique_id_type64! {
 #[derive(Debug, PartialEq, Eq, Hash)]
 pub struct Collider(pub(crate) ColliderId);
 
+/// Data to a 3D collider
+#[derive(Debug, Clone)]
+pub struct ColliderData3D {
+    /// Offset of the collider in local space, with regards to its containing entity
+    pub offset: Vec3,
+
+    /// Rotation of the collider in local space, with regards to its containing entity
+    pub rotation: Quat,
+
+    /// Whether this collider is a trigger
+    pub trigger: bool,
+
+    /// The type-specific data
+    pub type_data: ColliderType3D,
+}
+
+/// Collider-type specific data
+#[derive(Debug, Clone)]
+pub enum ColliderType3D {
+    /// Box collider
+    Box {
+        /// Width
+        x: f32,
+
+        /// Height
+        y: f32,
+
+        /// Depth
+        z: f32,
+    },
+
+    /// Sphere collider
+    Sphere {
+        /// Radius
+        radius: f32,
+    },
+
+    /// Capsule collider, along the local Y axis
+    Capsule {
+        /// Height of the cylindrical part, excluding the rounded caps
+        height: f32,
+
+        /// Radius
+        radius: f32,
+    },
+
+    /// Cylinder collider, along the local Y axis
+    Cylinder {
+        /// Height
+        height: f32,
+
+        /// Radius
+        radius: f32,
+    },
+
+    /// Convex hull of a point cloud, in local space
+    Convex {
+        /// The points. Must contain at least four points that are not on a single plane
+        points: Vec<Vec3>,
+    },
+}
+
+impl Default for ColliderType3D {
+    fn default() -> Self {
+        Self::Box {
+            x: 1.0,
+            y: 1.0,
+            z: 1.0,
+        }
+    }
+}
+
+impl Default for ColliderData3D {
+    fn default() -> Self {
+        Self {
+            offset: Vec3::ZERO,
+            rotation: Quat::IDENTITY,
+            trigger: false,
+            type_data: ColliderType3D::default(),
+        }
+    }
+}
+
+impl ColliderData3D {
+    /// Create a [`ColliderBuilder`] from this data, positioned relative to the rigidbody it is attached to.
+    /// Returns [`None`] if the shape is invalid, such as a convex hull of too few points
+    pub fn create(&self) -> Option<ColliderBuilder> {
+        let builder = match &self.type_data {
+            ColliderType3D::Box { x, y, z } => ColliderBuilder::cuboid(x * 0.5, y * 0.5, z * 0.5),
+            ColliderType3D::Sphere { radius } => ColliderBuilder::ball(*radius),
+            ColliderType3D::Capsule { height, radius } => {
+                ColliderBuilder::capsule_y(height * 0.5, *radius)
+            }
+            ColliderType3D::Cylinder { height, radius } => {
+                ColliderBuilder::cylinder(height * 0.5, *radius)
+            }
+            ColliderType3D::Convex { points } => ColliderBuilder::convex_hull(points)?,
+        };
+
+        Some(
+            builder
+                .position(make_pose((self.offset, self.rotation)))
+                .sensor(self.trigger),
+        )
+    }
+}
+
 /// Create a pose from the given pose data
 pub(crate) fn make_pose(pose: ColliderPose) -> Pose3 {
-    todo!()
-    // Pose3::new(pose.0.to_rapier(), pose.1.to_rapier())
+    Pose3::from_parts(pose.0.to_rapier(), pose.1.to_rapier())
+}
+
+/// Convert a pose back to pose data
+pub(crate) fn from_pose(pose: &Pose3) -> ColliderPose {
+    (
+        Vec3::from_rapier(pose.translation),
+        Quat::from_rapier(pose.rotation),
+    )
+}
+
+#[cfg(test)]
+mod test {
+    use super::from_pose;
+    use super::make_pose;
+    use crate::RapierConversion;
+    use wutengine_math::EulerRot;
+    use wutengine_math::Quat;
+    use wutengine_math::Vec3;
+
+    #[test]
+    fn test_pose_conversion() {
+        let pos = Vec3::new(1.0, -2.0, 3.0);
+        let rot = Quat::from_euler(EulerRot::XYZ, 0.3, -1.2, 2.0);
+
+        let pose = make_pose((pos, rot));
+
+        // Rotating a point through rapier matches rotating it directly
+        let point = Vec3::new(0.5, 0.25, -1.0);
+        let transformed = Vec3::from_rapier(pose.transform_point(point.to_rapier()));
+        assert!(transformed.abs_diff_eq(pos + rot * point, 1e-5));
+
+        let (back_pos, back_rot) = from_pose(&pose);
+        assert!(back_pos.abs_diff_eq(pos, 1e-5));
+        assert!(back_rot.abs_diff_eq(rot, 1e-5));
+    }
 }
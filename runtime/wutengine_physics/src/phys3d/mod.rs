@@ -1,8 +1,10 @@
+use std::sync::Mutex;
 use std::sync::RwLock;
 use std::sync::mpsc::Receiver;
 
 use collider::ColliderId;
 use nohash_hasher::IntMap;
+use rigidbody::RigidbodyId;
 use wutengine_util::InitOnce;
 
 pub mod collider;
@@ -10,10 +12,73 @@ pub mod rigidbody;
 
 use rapier3d::prelude::*;
 
+use crate::PHYSICS_MANAGER;
 use crate::RapierConversion;
 
 pub(crate) type ColliderPose = (wutengine_math::Vec3, wutengine_math::Quat);
 
+/// Colliders that are deleted the next time the physics world is updated
+static DEFERRED_DELETIONS: Mutex<Vec<collider::Collider>> = Mutex::new(Vec::new());
+
+/// Rigidbodies that are deleted the next time the physics world is updated, after the deferred colliders
+static DEFERRED_BODY_DELETIONS: Mutex<Vec<rigidbody::Rigidbody>> = Mutex::new(Vec::new());
+
+/// Deletes an existing collider from the physics world the next time it is updated. For use
+/// where no [`PhysicsWorldUpdater`] is available, such as when the owner of the collider is destroyed
+pub fn delete_collider_deferred(collider: collider::Collider) {
+    DEFERRED_DELETIONS.lock().unwrap().push(collider);
+}
+
+/// Deletes an existing rigidbody from the physics world the next time it is updated, like
+/// [`delete_collider_deferred`]. Colliders attached to it must be deleted separately
+pub fn delete_rigidbody_deferred(rigidbody: rigidbody::Rigidbody) {
+    DEFERRED_BODY_DELETIONS.lock().unwrap().push(rigidbody);
+}
+
+/// Locks the 3D physics world for reading and calls the given callback with a [`PhysicsWorldReader`]
+pub fn read_physics_world(cb: impl FnOnce(&PhysicsWorldReader)) {
+    profiling::function_scope!();
+
+    let manager_lock = PHYSICS_MANAGER.phys3d.read().unwrap();
+
+    cb(&PhysicsWorldReader {
+        manager: &manager_lock,
+    });
+}
+
+/// API entrypoint in order to read the simulated state of the physics world
+#[derive(derive_more::Debug)]
+pub struct PhysicsWorldReader<'a> {
+    /// A reference to the manager
+    #[debug(skip)]
+    manager: &'a PhysicsManager,
+}
+
+impl PhysicsWorldReader<'_> {
+    /// Returns the current pose of a collider in world space. Returns [`None`] if the collider is unknown
+    pub fn collider_pose(&self, collider: &collider::Collider) -> Option<ColliderPose> {
+        let handle = self.manager.collider_map.get(&collider.0)?;
+        let pose = self.manager.collider_set.get(*handle)?.position();
+
+        Some(collider::from_pose(pose))
+    }
+
+    /// Returns the current pose of a rigidbody in world space. Returns [`None`] if the rigidbody is unknown
+    pub fn rigidbody_pose(&self, rigidbody: &rigidbody::Rigidbody) -> Option<ColliderPose> {
+        let handle = self.manager.rigidbody_map.get(&rigidbody.0)?;
+        let pose = self.manager.rigidbody_set.get(*handle)?.position();
+
+        Some(collider::from_pose(pose))
+    }
+
+    /// Returns the number of colliders attached to a rigidbody. Returns [`None`] if the rigidbody is unknown
+    pub fn num_attached_colliders(&self, rigidbody: &rigidbody::Rigidbody) -> Option<usize> {
+        let handle = self.manager.rigidbody_map.get(&rigidbody.0)?;
+
+        Some(self.manager.rigidbody_set.get(*handle)?.colliders().len())
+    }
+}
+
 /// API entrypoint in order to update the physics world synchronously
 #[derive(derive_more::Debug)]
 pub struct PhysicsWorldUpdater<'a> {
@@ -23,8 +88,13 @@ pub struct PhysicsWorldUpdater<'a> {
 }
 
 impl<'a> PhysicsWorldUpdater<'a> {
-    /// Adds a new collider to the world, returning a handle to it
-    pub fn add_collider(&mut self, mut builder: ColliderBuilder) -> collider::Collider {
+    /// Adds a new collider to the world, returning a handle to it. If a parent rigidbody is given,
+    /// the collider moves along with it, and its position is relative to the rigidbody
+    pub fn add_collider(
+        &mut self,
+        mut builder: ColliderBuilder,
+        parent: Option<&rigidbody::Rigidbody>,
+    ) -> collider::Collider {
         let id = ColliderId::new();
         builder = builder.active_events(ActiveEvents::all());
         builder = builder.active_collision_types(ActiveCollisionTypes::all());
@@ -36,7 +106,16 @@ impl<'a> PhysicsWorldUpdater<'a> {
             collider.shape().shape_type()
         );
 
-        let handle = self.manager.collider_set.insert(collider);
+        let parent = parent.map(|parent| *self.manager.rigidbody_map.get(&parent.0).unwrap());
+
+        let handle = match parent {
+            Some(parent) => self.manager.collider_set.insert_with_parent(
+                collider,
+                parent,
+                &mut self.manager.rigidbody_set,
+            ),
+            None => self.manager.collider_set.insert(collider),
+        };
 
         self.manager.collider_map.insert(id, handle);
 
@@ -62,6 +141,82 @@ impl<'a> PhysicsWorldUpdater<'a> {
         assert!(old.is_some(), "Removed collider unknown in rapier");
     }
 
+    /// Deletes the colliders and rigidbodies queued with [`delete_collider_deferred`] and
+    /// [`delete_rigidbody_deferred`]
+    pub(crate) fn delete_deferred_colliders(&mut self) {
+        let deferred = core::mem::take(&mut *DEFERRED_DELETIONS.lock().unwrap());
+
+        for collider in deferred {
+            self.delete_collider(collider);
+        }
+
+        let deferred = core::mem::take(&mut *DEFERRED_BODY_DELETIONS.lock().unwrap());
+
+        for rigidbody in deferred {
+            self.delete_rigidbody(rigidbody);
+        }
+    }
+
+    /// Adds a new rigidbody of the given type to the world at the given pose in world space,
+    /// returning a handle to it. Colliders are attached to it with [`Self::add_collider`]
+    pub fn add_rigidbody(
+        &mut self,
+        body_type: rigidbody::RigidbodyType3D,
+        pose: ColliderPose,
+    ) -> rigidbody::Rigidbody {
+        let id = RigidbodyId::new();
+
+        log::info!("Adding new rigidbody {id} of type {body_type:?}");
+
+        let body = body_type.builder().pose(collider::make_pose(pose)).build();
+        let handle = self.manager.rigidbody_set.insert(body);
+
+        self.manager.rigidbody_map.insert(id, handle);
+
+        rigidbody::Rigidbody(id)
+    }
+
+    /// Deletes an existing rigidbody from the physics world. Colliders still attached to it are
+    /// detached, and stay in the world until deleted themselves
+    #[expect(
+        clippy::needless_pass_by_value,
+        reason = "The handle is invalid once the rigidbody is deleted"
+    )]
+    pub fn delete_rigidbody(&mut self, rigidbody: rigidbody::Rigidbody) {
+        let Some(handle) = self.manager.rigidbody_map.remove(&rigidbody.0) else {
+            log::error!("Tried to delete unknown rigidbody: {}", rigidbody.0);
+            return;
+        };
+
+        log::info!("Deleting rigidbody {}", rigidbody.0);
+
+        let old = self.manager.rigidbody_set.remove(
+            handle,
+            &mut self.manager.island_manager,
+            &mut self.manager.collider_set,
+            &mut self.manager.impulse_joint_set,
+            &mut self.manager.multibody_joint_set,
+            false,
+        );
+
+        assert!(old.is_some(), "Removed rigidbody unknown in rapier");
+    }
+
+    /// Moves an existing rigidbody, and the colliders attached to it, to a new position in world space.
+    /// Kinematic rigidbodies move there during the next step, others are teleported
+    pub fn move_rigidbody(&mut self, rigidbody: &rigidbody::Rigidbody, pose: ColliderPose) {
+        log::debug!("Moving rigidbody {} to {} {}", rigidbody.0, pose.0, pose.1);
+
+        let handle = self.manager.rigidbody_map.get(&rigidbody.0).unwrap();
+        let body = self.manager.rigidbody_set.get_mut(*handle).unwrap();
+
+        if body.is_kinematic() {
+            body.set_next_kinematic_position(collider::make_pose(pose));
+        } else {
+            body.set_position(collider::make_pose(pose), true);
+        }
+    }
+
     /// Moves an existing collider to a new position in world space
     pub fn move_collider(&mut self, collider: &collider::Collider, pose: ColliderPose) {
         log::debug!("Moving collider {} to {} {}", collider.0, pose.0, pose.1);
@@ -82,6 +237,9 @@ pub(crate) struct PhysicsManager {
     /// Gravity vector
     gravity: Vec3,
 
+    /// Map from public rigidbody IDs to rapier IDs
+    rigidbody_map: IntMap<RigidbodyId, RigidBodyHandle>,
+
     /// All rigidbodies
     rigidbody_set: RigidBodySet,
 
@@ -121,6 +279,7 @@ impl PhysicsManager {
     pub(crate) fn new() -> Self {
         PhysicsManager {
             gravity: Vec3::ZERO.with_y(-9.81),
+            rigidbody_map: IntMap::default(),
             rigidbody_set: RigidBodySet::new(),
             collider_map: IntMap::default(),
             collider_set: ColliderSet::new(),
@@ -218,6 +377,18 @@ impl PhysicsResultHandler {
     }
 }
 
+impl RapierConversion<rapier3d::math::Rotation> for wutengine_math::Quat {
+    #[inline]
+    fn to_rapier(self) -> rapier3d::math::Rotation {
+        rapier3d::math::Rotation::from_array(self.to_array())
+    }
+
+    #[inline]
+    fn from_rapier(val: rapier3d::math::Rotation) -> Self {
+        Self::from_array(val.to_array())
+    }
+}
+
 impl RapierConversion<rapier3d::math::Vector> for Vec3 {
     #[inline]
     fn to_rapier(self) -> rapier3d::math::Vector {
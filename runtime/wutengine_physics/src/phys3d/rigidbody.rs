@@ -1,20 +1,38 @@
 //! Rigidbody types and API
 
 use rapier3d::prelude::*;
+use wutengine_util_macro::unique_id_type64;
 
-#[derive(Debug)]
-pub struct Rigidbody {
-    handle: rapier3d::dynamics::RigidBodyHandle,
+unique_id_type64! {
+    /// The handle to a single rigidbody
+    pub(crate) RigidbodyId
 }
 
-impl Rigidbody {
-    pub fn destroy(self) {
-        core::mem::drop(self);
-    }
+/// Handle to a raw rigidbody in the physics world
+#[derive(Debug, PartialEq, Eq, Hash)]
+pub struct Rigidbody(pub(crate) RigidbodyId);
+
+/// How a 3D rigidbody is moved
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum RigidbodyType3D {
+    /// Never moved by the simulation. Can still be moved by hand, by teleporting it
+    #[default]
+    Fixed,
+
+    /// Moved by the simulation, affected by gravity and collisions
+    Dynamic,
+
+    /// Moved by hand only, but pushes dynamic bodies out of its way while moving
+    Kinematic,
 }
 
-impl Drop for Rigidbody {
-    fn drop(&mut self) {
-        todo!()
+impl RigidbodyType3D {
+    /// Create a [`RigidBodyBuilder`] of this type
+    pub(crate) fn builder(self) -> RigidBodyBuilder {
+        match self {
+            Self::Fixed => RigidBodyBuilder::fixed(),
+            Self::Dynamic => RigidBodyBuilder::dynamic(),
+            Self::Kinematic => RigidBodyBuilder::kinematic_position_based(),
+        }
     }
 }
@@ -47,6 +47,9 @@ struct TimeManager {
     /// The amount of frames that have passed in total since application start
     frame_num: AtomicUsize,
 
+    /// The amount of fixed timesteps that have passed in total since application start
+    fixed_step_num: AtomicU64,
+
     /// The current time since application start
     time: AtomicU64,
 
@@ -65,6 +68,10 @@ struct TimeManager {
     /// The configured fixed delta time for this frame, as
     /// used by the physics updates
     fixed_delta: AtomicU64,
+
+    /// The fraction of a fixed timestep left in the accumulator after this frame's fixed updates,
+    /// stored as a bitcast [f64] using [`f64::to_bits`] and [`f64::from_bits`]
+    fixed_alpha: AtomicU64,
 }
 
 impl TimeManager {
@@ -81,6 +88,7 @@ impl TimeManager {
             }),
             time_scale: AtomicU64::new(1.0_f64.to_bits()),
             frame_num: AtomicUsize::new(0),
+            fixed_step_num: AtomicU64::new(0),
 
             time: AtomicU64::new(0),
             unscaled_time: AtomicU64::new(0),
@@ -89,6 +97,7 @@ impl TimeManager {
 
             fixed_time: AtomicU64::new(0),
             fixed_delta: AtomicU64::new(fixed_delta_nanos),
+            fixed_alpha: AtomicU64::new(0.0_f64.to_bits()),
         }
     }
 }
@@ -185,6 +194,12 @@ pub fn frame_num() -> usize {
     TIME_MANAGER.frame_num.load(Ordering::Acquire)
 }
 
+/// Returns the amount of fixed timesteps that have completed since engine startup
+#[inline]
+pub fn fixed_step_num() -> u64 {
+    TIME_MANAGER.fixed_step_num.load(Ordering::Acquire)
+}
+
 /// Returns the time at the beginning of this frame, since engine startup
 ///
 /// Time is returned in nanoseconds
@@ -353,6 +368,25 @@ pub fn fixed_delta() -> f32 {
     (fixed_delta_nanos() as f32) / (NANOS_PER_SECOND as f32)
 }
 
+/// The fraction of a fixed timestep that has passed since the last fixed update of this frame,
+/// in the range `[0, 1)`.
+///
+/// Use to interpolate between the two latest fixed-step states when rendering
+#[inline]
+pub fn fixed_alpha64() -> f64 {
+    f64::from_bits(TIME_MANAGER.fixed_alpha.load(Ordering::Acquire))
+}
+
+/// The fraction of a fixed timestep that has passed since the last fixed update of this frame,
+/// in the range `[0, 1)`.
+///
+/// Use to interpolate between the two latest fixed-step states when rendering
+#[inline]
+#[expect(clippy::cast_possible_truncation, reason = "Inherent to operation")]
+pub fn fixed_alpha() -> f32 {
+    fixed_alpha64() as f32
+}
+
 /// Updates the fixed timestep to the new value, starting next frame
 ///
 /// Value is in nanoseconds (see [`NANOS_PER_SECOND`])
@@ -426,17 +460,6 @@ pub fn update_frame(now: Instant) -> u64 {
 
     let mut time_manager_internal = TIME_MANAGER.internal.lock().unwrap();
 
-    // Set new scale / delta based on the last requested values
-    let (new_time_scale, new_fixed_delta) = update_time_config(&time_manager_internal);
-
-    assert!(
-        new_time_scale.is_sign_positive(),
-        "Time scale must be positive"
-    );
-
-    // Update the frame counter
-    TIME_MANAGER.frame_num.fetch_add(1, Ordering::AcqRel);
-
     // Real-time
     let unclamped_time_delta_nanos = now
         .duration_since(time_manager_internal.prev_frame)
@@ -462,8 +485,42 @@ pub fn update_frame(now: Instant) -> u64 {
         u64::try_from(unclamped_time_delta_nanos).expect("Should fit")
     };
 
+    time_manager_internal.prev_frame = now;
+
+    advance_frame(&mut time_manager_internal, time_since_prev_frame)
+}
+
+/// Called by the engine runtime instead of [`update_frame`] to update the current time by a
+/// given real-time delta, rather than by the measured time. Used to replay recorded frames
+/// deterministically. Returns the amount of fixed updates to run this frame
+///
+/// Value is in nanoseconds (see [`NANOS_PER_SECOND`]), as returned by [`unscaled_delta_nanos`]
+pub fn update_frame_with_delta(unscaled_delta_nanos: u64) -> u64 {
+    profiling::function_scope!();
+
+    let mut time_manager_internal = TIME_MANAGER.internal.lock().unwrap();
+
+    // Keep the measured time in sync, so that switching back to `update_frame` does not cause a jump
+    time_manager_internal.prev_frame = Instant::now();
+
+    advance_frame(&mut time_manager_internal, unscaled_delta_nanos)
+}
+
+/// Advances the frame by `unscaled_delta` nanoseconds of real time. Returns the amount of
+/// fixed updates to run this frame
+fn advance_frame(time_manager_internal: &mut TimeManagerInternal, unscaled_delta: u64) -> u64 {
+    // Set new scale / delta based on the last requested values
+    let (new_time_scale, new_fixed_delta) = update_time_config(time_manager_internal);
+
+    assert!(
+        new_time_scale.is_sign_positive(),
+        "Time scale must be positive"
+    );
+
+    // Update the frame counter
+    TIME_MANAGER.frame_num.fetch_add(1, Ordering::AcqRel);
+
     // Calculate the deltas and the number of fixed steps this frame
-    let unscaled_delta = time_since_prev_frame;
     TIME_MANAGER
         .unscaled_time
         .fetch_add(unscaled_delta, Ordering::Release);
@@ -489,12 +546,16 @@ pub fn update_frame(now: Instant) -> u64 {
 
     time_manager_internal.fixed_accumulator = fixed_accumulator;
 
+    #[expect(clippy::cast_precision_loss, reason = "Inherent to operation")]
+    let fixed_alpha = (fixed_accumulator as f64) / (new_fixed_delta as f64);
+    TIME_MANAGER
+        .fixed_alpha
+        .store(fixed_alpha.to_bits(), Ordering::Release);
+
     // As the new fixed time, we clamp set it to the current "normal" time to prevent
     // them going out of sync due to an accumulation of floating point errors
     TIME_MANAGER.fixed_time.store(new_time, Ordering::Release);
 
-    time_manager_internal.prev_frame = now;
-
     fixed_steps_to_run
 }
 
@@ -518,4 +579,6 @@ pub fn update_fixed() {
     TIME_MANAGER
         .fixed_time
         .fetch_add(scaled_delta, Ordering::AcqRel);
+
+    TIME_MANAGER.fixed_step_num.fetch_add(1, Ordering::AcqRel);
 }